@@ -0,0 +1,51 @@
+//! Times cellular automata generations on a canvas as large as the GUI allows.
+//! Run with `cargo bench --bench cellular_automata`.
+
+use std::time::{Duration, Instant};
+
+use nice_map_generator::{cellular_automata::{Boundary, CARule, Neighborhood, CA}, squares::{CellKind, Square, SquareGrid}};
+
+/// Rows and cols of the benchmark grid, matching the largest canvas the GUI allows.
+const GRID_SIZE: usize = 1000;
+/// Generations run for each timing.
+const GENERATIONS: usize = 4;
+
+/// Makes a GRID_SIZE by GRID_SIZE grid with about half of it walls.
+fn random_grid(seed: u64) -> SquareGrid {
+	let mut squares = Vec::with_capacity(GRID_SIZE * GRID_SIZE);
+	for row in 0..GRID_SIZE {
+		for col in 0..GRID_SIZE {
+			squares.push(Square::new(col, row, 1, 1).with_kind(CellKind::Floor));
+		}//end looping over cols
+	}//end looping over rows
+	let grid = SquareGrid::from_squares(squares, GRID_SIZE, GRID_SIZE).expect("benchmark grid should be valid");
+	let mut ca = CA::default().with_squares(grid);
+	ca.fill_random_walls(0.5, seed, None).expect("benchmark grid should be set");
+	ca.pop_squares().expect("benchmark grid should be set")
+}//end random_grid(seed)
+
+/// Runs GENERATIONS generations of rule on grid with threads threads, returning the result and how long it took.
+fn time_generations(grid: &SquareGrid, rule: &CARule, boundary: Boundary, threads: usize) -> (SquareGrid, Duration) {
+	let mut ca = CA::default().with_rule(rule.clone()).with_boundary(boundary).with_threads(threads).with_squares(grid.clone());
+	let start = Instant::now();
+	for _ in 0..GENERATIONS { ca.run_generation(); }
+	let elapsed = start.elapsed();
+	(ca.pop_squares().expect("squares were set"), elapsed)
+}//end time_generations(grid, rule, boundary, threads)
+
+fn main() {
+	let grid = random_grid(0);
+	let cases = [
+		("moore size 1, wall boundary", CARule::from_thresholds(5, 5, Neighborhood::Moore, 1), Boundary::Wall),
+		("moore size 3, wrap boundary", CARule::from_thresholds(25, 25, Neighborhood::Moore, 3), Boundary::Wrap),
+		("von neumann size 3, mirror boundary", CARule::from_thresholds(12, 12, Neighborhood::VonNeumann, 3), Boundary::Mirror),
+	];
+	let threads = CA::available_threads();
+	println!("{} generations on a {}x{} grid, using 1 or {} threads", GENERATIONS, GRID_SIZE, GRID_SIZE, threads);
+	for (name, rule, boundary) in cases.iter() {
+		let (single_result, single_time) = time_generations(&grid, rule, *boundary, 1);
+		let (multi_result, multi_time) = time_generations(&grid, rule, *boundary, threads);
+		assert!(single_result == multi_result, "{} gave different results depending on thread count", name);
+		println!("{:<38} 1 thread: {:>8.1?}   {} threads: {:>8.1?}", name, single_time, threads, multi_time);
+	}//end timing each case
+}//end main()
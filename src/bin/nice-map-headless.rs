@@ -0,0 +1,290 @@
+use std::{collections::HashMap, path::PathBuf, process};
+
+use nice_map_generator::{cellular_automata::{Boundary, CARule, Neighborhood, CA, CAC}, export::{render::{self, MapTheme, RenderOptions}, svg::{self, ScaleBar, SvgOptions}, text::{self, TextCharMap}, tiled::{self, TiledSettings}, MapKind}, room_growth::{RoomLimits, StartPlacement, CRG, CRGC}, squares::{CellKind, Square, SquareGrid}, zones::{ZoneSettings, ZoneType}};
+
+const USAGE: &str = "Generates maps without opening the GUI, saving each one as an image or Tiled map.
+Exits with an error code if any map couldn't be generated or saved.
+
+Usage:
+  nice-map-headless cave [options]
+  nice-map-headless room [options]
+
+Common options:
+  --seed <u64>              seed for the first map, each later map adds 1 (default 0)
+  --count <n>               number of maps to generate (default 1)
+  --width <n>               width of the map in squares (default 100)
+  --height <n>              height of the map in squares (default 100)
+  --scale <n>               pixel diameter of each square in output images (default 4)
+  --format <fmt>            png, jpeg, bmp, webp, svg, tmj (Tiled JSON), tmx, or txt
+                            (text grid) (default png)
+  --tileset <file>          JSON file with tileset settings for tmj and tmx (default tile IDs)
+  --charmap <file>          JSON file with the characters to use for txt (default characters)
+  --out <dir>               directory to save images in (default .)
+
+Image options:
+  --theme <theme>           player for the built-in player theme, or a JSON theme file
+                            (default editing colors)
+  --grid-lines <bool>       draw a line along the edge of every square (default false)
+
+SVG options:
+  --grid <bool>             draw lines between every square (default false)
+  --scale-bar <n>           draw a scale bar n squares long under the map (default none)
+  --scale-bar-label <text>  text shown next to the scale bar (default \"<n> squares\")
+
+Cave options:
+  --fill <0-1>              fraction of squares starting as wall (default 0.5)
+  --neighborhood-size <n>   CA neighborhood size (default 1)
+  --threshold <n>           CA neighborhood threshold (default 5)
+  --generations <n>         CA generations to run (default 4)
+  --neighborhood <shape>    moore or von-neumann (default moore)
+  --rule <schedule>         B/S rule schedule, such as \"B5678/S45678:4; B5678/S5678:2\",
+                            used instead of --threshold
+  --boundary <policy>       how CA treats cells past the edges: wall, floor, wrap,
+                            or mirror (default wall)
+  --min-region-size <n>     fill in floor regions smaller than this without stairs (default 0)
+  --connect-regions <bool>  carve tunnels so all floor regions connect (default false)
+
+Room options:
+  --rooms <n>               number of room starts to place, or room starts per zone
+                            with --zones (default random)
+  --placement <mode>        how room starts are spread out: random, poisson, or grid
+                            (default random)
+  --spacing <n>             minimum distance between starts for poisson, or cell size
+                            for grid (default 5)
+  --merge-distance <n>      merge room starts closer together than this (default 0)
+  --min-width <n>           merge or remove rooms narrower than this (default 1)
+  --min-height <n>          merge or remove rooms shorter than this (default 1)
+  --max-width <n>           stop rooms growing wider than this (default no limit)
+  --max-height <n>          stop rooms growing taller than this (default no limit)
+  --max-area <n>            stop rooms growing past this many floor squares (default no limit)
+  --max-aspect <n>          stop rooms growing once their long side is this many times their
+                            short side (default no limit)
+  --door-probability <0-1>  base probability used when placing doors without --zones
+                            (default 0.02)
+  --zones <list>            grow zones before rooms, such as \"public, public, private, boss\"
+                            (zone types are public, private, sublevel, and boss)
+  --doors-within <0-1>      chance of extra doors between rooms in the same zone (default 0.3)
+  --doors-between <0-1>     chance of extra doors between rooms in different zones (default 0.05)
+  --loops <n>               add doors until the map has n independent loops (default 0)
+  --secret-doors <0-1>      chance of each door on a shortcut becoming a secret door (default 0)";
+
+fn main() {
+	let args: Vec<String> = std::env::args().skip(1).collect();
+	if args.is_empty() || args.iter().any(|arg| arg == "--help" || arg == "-h") {
+		println!("{}", USAGE);
+		return;
+	}//end if user needs help
+
+	let mode = args[0].clone();
+	let options = match parse_options(&args[1..]) {
+		Ok(options) => options,
+		Err(msg) => exit_with_error(&msg),
+	};
+
+	let seed: u64 = get_option(&options, "seed", 0);
+	let count: u64 = get_option(&options, "count", 1);
+	let width: usize = get_option(&options, "width", 100);
+	let height: usize = get_option(&options, "height", 100);
+	let scale: usize = get_option(&options, "scale", 4);
+	let format = options.get("format").cloned().unwrap_or(String::from("png")).to_lowercase();
+	let out_dir = PathBuf::from(options.get("out").cloned().unwrap_or(String::from(".")));
+	if width < 3 || height < 3 || scale < 1 { exit_with_error("Width and height must be at least 3, and scale must be at least 1."); }
+	if !["png", "jpeg", "bmp", "webp", "svg", "tmj", "tmx", "txt"].contains(&format.as_str()) { exit_with_error(&format!("Unrecognized image format {}.", format)); }
+	let tiled_settings = match options.get("tileset") {
+		Some(tileset_path) => match TiledSettings::load(&PathBuf::from(tileset_path)) {
+			Ok(tiled_settings) => tiled_settings,
+			Err(msg) => exit_with_error(&msg),
+		}, None => TiledSettings::default(),
+	};
+	let char_map = match options.get("charmap") {
+		Some(char_map_path) => match TextCharMap::load(&PathBuf::from(char_map_path)) {
+			Ok(char_map) => char_map,
+			Err(msg) => exit_with_error(&msg),
+		}, None => TextCharMap::default(),
+	};
+	let theme = match options.get("theme").map(|theme| theme.as_str()) {
+		None => MapTheme::editing(),
+		Some("player") => MapTheme::default(),
+		Some(theme_path) => match MapTheme::load(&PathBuf::from(theme_path)) {
+			Ok(theme) => theme,
+			Err(msg) => exit_with_error(&msg),
+		},
+	};
+	let grid_lines = match get_option(&options, "grid-lines", false) { true => Some((96,96,96)), false => None };
+	let render_options = RenderOptions { scale: scale as u32, grid_lines, theme };
+	let scale_bar = options.get("scale-bar").map(|_| {
+		let scale_bar_squares: usize = get_option(&options, "scale-bar", 0);
+		let label = options.get("scale-bar-label").cloned().unwrap_or(format!("{} squares", scale_bar_squares));
+		ScaleBar { squares: scale_bar_squares, label }
+	});
+	let svg_options = SvgOptions { square_size: scale as f64, grid_overlay: get_option(&options, "grid", false), scale_bar, ..SvgOptions::default() };
+	if let Err(err) = std::fs::create_dir_all(&out_dir) { exit_with_error(&format!("Couldn't create output directory {:?}: {}", out_dir, err)); }
+
+	let end_seed = match seed.checked_add(count) {
+		Some(end_seed) => end_seed,
+		None => exit_with_error(&format!("Can't make {} maps starting from seed {}, since the later seeds would be past the largest seed, {}.", count, seed, u64::MAX)),
+	};
+	let mut failures = 0;
+	for map_seed in seed..end_seed {
+		let squares = match mode.as_str() {
+			"cave" => {
+				let fill: f64 = get_option(&options, "fill", 0.5);
+				let neighborhood_size: usize = get_option(&options, "neighborhood-size", 1);
+				let threshold: usize = get_option(&options, "threshold", 5);
+				let generations: usize = get_option(&options, "generations", 4);
+				let neighborhood = match options.get("neighborhood").map(|shape| shape.to_lowercase()).as_deref() {
+					None | Some("moore") => Neighborhood::Moore,
+					Some("von-neumann") => Neighborhood::VonNeumann,
+					Some(shape) => exit_with_error(&format!("Unrecognized neighborhood {}. Expected moore or von-neumann.", shape)),
+				};
+				let boundary = match options.get("boundary").map(|policy| policy.to_lowercase()).as_deref() {
+					None | Some("wall") => Boundary::Wall,
+					Some("floor") => Boundary::Floor,
+					Some("wrap") => Boundary::Wrap,
+					Some("mirror") => Boundary::Mirror,
+					Some(policy) => exit_with_error(&format!("Unrecognized boundary {}. Expected wall, floor, wrap, or mirror.", policy)),
+				};
+				let rule_schedule = match options.get("rule") {
+					Some(schedule) => match CARule::parse_schedule(schedule, neighborhood, neighborhood_size) {
+						Ok(rule_schedule) => rule_schedule,
+						Err(msg) => exit_with_error(&msg),
+					}, None => vec![(CARule::from_thresholds(threshold, threshold, neighborhood, neighborhood_size), 1)],
+				};
+				if !(0.0..=1.0).contains(&fill) { exit_with_error("Fill must be between 0 and 1."); }
+
+				let mut ca_runner = CA::new(neighborhood_size, threshold).with_rule_schedule(rule_schedule).with_boundary(boundary).with_squares(blank_grid(width, height, scale, CAC::Floor.kind()));
+				if let Err(msg) = ca_runner.fill_random_walls(fill, map_seed, None) { exit_with_error(&msg); }
+				for _ in 0..generations { ca_runner.run_generation(); }
+				let min_region_size: usize = get_option(&options, "min-region-size", 0);
+				let connect_regions: bool = get_option(&options, "connect-regions", false);
+				if let Err(msg) = ca_runner.remove_small_regions(min_region_size) { exit_with_error(&msg); }
+				if connect_regions {
+					if let Err(msg) = ca_runner.connect_regions() { eprintln!("Map with seed {} couldn't connect regions: {}", map_seed, msg); failures += 1; }
+				}//end if we should connect regions
+				ca_runner.pop_squares()
+			},
+			"room" => {
+				let rooms: Option<usize> = options.get("rooms").map(|_| get_option(&options, "rooms", 0));
+				let door_probability: f64 = get_option(&options, "door-probability", 0.02);
+				let spacing: f64 = get_option(&options, "spacing", 5.0);
+				let placement = match options.get("placement").map(|mode| mode.as_str()) {
+					None | Some("random") => StartPlacement::Random,
+					Some("poisson") => StartPlacement::PoissonDisk { min_distance: spacing },
+					Some("grid") => StartPlacement::JitteredGrid { spacing: spacing as usize },
+					Some(other) => exit_with_error(&format!("Unrecognized placement {}. Expected random, poisson, or grid.", other)),
+				};
+				let merge_distance: f64 = get_option(&options, "merge-distance", 0.0);
+				let room_limits = RoomLimits {
+					min_width: get_option(&options, "min-width", 1),
+					min_height: get_option(&options, "min-height", 1),
+					max_width: options.get("max-width").map(|_| get_option(&options, "max-width", 0)),
+					max_height: options.get("max-height").map(|_| get_option(&options, "max-height", 0)),
+					max_area: options.get("max-area").map(|_| get_option(&options, "max-area", 0)),
+					max_aspect_ratio: options.get("max-aspect").map(|_| get_option(&options, "max-aspect", 0.0)),
+				};
+				let loops: usize = get_option(&options, "loops", 0);
+				let secret_door_chance: f64 = get_option(&options, "secret-doors", 0.0);
+
+				let zone_types: Option<Vec<ZoneType>> = options.get("zones").map(|list| ZoneType::parse_list(list).unwrap_or_else(|msg| exit_with_error(&msg)));
+
+				let mut rg = CRG::default().with_seed(map_seed).with_room_limits(room_limits).with_squares(blank_grid(width, height, scale, CRGC::Empty.kind()));
+				let proc_res = match zone_types {
+					Some(zone_types) => {
+						let zone_defaults = ZoneSettings::default();
+						let zone_settings = ZoneSettings {
+							zone_types,
+							rooms_per_zone: rooms,
+							doors_within: get_option(&options, "doors-within", zone_defaults.doors_within),
+							doors_between: get_option(&options, "doors-between", zone_defaults.doors_between),
+						};
+						rg.generate_zoned_rooms(&zone_settings)
+					}, None => rg.add_spaced_room_starts(rooms, placement)
+						.and_then(|_| rg.merge_close_room_starts(merge_distance))
+						.and_then(|_| rg.grow_rooms_from_starts())
+						.and_then(|_| rg.place_random_doors(door_probability)),
+				};
+				let proc_res = match loops > 0 {
+					true => proc_res.and_then(|_| rg.inject_loops(loops)),
+					false => proc_res,
+				};
+				let proc_res = match secret_door_chance > 0.0 {
+					true => proc_res.and_then(|_| rg.place_secret_doors(secret_door_chance)),
+					false => proc_res,
+				};
+				match proc_res {
+					Ok(_) => rg.pop_squares(),
+					Err(msg) => { eprintln!("Map with seed {} failed: {}", map_seed, msg); failures += 1; None },
+				}//end matching result of room generation
+			},
+			_ => exit_with_error(&format!("Unrecognized mode {}. Expected cave or room.", mode)),
+		};
+
+		if let Some(squares) = squares {
+			let path = out_dir.join(format!("{}_{}.{}", mode, map_seed, format));
+			let map_kind = match mode.as_str() { "cave" => MapKind::Cave, _ => MapKind::Room };
+			let save_res = match format.as_str() {
+				"tmj" | "tmx" => tiled::save_tiled_map(&squares, map_kind, &[], &tiled_settings, &path),
+				"txt" => text::save_text_map(&squares, map_kind, &[], &char_map, &path),
+				"svg" => svg::save_svg_map(&squares, map_kind, &svg_options, &path),
+				_ => render::render_map(&squares, map_kind, &render_options).save(&path).map_err(|err| err.to_string()),
+			};
+			match save_res {
+				Ok(_) => println!("Saved {}", path.display()),
+				Err(err) => { eprintln!("Couldn't save {}: {}", path.display(), err); failures += 1; },
+			}//end matching whether we saved the map
+		}//end if we generated the map
+	}//end generating each map
+
+	if failures > 0 {
+		eprintln!("{} of {} maps had problems.", failures, count);
+		process::exit(1);
+	}//end if any map failed
+}//end main method
+
+/// Parses arguments of the form --name value into a map from name to value.
+fn parse_options(args: &[String]) -> Result<HashMap<String, String>, String> {
+	let mut options = HashMap::new();
+	let mut arg_iter = args.iter();
+	while let Some(arg) = arg_iter.next() {
+		match arg.strip_prefix("--") {
+			Some(name) => {
+				match arg_iter.next() {
+					Some(value) => { options.insert(name.to_string(), value.clone()); },
+					None => return Err(format!("Option --{} needs a value.", name)),
+				}//end matching whether option has a value
+			}, None => return Err(format!("Unexpected argument {}.", arg)),
+		}//end matching whether this is an option name
+	}//end looping over arguments
+	Ok(options)
+}//end parse_options(args)
+
+/// Gets the option with name parsed as T, or default if it wasn't given.
+/// Exits the program if the option can't be parsed.
+fn get_option<T: std::str::FromStr>(options: &HashMap<String, String>, name: &str, default: T) -> T {
+	match options.get(name) {
+		Some(value) => match value.parse() {
+			Ok(parsed) => parsed,
+			Err(_) => exit_with_error(&format!("Couldn't understand value {} for --{}.", value, name)),
+		}, None => default,
+	}//end matching whether option was given
+}//end get_option(options, name, default)
+
+/// Creates a SquareGrid of width by height squares, each scale pixels across and set to kind.
+fn blank_grid(width: usize, height: usize, scale: usize, kind: CellKind) -> SquareGrid {
+	let mut squares = Vec::new();
+	for row in 0..height {
+		for col in 0..width {
+			squares.push(Square::new(col * scale, row * scale, scale, scale).with_kind(kind));
+		}//end looping over cols
+	}//end looping over rows
+	match SquareGrid::from_squares(squares, width * scale, height * scale) {
+		Ok(squares) => squares,
+		Err(err_info) => exit_with_error(&err_info.1),
+	}//end matching whether we made the grid
+}//end blank_grid(width, height, scale, kind)
+
+/// Prints msg and usage information, then exits with an error code.
+fn exit_with_error(msg: &str) -> ! {
+	eprintln!("{}\n\n{}", msg, USAGE);
+	process::exit(1);
+}//end exit_with_error(msg)
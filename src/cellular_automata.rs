@@ -1,4 +1,9 @@
-use crate::squares::SquareGrid;
+use std::collections::VecDeque;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::squares::{CellKind, Palette, SquareGrid, SquareRegion};
 
 #[derive(Debug, PartialEq, Eq, Clone, Ord, PartialOrd, Hash)]
 pub struct CA {
@@ -9,12 +14,19 @@ pub struct CA {
 	pub neighborhood_threshold: usize,
 	generations_so_far: usize,
 	squares: Option<SquareGrid>,
+	/// Rules to use, each paired with the number of generations to use it for.  
+	/// If empty, [neighborhood_size] and [neighborhood_threshold] are used instead.
+	rule_schedule: Vec<(CARule, usize)>,
+	/// How cells outside the edges of squares are treated when counting neighbors.
+	boundary: Boundary,
+	/// Number of threads neighbors are counted with. 1 counts everything on the calling thread.
+	threads: usize,
 }//end struct CA
 
 impl Default for CA {
 	/// default size 1 and threshold 5
 	fn default() -> Self {
-		Self { neighborhood_size: 1, neighborhood_threshold: 5, generations_so_far: 0, squares: None }
+		Self { neighborhood_size: 1, neighborhood_threshold: 5, generations_so_far: 0, squares: None, rule_schedule: Vec::new(), boundary: Boundary::Wall, threads: CA::available_threads() }
 	}//end default()
 }//end impl Deafult default for CA
 
@@ -22,10 +34,47 @@ impl Default for CA {
 impl CA {
 	/// Instantiates object with custom parameters, 
 	/// though no squares.
-	pub fn new(neighborhood_size: usize, neighborhood_threshold: usize) -> CA {CA{neighborhood_size, neighborhood_threshold, generations_so_far: 0, squares: None }}
+	pub fn new(neighborhood_size: usize, neighborhood_threshold: usize) -> CA {CA{neighborhood_size, neighborhood_threshold, generations_so_far: 0, squares: None, rule_schedule: Vec::new(), boundary: Boundary::Wall, threads: CA::available_threads() }}
 
 	pub fn generations_so_far(&self) -> &usize {&self.generations_so_far}
 	pub fn squares(&self) -> &Option<SquareGrid> {&self.squares}
+	pub fn rule_schedule(&self) -> &Vec<(CARule, usize)> {&self.rule_schedule}
+	pub fn boundary(&self) -> &Boundary {&self.boundary}
+	pub fn threads(&self) -> &usize {&self.threads}
+
+	/// Sets how cells outside the edges of squares are treated when counting neighbors.
+	pub fn set_boundary(&mut self, boundary: Boundary) {self.boundary = boundary;}
+	/// Convenience function for setting boundary.
+	pub fn with_boundary(mut self, boundary: Boundary) -> CA {self.boundary = boundary; self}
+
+	/// Sets how many threads neighbors are counted with. Results are the same no matter how many are used.  
+	/// Defaults to the number of threads the system can run at once. 0 is treated as 1.
+	pub fn set_threads(&mut self, threads: usize) {self.threads = threads.max(1);}
+	/// Convenience function for setting threads.
+	pub fn with_threads(mut self, threads: usize) -> CA {self.set_threads(threads); self}
+	/// Number of threads the system can run at once, or 1 if that can't be found.
+	pub fn available_threads() -> usize {std::thread::available_parallelism().map(|threads| threads.get()).unwrap_or(1)}
+
+	/// Sets the rules to run, each paired with the number of generations to run it for.  
+	/// Once the schedule runs out, the last rule keeps being used.
+	pub fn set_rule_schedule(&mut self, rule_schedule: Vec<(CARule, usize)>) {self.rule_schedule = rule_schedule;}
+	/// Convenience function for setting rule schedule.
+	pub fn with_rule_schedule(mut self, rule_schedule: Vec<(CARule, usize)>) -> CA {self.rule_schedule = rule_schedule; self}
+	/// Convenience function for using a single rule for every generation.
+	pub fn with_rule(mut self, rule: CARule) -> CA {self.rule_schedule = vec![(rule, 1)]; self}
+
+	/// Gets the rule that the next generation will use, based on [generations_so_far].
+	pub fn current_rule(&self) -> CARule {
+		let mut generations_left = self.generations_so_far;
+		for (rule, generations) in self.rule_schedule.iter() {
+			if generations_left < *generations { return rule.clone(); }
+			generations_left -= generations;
+		}//end finding rule for this generation
+		match self.rule_schedule.last() {
+			Some((rule, _)) => rule.clone(),
+			None => CARule::from_thresholds(self.neighborhood_threshold, self.neighborhood_threshold, Neighborhood::Moore, self.neighborhood_size),
+		}//end matching whether we ran out of schedule or never had one
+	}//end current_rule(self)
 
 	/// Sets this object to use a particular collection 
 	/// of squares, resetting [generations_so_far].
@@ -37,135 +86,691 @@ impl CA {
 	/// Returns SquareGrid object with ownership, setting self.sqaures to None
 	pub fn pop_squares(&mut self) -> Option<SquareGrid> { let squares = self.squares.clone(); self.squares = None; squares }
 
-	/// Counts the number of neighbors with target CA Color/Classification.  
-	/// It might be noted that this count does not include the value at [row], [col].  
-	/// - If bounds row/column's neighborhood is partially cut off by bounds, 
-	/// then count will be a bit higher. In cases of something going wrong 
-	/// despite bounds checking, a message might be println!()-ed, but 
-	/// the function shouldn't panic or return None.  
-	/// - The function should only return None if self.squares is None.  
-	/// - Since it is assumed that this function will be called with target == CAC::Wall,  
-	/// it is set up so that outermost cells are likely to stay walls.
-	fn neighbor_count(&self, row: usize, col: usize, target: CAC) -> Option<usize> {
-		match &self.squares {
+	/// Randomly sets each wall or floor square to wall with probability wall_fraction, or floor otherwise.  
+	/// The same seed on the same squares will always give the same result.  
+	/// - Stairs and other squares are never changed.  
+	/// - If mask is given, it should be parallel to squares, and squares where mask is true are left untouched.  
+	/// - Resets [generations_so_far], since the squares are starting over.
+	/// 
+	/// Returns Err if squares is None, wall_fraction isn't between 0 and 1, or mask is the wrong length.
+	pub fn fill_random_walls(&mut self, wall_fraction: f64, seed: u64, mask: Option<&Vec<bool>>) -> Result<(), String> {
+		if !(0.0..=1.0).contains(&wall_fraction) { return Err(format!("Wall fraction {} should be between 0 and 1.", wall_fraction)); }
+		match &mut self.squares {
 			Some(squares) => {
-				let mut target_count = 0;
-		
-				// get bounds for valid row and cols, doing bounds checking
-				// squares out of bounds are counted as targets
-				let low_row: usize;
-				if row < self.neighborhood_size { low_row = 0; target_count += 3; } else { low_row = row - self.neighborhood_size; }
-				let low_col: usize;
-				if col < self.neighborhood_size { low_col = 0; target_count += 3; } else { low_col = col - self.neighborhood_size; }
-				let hih_row: usize;
-				if row + self.neighborhood_size > *squares.rows() - 1 { hih_row = *squares.rows() - 1; target_count += 3; }
-				else { hih_row = row + self.neighborhood_size; }
-				let hih_col: usize;// = col + self.neighborhood_size;
-				if col + self.neighborhood_size > *squares.cols() - 1 { hih_col = *squares.cols() - 1; target_count += 3; }
-				else { hih_col = col + self.neighborhood_size; }
-
-				// Count number of neighbors which CAC::classify(color) == target
-				for col_idx in low_col..(hih_col + 1) {
-					for row_idx in low_row..(hih_row + 1) {
-						if row_idx == row && col_idx == col { continue; }
-						match squares.get(&row_idx, &col_idx) {
-							Some(square) => if CAC::classify(*square.color()) == target { target_count += 1; },
-							None => println!("Somehow we had an invalid index when counting neighborhoods even though we pre-checked that."),}
-					}//end looping over row indices in neighborhood of row, col
-				}//end looping over col indices in neighborhood of row, col
-		
-				Some(target_count)
-			}, None => {None}}
-	}//end neighbor_count
+				let num_squares = *squares.rows() * *squares.cols();
+				if let Some(mask) = mask {
+					if mask.len() != num_squares { return Err(format!("Mask has {} entries, but there are {} squares.", mask.len(), num_squares)); }
+				}//end if mask given
+
+				let mut rng = StdRng::seed_from_u64(seed);
+				for (i, square) in squares.iter_mut().enumerate() {
+					// draw for every square so that masked squares don't shift the rest of the pattern
+					let becomes_wall = rng.gen_bool(wall_fraction);
+					let masked = match mask { Some(mask) => mask[i], None => false };
+					let this_cac = CAC::from_kind(*square.kind());
+					if masked || (this_cac != CAC::Floor && this_cac != CAC::Wall) { continue; }
+					if becomes_wall { square.set_kind(CAC::Wall.kind()); }
+					else { square.set_kind(CAC::Floor.kind()); }
+				}//end randomly setting each square
+
+				self.generations_so_far = 0;
+				Ok(())
+			}, None => Err(String::from("No Squares Set")),
+		}//end matching whether we have squares
+	}//end fill_random_walls(self, wall_fraction, seed, mask)
 
 	/// Creates a vector parallel to squares.  
 	/// Needless to say, this function returns None if self.squares is None.  
-	/// This vector provides the number of neighbors with target CAC of every square at once.  
-	/// This is achieved through repeated calls to [neighbor_count()]
-	fn all_neighbor_count(&self, target: CAC) -> Option<Vec<usize>> {
-		match &self.squares {
-			Some(squares) => {
-				let mut neighbor_count_all = Vec::new();
-
-				for row_idx in 0..*squares.rows() {
-					for col_idx in 0..*squares.cols() {
-						match self.neighbor_count(row_idx, col_idx, target) {
-							Some(count) => neighbor_count_all.push(count),
-							None => return None, }
-					}//end looping over cols
-				}//end looping over rows
-
-				Some(neighbor_count_all)
-			}, None => None,}
+	/// This vector provides the number of neighbors with target CAC of every square at once,
+	/// not counting the square itself.  
+	/// - The shape and size of the neighborhood come from rule.  
+	/// - Neighbors outside the edges of squares are handled according to [boundary].  
+	/// - Counts are read from a summed-area table of target squares, padded out by the neighborhood size,
+	///   so Moore neighborhoods take four lookups per square, and von Neumann neighborhoods take four per row of the diamond.  
+	/// - Rows are split between [threads] threads.
+	fn all_neighbor_count(&self, target: CAC, rule: &CARule) -> Option<Vec<usize>> {
+		let squares = self.squares.as_ref()?;
+		let rows = *squares.rows();
+		let cols = *squares.cols();
+		let size = rule.neighborhood_size;
+		if rows == 0 || cols == 0 { return Some(Vec::new()); }
+
+		let is_target: Vec<bool> = squares.iter().map(|square| CAC::from_kind(*square.kind()) == target).collect();
+		let outside_is_target = match self.boundary { Boundary::Floor => target == CAC::Floor, _ => target == CAC::Wall };
+		// which row or col of squares each padded row or col stands for, if any
+		let padded_rows: Vec<Option<usize>> = (0..rows + 2 * size).map(|idx| self.boundary.resolve(idx as isize - size as isize, rows as isize)).collect();
+		let padded_cols: Vec<Option<usize>> = (0..cols + 2 * size).map(|idx| self.boundary.resolve(idx as isize - size as isize, cols as isize)).collect();
+
+		// table[(r * table_cols) + c] is the number of target squares above and left of padded row r, col c
+		let table_cols = padded_cols.len() + 1;
+		let mut table = vec![0usize; (padded_rows.len() + 1) * table_cols];
+		for (r, padded_row) in padded_rows.iter().enumerate() {
+			let mut row_sum = 0;
+			for (c, padded_col) in padded_cols.iter().enumerate() {
+				let this_is_target = match padded_row.zip(*padded_col) {
+					Some((row, col)) => is_target[row * cols + col],
+					None => outside_is_target,
+				};
+				if this_is_target { row_sum += 1; }
+				table[(r + 1) * table_cols + c + 1] = table[r * table_cols + c + 1] + row_sum;
+			}//end looping over padded cols
+		}//end looping over padded rows
+		// number of target squares in padded rows top..=bottom and cols left..=right
+		let area_count = |top: usize, left: usize, bottom: usize, right: usize| {
+			table[(bottom + 1) * table_cols + right + 1] + table[top * table_cols + left] - table[top * table_cols + right + 1] - table[(bottom + 1) * table_cols + left]
+		};
+
+		// the center of the neighborhood for row, col is at padded row + size, col + size
+		let count_row = |row: usize, counts: &mut [usize]| {
+			for (col, count) in counts.iter_mut().enumerate() {
+				let in_neighborhood = match rule.neighborhood {
+					Neighborhood::Moore => area_count(row, col, row + 2 * size, col + 2 * size),
+					Neighborhood::VonNeumann => (0..=2 * size).map(|row_offset| {
+						let half_width = size - row_offset.abs_diff(size);
+						area_count(row + row_offset, col + size - half_width, row + row_offset, col + size + half_width)
+					}).sum(),
+				};
+				*count = in_neighborhood - is_target[row * cols + col] as usize;
+			}//end counting each square in row
+		};
+
+		let mut neighbor_count_all = vec![0; rows * cols];
+		let rows_per_thread = rows.div_ceil(self.threads.max(1));
+		if rows_per_thread >= rows {
+			for (row, counts) in neighbor_count_all.chunks_mut(cols).enumerate() { count_row(row, counts); }
+		} else {
+			std::thread::scope(|scope| {
+				for (chunk_idx, chunk) in neighbor_count_all.chunks_mut(rows_per_thread * cols).enumerate() {
+					let count_row = &count_row;
+					scope.spawn(move || {
+						for (row_in_chunk, counts) in chunk.chunks_mut(cols).enumerate() { count_row(chunk_idx * rows_per_thread + row_in_chunk, counts); }
+					});
+				}//end giving each thread a chunk of rows
+			});
+		}//end else we're splitting rows between threads
+
+		Some(neighbor_count_all)
 	}//end all_neighbor_count
 
 	/// Runs a single generation of cellular automata with the given settings.  
-	/// Changes will be made to self.squares to reflect these changes.
+	/// Changes will be made to self.squares to reflect these changes.  
+	/// The rule used is determined by [current_rule()].
 	/// 
 	/// If squares is None, no changes will be made, and false will be returned.
 	pub fn run_generation(&mut self) -> bool {
-		match &self.squares {
+		let rule = self.current_rule();
+		// get neighbor count for each square
+		let parallel_neighbor_count = match self.all_neighbor_count(CAC::Wall, &rule) {
+			Some(parallel_neighbor_count) => parallel_neighbor_count,
+			None => return false,
+		};
+		match &mut self.squares {
 			Some(squares) => {
-				// get neighbor count for each square
-				match self.all_neighbor_count(CAC::Wall) {
-					Some(parallel_neighbor_count) => {
-						let mut cur_squares = squares.clone();
-
-						for (i, square) in cur_squares.iter_mut().enumerate() {
-							let this_neighbor_count = parallel_neighbor_count[i];
-							// set color based on neighbor count
-							let this_cac = CAC::classify(*square.color());
-							if this_cac == CAC::Floor || this_cac == CAC::Wall {
-								if this_neighbor_count >= self.neighborhood_threshold {
-									square.set_color(CAC::Wall.color());
-								} else { square.set_color(CAC::Floor.color()); }
-							}//end if this is already a wall or floor
-						}//end changing color of each square based on neighbor count
-
-						// variable maintenance
-						self.squares = Some(cur_squares);
-						self.generations_so_far += 1;
-
-						true
-					}, None => false, }
+				for (square, this_neighbor_count) in squares.iter_mut().zip(parallel_neighbor_count) {
+					// set kind based on neighbor count
+					let this_cac = CAC::from_kind(*square.kind());
+					if this_cac == CAC::Floor || this_cac == CAC::Wall {
+						if rule.next_is_wall(this_cac == CAC::Wall, this_neighbor_count) {
+							square.set_kind(CAC::Wall.kind());
+						} else { square.set_kind(CAC::Floor.kind()); }
+					}//end if this is already a wall or floor
+				}//end changing kind of each square based on neighbor count
+
+				// variable maintenance
+				self.generations_so_far += 1;
+
+				true
 			}, None => false,}
 	}//end run_generation
+
+	/// Finds each group of orthogonally connected floor and stairs squares.  
+	/// Returns None if squares is None.
+	pub fn floor_regions(&self) -> Option<Vec<SquareRegion>> {
+		self.squares.as_ref().map(CA::find_floor_regions)
+	}//end floor_regions(self)
+
+	/// Turns every floor region with fewer than min_size squares into walls.  
+	/// Regions containing stairs are never removed, since they need to stay reachable.  
+	/// Returns the number of regions removed, or Err if squares is None.
+	pub fn remove_small_regions(&mut self, min_size: usize) -> Result<usize, String> {
+		match &mut self.squares {
+			Some(squares) => {
+				let mut regions_removed = 0;
+				for region in CA::find_floor_regions(squares) {
+					if region.len() >= min_size { continue; }
+					let has_stairs = region.iter().any(|(row, col)| CA::is_class(squares, *row, *col, CAC::Stairs));
+					if has_stairs { continue; }
+					for (row, col) in region {
+						if let Some(square) = squares.get_mut(&row, &col) { square.set_kind(CAC::Wall.kind()); }
+					}//end filling in region with walls
+					regions_removed += 1;
+				}//end checking size of each region
+				Ok(regions_removed)
+			}, None => Err(String::from("No Squares Set")),
+		}//end matching whether we have squares
+	}//end remove_small_regions(self, min_size)
+
+	/// Carves tunnels of floor through walls until all floor regions are connected,
+	/// so that every region can reach every stairs square.  
+	/// A single breadth first search spreads out from the region with stairs, or the largest region if there are no stairs.
+	/// Each time it reaches another region, a tunnel is carved back along the way it came, and the search carries on from
+	/// that region too, so each region is joined by the shortest tunnel from the regions joined before it.
+	/// This visits each square once, no matter how many regions there are.  
+	/// Returns the number of walls carved away.
+	/// 
+	/// Returns Err if squares is None, or if some region is sealed off by squares that aren't walls or floor.
+	/// In that case, no changes will be made.
+	pub fn connect_regions(&mut self) -> Result<usize, String> {
+		match &self.squares {
+			Some(squares) => {
+				let mut squares = squares.clone();
+				let rows = *squares.rows();
+				let cols = *squares.cols();
+				let regions = CA::find_floor_regions(&squares);
+				if regions.len() <= 1 { return Ok(0); }
+
+				// pick the region everything else should connect to
+				let main_region = match regions.iter().position(|region| region.iter().any(|(row, col)| CA::is_class(&squares, *row, *col, CAC::Stairs))) {
+					Some(region_index) => region_index,
+					None => (0..regions.len()).max_by_key(|region_index| regions[*region_index].len()).unwrap_or(0),
+				};
+				let mut region_of_square = vec![None; rows * cols];
+				for (region_index, region) in regions.iter().enumerate() {
+					for (row, col) in region { region_of_square[row * cols + col] = Some(region_index); }
+				}//end recording region of each square
+
+				// squares already connected to main region have no came_from, so tunnels stop once they reach them
+				let mut came_from: Vec<Option<usize>> = vec![None; rows * cols];
+				let mut visited = vec![false; rows * cols];
+				let mut connected = vec![false; regions.len()];
+				let mut to_visit = VecDeque::new();
+				let mut join_region = |region_index: usize, visited: &mut Vec<bool>, to_visit: &mut VecDeque<(usize,usize)>| {
+					connected[region_index] = true;
+					for (row, col) in regions[region_index].iter() {
+						visited[row * cols + col] = true;
+						to_visit.push_back((*row, *col));
+					}//end continuing search from each square in region
+				};
+				join_region(main_region, &mut visited, &mut to_visit);
+
+				let mut squares_carved = 0;
+				while let Some((row, col)) = to_visit.pop_front() {
+					for (row_a, col_a) in squares.orthogonal_neighbors(row, col) {
+						let idx = row_a * cols + col_a;
+						if visited[idx] { continue; }
+						let cac = match squares.get(&row_a, &col_a) { Some(square) => CAC::from_kind(*square.kind()), None => CAC::Other };
+						if cac == CAC::Other { continue; }
+						visited[idx] = true;
+						match region_of_square[idx] {
+							Some(region_index) => {
+								// carve out walls on the path back to the regions already joined
+								let mut path_idx = Some(row * cols + col);
+								while let Some(cur_idx) = path_idx {
+									if let Some(square) = squares.get_mut(&(cur_idx / cols), &(cur_idx % cols)) {
+										if CAC::from_kind(*square.kind()) == CAC::Wall { square.set_kind(CAC::Floor.kind()); squares_carved += 1; }
+									}//end if we can get the square on the path
+									path_idx = came_from[cur_idx].take();
+								}//end following path back to joined regions
+								join_region(region_index, &mut visited, &mut to_visit);
+							}, None => {
+								came_from[idx] = Some(row * cols + col);
+								to_visit.push_back((row_a, col_a));
+							},
+						}//end matching whether we reached another region
+					}//end looping over neighbors
+				}//end searching out from joined regions
+
+				let unconnected = connected.iter().filter(|connected| !**connected).count();
+				if unconnected > 0 { return Err(format!("Couldn't connect {} of {} cave regions, since they're sealed off by squares that aren't wall or floor.", unconnected, regions.len())); }
+				self.squares = Some(squares);
+				Ok(squares_carved)
+			}, None => Err(String::from("No Squares Set")),
+		}//end matching whether we have squares
+	}//end connect_regions(self)
+
+	/// Finds each group of orthogonally connected floor and stairs squares in squares.
+	fn find_floor_regions(squares: &SquareGrid) -> Vec<SquareRegion> {
+		let cols = *squares.cols();
+		let mut visited = vec![false; *squares.rows() * cols];
+		let mut regions = Vec::new();
+		for start in 0..visited.len() {
+			if visited[start] || !CA::is_walkable(squares, start / cols, start % cols) { continue; }
+			visited[start] = true;
+			let mut region = Vec::new();
+			let mut to_visit = vec![(start / cols, start % cols)];
+			while let Some((row, col)) = to_visit.pop() {
+				region.push((row, col));
+				for (row_a, col_a) in squares.orthogonal_neighbors(row, col) {
+					if !visited[row_a * cols + col_a] && CA::is_walkable(squares, row_a, col_a) {
+						visited[row_a * cols + col_a] = true;
+						to_visit.push((row_a, col_a));
+					}//end if we should add this neighbor to region
+				}//end looping over neighbors
+			}//end looping while we have squares to visit
+			regions.push(region);
+		}//end looping over possible start squares
+		regions
+	}//end find_floor_regions(squares)
+
+	/// Whether the square at row, col is floor or stairs.
+	fn is_walkable(squares: &SquareGrid, row: usize, col: usize) -> bool {
+		CA::is_class(squares, row, col, CAC::Floor) || CA::is_class(squares, row, col, CAC::Stairs)
+	}//end is_walkable(squares, row, col)
+
+	/// Whether the square at row, col has classification target.
+	fn is_class(squares: &SquareGrid, row: usize, col: usize, target: CAC) -> bool {
+		match squares.get(&row, &col) {
+			Some(square) => CAC::from_kind(*square.kind()) == target,
+			None => false,
+		}//end matching whether square exists
+	}//end is_class(squares, row, col, target)
 }//end impl for CA
 
-/// CA Color/Classification
+/// Shape of the neighborhood considered around each cell.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum Neighborhood {
+	/// Cells within neighborhood size both vertically and horizontally, making a square.
+	Moore,
+	/// Cells within neighborhood size steps up, down, left, or right, making a diamond.
+	VonNeumann,
+}//end enum Neighborhood
+
+impl Neighborhood {
+	/// Whether a cell row_dist rows and col_dist cols away is within a neighborhood of this shape and size.
+	pub fn contains(&self, row_dist: usize, col_dist: usize, size: usize) -> bool {
+		match self {
+			Neighborhood::Moore => row_dist <= size && col_dist <= size,
+			Neighborhood::VonNeumann => row_dist + col_dist <= size,
+		}//end matching neighborhood shape
+	}//end contains(self, row_dist, col_dist, size)
+
+	/// Number of cells in a neighborhood of this shape and size, not counting the center.
+	pub fn max_neighbors(&self, size: usize) -> usize {
+		match self {
+			Neighborhood::Moore => (2 * size + 1) * (2 * size + 1) - 1,
+			Neighborhood::VonNeumann => 2 * size * (size + 1),
+		}//end matching neighborhood shape
+	}//end max_neighbors(self, size)
+}//end impl for Neighborhood
+
+/// How cells outside the edges of the grid are treated when counting neighbors.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum Boundary {
+	/// Cells outside the grid count as walls, so edges tend to stay walls.
+	Wall,
+	/// Cells outside the grid count as floor, for open-edged areas.
+	Floor,
+	/// The grid wraps around to the opposite edge, for maps tiled side by side.
+	Wrap,
+	/// The grid is reflected across its edges.
+	Mirror,
+}//end enum Boundary
+
+impl Boundary {
+	/// Converts a possibly out of bounds index along a dimension of length len into an index within the grid.  
+	/// Returns None if the index is out of bounds and this boundary doesn't map it back into the grid.
+	pub fn resolve(&self, idx: isize, len: isize) -> Option<usize> {
+		if idx >= 0 && idx < len { return Some(idx as usize); }
+		match self {
+			Boundary::Wall | Boundary::Floor => None,
+			Boundary::Wrap => Some(idx.rem_euclid(len) as usize),
+			Boundary::Mirror => {
+				// reflection repeats every 2 * len, with the edge cell included in the reflection
+				let reflected = idx.rem_euclid(2 * len);
+				if reflected < len { Some(reflected as usize) }
+				else { Some((2 * len - 1 - reflected) as usize) }
+			},
+		}//end matching boundary
+	}//end resolve(self, idx, len)
+}//end impl for Boundary
+
+/// Birth/Survival rule for a single CA generation.  
+/// A floor cell becomes a wall if its wall neighbor count is in [birth],
+/// and a wall cell stays a wall if its wall neighbor count is in [survival].
+#[derive(Debug, PartialEq, Eq, Clone, Ord, PartialOrd, Hash)]
+pub struct CARule {
+	/// Wall neighbor counts that turn a floor cell into a wall.
+	pub birth: Vec<usize>,
+	/// Wall neighbor counts that let a wall cell stay a wall.
+	pub survival: Vec<usize>,
+	pub neighborhood: Neighborhood,
+	/// Cells within this distance are considered for neighborhood.
+	pub neighborhood_size: usize,
+}//end struct CARule
+
+impl CARule {
+	pub fn new(birth: Vec<usize>, survival: Vec<usize>, neighborhood: Neighborhood, neighborhood_size: usize) -> CARule {
+		CARule { birth, survival, neighborhood, neighborhood_size }
+	}//end new()
+
+	/// Creates a rule where floor becomes wall with at least birth_threshold wall neighbors,
+	/// and wall stays wall with at least survival_threshold wall neighbors.
+	pub fn from_thresholds(birth_threshold: usize, survival_threshold: usize, neighborhood: Neighborhood, neighborhood_size: usize) -> CARule {
+		let max_neighbors = neighborhood.max_neighbors(neighborhood_size);
+		CARule {
+			birth: (birth_threshold..=max_neighbors).collect(),
+			survival: (survival_threshold..=max_neighbors).collect(),
+			neighborhood,
+			neighborhood_size,
+		}//end struct construction
+	}//end from_thresholds()
+
+	/// Parses a rule in B/S notation, such as B5678/S45678.  
+	/// Counts can be listed as single digits, or separated by commas
+	/// if any are larger than 9, such as B13,14,15/S12,13,14,15.
+	pub fn from_bs_notation(notation: &str, neighborhood: Neighborhood, neighborhood_size: usize) -> Result<CARule, String> {
+		let notation = notation.trim().to_uppercase();
+		let mut birth = None;
+		let mut survival = None;
+		for part in notation.split('/') {
+			let part = part.trim();
+			if let Some(counts) = part.strip_prefix('B') { birth = Some(CARule::parse_counts(counts)?); }
+			else if let Some(counts) = part.strip_prefix('S') { survival = Some(CARule::parse_counts(counts)?); }
+			else { return Err(format!("Couldn't understand {} in rule {}. Expected something like B5678/S45678.", part, notation)); }
+		}//end parsing each part of notation
+		match (birth, survival) {
+			(Some(birth), Some(survival)) => Ok(CARule { birth, survival, neighborhood, neighborhood_size }),
+			_ => Err(format!("Rule {} needs both a B and an S part, like B5678/S45678.", notation)),
+		}//end matching whether we got both parts
+	}//end from_bs_notation()
+
+	/// Parses a list of counts, either single digits or comma separated.
+	fn parse_counts(counts: &str) -> Result<Vec<usize>, String> {
+		let counts = counts.trim();
+		if counts.contains(',') {
+			counts.split(',')
+				.map(|count| count.trim().parse::<usize>().map_err(|_| format!("Couldn't understand count {}.", count)))
+				.collect()
+		} else {
+			counts.chars()
+				.map(|count| count.to_digit(10).map(|count| count as usize).ok_or(format!("Couldn't understand count {}.", count)))
+				.collect()
+		}//end else counts are single digits
+	}//end parse_counts(counts)
+
+	/// Gets this rule in B/S notation, using commas if any count is larger than 9.
+	pub fn bs_notation(&self) -> String {
+		let use_commas = self.birth.iter().chain(self.survival.iter()).any(|count| *count > 9);
+		let separator = if use_commas {","} else {""};
+		let birth: Vec<String> = self.birth.iter().map(|count| count.to_string()).collect();
+		let survival: Vec<String> = self.survival.iter().map(|count| count.to_string()).collect();
+		format!("B{}/S{}", birth.join(separator), survival.join(separator))
+	}//end bs_notation(self)
+
+	/// Parses a schedule of rules separated by semicolons, each optionally followed
+	/// by a colon and the number of generations to use it for, such as B5678/S45678:4; B5678/S5678:2.  
+	/// Rules without a generation count are used for 1 generation.
+	pub fn parse_schedule(schedule: &str, neighborhood: Neighborhood, neighborhood_size: usize) -> Result<Vec<(CARule, usize)>, String> {
+		let mut rule_schedule = Vec::new();
+		for step in schedule.split(';').filter(|step| !step.trim().is_empty()) {
+			let (notation, generations) = match step.split_once(':') {
+				Some((notation, generations)) => match generations.trim().parse::<usize>() {
+					Ok(generations) => (notation, generations),
+					Err(_) => return Err(format!("Couldn't understand generation count {} in schedule.", generations)),
+				}, None => (step, 1),
+			};
+			rule_schedule.push((CARule::from_bs_notation(notation, neighborhood, neighborhood_size)?, generations));
+		}//end parsing each step of schedule
+		Ok(rule_schedule)
+	}//end parse_schedule()
+
+	/// Determines whether a cell should be a wall in the next generation.  
+	/// Counts higher than the neighborhood allows are treated as the maximum.
+	pub fn next_is_wall(&self, is_wall: bool, wall_count: usize) -> bool {
+		let wall_count = wall_count.min(self.neighborhood.max_neighbors(self.neighborhood_size));
+		if is_wall { self.survival.contains(&wall_count) }
+		else { self.birth.contains(&wall_count) }
+	}//end next_is_wall(self, is_wall, wall_count)
+}//end impl for CARule
+
+/// CA Classification, read from the [CellKind] of each square.
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Ord, PartialOrd, Hash)]
 pub enum CAC {
 	Wall,
 	Floor,
 	Stairs,
+	/// Anything CA doesn't change, such as doors or empty space.
 	Other,
 }//end enum CAC
 
 #[allow(dead_code)]
 impl CAC {
-	pub fn classify(color: (u8,u8,u8)) -> CAC {
-		match color {
-			(0,0,0) => CAC::Wall,
-			(255,255,255) => CAC::Floor,
-			(0,255,0) => CAC::Stairs,
+	pub fn from_kind(kind: CellKind) -> CAC {
+		match kind {
+			CellKind::Wall => CAC::Wall,
+			CellKind::Floor => CAC::Floor,
+			CellKind::Stairs => CAC::Stairs,
 			_ => CAC::Other,
-		}//end matching color to CAC
-	}//end classify
+		}//end matching kind to CAC
+	}//end from_kind
 
-	pub fn color(&self) -> (u8,u8,u8) {
+	/// Gets the kind of square written for this classification.
+	pub fn kind(&self) -> CellKind {
 		match self {
-			CAC::Wall => (0,0,0),
-			CAC::Floor => (255,255,255),
-			CAC::Stairs => (0,255,0),
-			CAC::Other => (50,50,50),
-		}//end matching CAC to color
-	}//end color()
+			CAC::Wall => CellKind::Wall,
+			CAC::Floor => CellKind::Floor,
+			CAC::Stairs => CellKind::Stairs,
+			CAC::Other => CellKind::Other((50,50,50)),
+		}//end matching CAC to kind
+	}//end kind()
 
+	/// Colors of the kinds CA works with, as drawn on the cave canvas.
 	pub fn colors_vec() -> Vec<(u8,u8,u8)> {
-		let mut color_vec = Vec::new();
-		color_vec.push((0,0,0));
-		color_vec.push((255,255,255));
-		color_vec.push((0,255,0));
-		color_vec
+		let palette = Palette::cave();
+		vec![palette.wall, palette.floor, palette.stairs]
 	}
-}//end impl for CAC
\ No newline at end of file
+}//end impl for CAC
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::export::{text::{from_text, TextCharMap}, MapKind};
+
+	/// Builds a CA over a text cave map, using the default cave characters.
+	fn cave_ca(text: &str, boundary: Boundary) -> CA {
+		let squares = from_text(text, MapKind::Cave, &TextCharMap::default(), 1).unwrap();
+		CA::new(1, 5).with_boundary(boundary).with_squares(squares)
+	}//end cave_ca(text, boundary)
+
+	/// Gets the wall neighbor count of every square in ca, in row-major order.
+	fn wall_counts(ca: &CA, neighborhood: Neighborhood, size: usize) -> Vec<usize> {
+		ca.all_neighbor_count(CAC::Wall, &CARule::from_thresholds(5, 4, neighborhood, size)).unwrap()
+	}//end wall_counts(ca, neighborhood, size)
+
+	#[test]
+	fn neighbor_counts_follow_boundary() {
+		let corner_wall = "#..\n...\n...";
+		// counts for squares (0,0), (0,1), (1,1), and (2,2)
+		let picked = |counts: Vec<usize>| vec![counts[0], counts[1], counts[4], counts[8]];
+		assert_eq!(picked(wall_counts(&cave_ca(corner_wall, Boundary::Wall), Neighborhood::Moore, 1)), vec![5, 4, 1, 5]);
+		assert_eq!(picked(wall_counts(&cave_ca(corner_wall, Boundary::Floor), Neighborhood::Moore, 1)), vec![0, 1, 1, 0]);
+		assert_eq!(picked(wall_counts(&cave_ca(corner_wall, Boundary::Wrap), Neighborhood::Moore, 1)), vec![0, 1, 1, 1]);
+		assert_eq!(picked(wall_counts(&cave_ca(corner_wall, Boundary::Mirror), Neighborhood::Moore, 1)), vec![3, 2, 1, 0]);
+		assert_eq!(picked(wall_counts(&cave_ca(corner_wall, Boundary::Wall), Neighborhood::VonNeumann, 1)), vec![2, 2, 0, 2]);
+		assert_eq!(picked(wall_counts(&cave_ca(corner_wall, Boundary::Mirror), Neighborhood::VonNeumann, 1)), vec![2, 1, 0, 0]);
+	}//end neighbor_counts_follow_boundary()
+
+	/// Counts target neighbors of every square one at a time, resolving each neighbor through the boundary.
+	fn naive_counts(ca: &CA, target: CAC, neighborhood: Neighborhood, size: usize) -> Vec<usize> {
+		let squares = ca.squares.as_ref().unwrap();
+		let (rows, cols) = (*squares.rows() as isize, *squares.cols() as isize);
+		let outside_is_target = match ca.boundary { Boundary::Floor => target == CAC::Floor, _ => target == CAC::Wall };
+		let reach = size as isize;
+		let mut counts = Vec::new();
+		for row in 0..rows {
+			for col in 0..cols {
+				let mut count = 0;
+				for row_off in -reach..=reach {
+					for col_off in -reach..=reach {
+						if (row_off, col_off) == (0, 0) { continue; }
+						if !neighborhood.contains(row_off.unsigned_abs(), col_off.unsigned_abs(), size) { continue; }
+						let neighbor = ca.boundary.resolve(row + row_off, rows).zip(ca.boundary.resolve(col + col_off, cols));
+						let is_target = match neighbor {
+							Some((n_row, n_col)) => CAC::from_kind(*squares.get(&n_row, &n_col).unwrap().kind()) == target,
+							None => outside_is_target,
+						};
+						if is_target { count += 1; }
+					}//end looping over col offsets
+				}//end looping over row offsets
+				counts.push(count);
+			}//end looping over cols
+		}//end looping over rows
+		counts
+	}//end naive_counts(ca, target, neighborhood, size)
+
+	#[test]
+	fn neighbor_counts_match_naive_counts() {
+		let boundaries = [Boundary::Wall, Boundary::Floor, Boundary::Wrap, Boundary::Mirror];
+		let neighborhoods = [Neighborhood::Moore, Neighborhood::VonNeumann];
+		// the 2x3 grid is smaller than the larger neighborhoods, so they reach past the far edge too
+		let grids = [(7, 9, 1), (6, 5, 2), (2, 3, 3)];
+		for (rows, cols, seed) in grids {
+			let floor_text = vec![".".repeat(cols); rows].join("\n");
+			for boundary in boundaries {
+				let mut ca = cave_ca(&floor_text, boundary);
+				ca.fill_random_walls(0.45, seed, None).unwrap();
+				for neighborhood in neighborhoods {
+					for size in 1..=3 {
+						for target in [CAC::Wall, CAC::Floor] {
+							let rule = CARule::from_thresholds(5, 4, neighborhood, size);
+							let expected = naive_counts(&ca, target, neighborhood, size);
+							let single = ca.clone().with_threads(1).all_neighbor_count(target, &rule).unwrap();
+							let multi = ca.clone().with_threads(4).all_neighbor_count(target, &rule).unwrap();
+							assert_eq!(single, expected, "{:?} {:?} size {} on {}x{}", boundary, neighborhood, size, rows, cols);
+							assert_eq!(multi, expected, "{:?} {:?} size {} on {}x{} with threads", boundary, neighborhood, size, rows, cols);
+						}//end looping over targets
+					}//end looping over sizes
+				}//end looping over neighborhoods
+			}//end looping over boundaries
+		}//end looping over grids
+	}//end neighbor_counts_match_naive_counts()
+
+	#[test]
+	fn boundary_resolves_out_of_bounds_indices() {
+		assert_eq!(Boundary::Wall.resolve(-1, 5), None);
+		assert_eq!(Boundary::Floor.resolve(5, 5), None);
+		assert_eq!(Boundary::Wrap.resolve(-1, 5), Some(4));
+		assert_eq!(Boundary::Wrap.resolve(6, 5), Some(1));
+		assert_eq!(Boundary::Mirror.resolve(-1, 5), Some(0));
+		assert_eq!(Boundary::Mirror.resolve(-2, 5), Some(1));
+		assert_eq!(Boundary::Mirror.resolve(5, 5), Some(4));
+		assert_eq!(Boundary::Mirror.resolve(3, 5), Some(3));
+	}//end boundary_resolves_out_of_bounds_indices()
+
+	#[test]
+	fn bs_notation_parses_digits_and_commas() {
+		let rule = CARule::from_bs_notation("B5678/S45678", Neighborhood::Moore, 1).unwrap();
+		assert_eq!(rule.birth, vec![5,6,7,8]);
+		assert_eq!(rule.survival, vec![4,5,6,7,8]);
+		let rule = CARule::from_bs_notation(" b13, 14/s12,13 ", Neighborhood::Moore, 2).unwrap();
+		assert_eq!(rule.birth, vec![13,14]);
+		assert_eq!(rule.survival, vec![12,13]);
+		assert_eq!(rule.bs_notation(), "B13,14/S12,13");
+	}//end bs_notation_parses_digits_and_commas()
+
+	#[test]
+	fn bs_notation_round_trips() {
+		for notation in ["B5678/S45678", "B3/S23", "B/S", "B10,11/S9,10"] {
+			let rule = CARule::from_bs_notation(notation, Neighborhood::Moore, 2).unwrap();
+			assert_eq!(rule.bs_notation(), notation);
+		}//end looping over notations
+	}//end bs_notation_round_trips()
+
+	#[test]
+	fn bs_notation_rejects_bad_input() {
+		for notation in ["", "B5678", "S45678", "B5x/S4", "X5/S4", "B1,a/S2", "B5678/S45678/Q1"] {
+			assert!(CARule::from_bs_notation(notation, Neighborhood::Moore, 1).is_err(), "{} should be rejected", notation);
+		}//end looping over bad notations
+	}//end bs_notation_rejects_bad_input()
+
+	#[test]
+	fn schedule_parses_generation_counts() {
+		let schedule = CARule::parse_schedule("B5678/S45678:4; B5678/S5678", Neighborhood::Moore, 1).unwrap();
+		assert_eq!(schedule.len(), 2);
+		assert_eq!(schedule[0].1, 4);
+		assert_eq!(schedule[1].0.bs_notation(), "B5678/S5678");
+		assert_eq!(schedule[1].1, 1);
+		assert!(CARule::parse_schedule("B5678/S45678:four", Neighborhood::Moore, 1).is_err());
+		assert!(CARule::parse_schedule("B5678/S45678; nonsense", Neighborhood::Moore, 1).is_err());
+	}//end schedule_parses_generation_counts()
+
+	#[test]
+	fn random_fill_is_seeded() {
+		let floor_text = vec![".".repeat(20); 10].join("\n");
+		let fill = |seed: u64, wall_fraction: f64| {
+			let mut ca = cave_ca(&floor_text, Boundary::Wall);
+			ca.fill_random_walls(wall_fraction, seed, None).unwrap();
+			ca.pop_squares().unwrap()
+		};
+		assert_eq!(fill(9, 0.5), fill(9, 0.5));
+		assert_ne!(fill(9, 0.5), fill(10, 0.5));
+		assert!(fill(9, 1.0).iter().all(|square| *square.kind() == CellKind::Wall));
+		assert!(fill(9, 0.0).iter().all(|square| *square.kind() == CellKind::Floor));
+		assert!(cave_ca(&floor_text, Boundary::Wall).fill_random_walls(1.5, 9, None).is_err());
+	}//end random_fill_is_seeded()
+
+	#[test]
+	fn random_fill_leaves_stairs_and_mask_alone() {
+		let text = vec![".>.#.".repeat(4); 6].join("\n");
+		let original = cave_ca(&text, Boundary::Wall).pop_squares().unwrap();
+		// mask off the left half of the grid
+		let mask: Vec<bool> = (0..original.iter().count()).map(|idx| idx % 20 < 10).collect();
+		for seed in 0..5 {
+			let mut ca = cave_ca(&text, Boundary::Wall);
+			ca.fill_random_walls(0.5, seed, Some(&mask)).unwrap();
+			let filled = ca.pop_squares().unwrap();
+			for (idx, (before, after)) in original.iter().zip(filled.iter()).enumerate() {
+				if mask[idx] || *before.kind() == CellKind::Stairs { assert_eq!(before, after, "square {} changed with seed {}", idx, seed); }
+			}//end checking each square
+			assert_ne!(original, filled);
+		}//end looping over seeds
+		assert!(cave_ca(&text, Boundary::Wall).fill_random_walls(0.5, 0, Some(&vec![false; 3])).is_err());
+	}//end random_fill_leaves_stairs_and_mask_alone()
+
+	#[test]
+	fn small_regions_become_walls() {
+		// regions of 1, 4, and 8 squares, plus a region of 1 square with stairs in it
+		let mut ca = cave_ca("\
+>#..#...
+##..#...
+######..
+.#######", Boundary::Wall);
+		assert_eq!(ca.floor_regions().unwrap().len(), 4);
+		assert_eq!(ca.remove_small_regions(5).unwrap(), 2);
+		let mut sizes: Vec<usize> = ca.floor_regions().unwrap().iter().map(|region| region.len()).collect();
+		sizes.sort_unstable();
+		assert_eq!(sizes, vec![1, 8]);
+		assert_eq!(ca.remove_small_regions(8).unwrap(), 0);
+		assert_eq!(ca.remove_small_regions(9).unwrap(), 1);
+		assert_eq!(ca.floor_regions().unwrap().len(), 1);
+		assert!(CA::default().remove_small_regions(1).is_err());
+	}//end small_regions_become_walls()
+
+	#[test]
+	fn connect_regions_leaves_one_region() {
+		let floor_text = vec![".".repeat(25); 15].join("\n");
+		for seed in 0..5 {
+			let mut ca = cave_ca(&floor_text, Boundary::Wall);
+			ca.fill_random_walls(0.55, seed, None).unwrap();
+			assert!(ca.floor_regions().unwrap().len() > 1, "seed {} only made one region to start with", seed);
+			let walls_before = ca.get_squares().as_ref().unwrap().iter().filter(|square| *square.kind() == CellKind::Wall).count();
+			let carved = ca.connect_regions().unwrap();
+			let walls_after = ca.get_squares().as_ref().unwrap().iter().filter(|square| *square.kind() == CellKind::Wall).count();
+			assert_eq!(ca.floor_regions().unwrap().len(), 1, "regions still apart with seed {}", seed);
+			assert_eq!(walls_before - walls_after, carved);
+			assert_eq!(ca.connect_regions().unwrap(), 0);
+		}//end looping over seeds
+	}//end connect_regions_leaves_one_region()
+
+	#[test]
+	fn connect_regions_errs_when_sealed_off() {
+		let text = "\
+..#..
+..#??
+###?.
+..??.";
+		let mut ca = cave_ca(text, Boundary::Wall);
+		assert!(ca.connect_regions().is_err());
+		assert_eq!(ca.pop_squares(), cave_ca(text, Boundary::Wall).pop_squares());
+	}//end connect_regions_errs_when_sealed_off()
+}//end mod tests
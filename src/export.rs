@@ -1,21 +1,54 @@
 use image::RgbImage;
 
-use crate::squares::SquareGrid;
+use crate::squares::{Palette, SquareGrid};
 
-pub fn generate_img_from_map(map: &SquareGrid) -> RgbImage {
+pub mod tiled;
+
+pub mod text;
+
+pub mod svg;
+
+pub mod render;
+
+/// Which kind of map a [SquareGrid] holds, which decides how the colors of its squares are classified.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum MapKind {
+	/// Classified with [crate::cellular_automata::CAC].
+	Cave,
+	/// Classified with [crate::room_growth::CRGC].
+	Room,
+}//end enum MapKind
+
+impl MapKind {
+	/// The colors squares are drawn with on the canvas for this kind of map.
+	pub fn palette(&self) -> Palette {
+		match self {
+			MapKind::Cave => Palette::cave(),
+			MapKind::Room => Palette::room(),
+		}//end matching kind of map
+	}//end palette(self)
+}//end impl for MapKind
+
+/// Draws each square with its color from palette into an image the size of the canvas the squares came from.  
+/// Use [render::render_map()] for images meant to be shown to players.
+pub fn generate_img_from_map(map: &SquareGrid, palette: &Palette) -> RgbImage {
 	let mut img = RgbImage::new(*map.img_width() as u32, *map.img_height() as u32);
 	for square in map.iter() {
 		for x in *square.x()..(square.x()+square.width()) {
 			for y in *square.y()..(square.y()+square.height()) {
-				match img.get_pixel_mut_checked(x as u32, y as u32) {
-					Some(pixel) => {
-						let color = [square.color().0, square.color().1, square.color().2];
-						*pixel = image::Rgb(color);
-					},
-					None => println!("Couldn't get pixel at x:{}, y:{} ?", x, y),
-				}//end matching img
+				// squares in a SquareGrid are always within the image bounds
+				if let Some(pixel) = img.get_pixel_mut_checked(x as u32, y as u32) {
+					let color = palette.color(*square.kind());
+					let color = [color.0, color.1, color.2];
+					*pixel = image::Rgb(color);
+				}//end if pixel is within image
 			}//end looping through y values in square
 		}//end looping through x values in square
 	}//end copying pixel information from each square into img
 	img
-}//end generate_img_from_map(map)
+}//end generate_img_from_map(map, palette)
+
+/// Escapes characters that can't appear as-is in XML attribute values or text.
+fn xml_escape(text: &str) -> String {
+	text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}//end xml_escape(text)
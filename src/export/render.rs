@@ -0,0 +1,245 @@
+use std::{collections::HashMap, path::Path};
+
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+
+use crate::{cellular_automata::CAC, room_growth::CRGC, squares::{CellKind, Palette, SquareGrid}};
+
+use super::MapKind;
+
+/// How a class of square is drawn.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub enum ThemeFill {
+	/// Every pixel of the square is this color.
+	Color((u8,u8,u8)),
+	/// Path to an image that is stretched over each square.
+	/// Paths are relative to the theme file, and the image is loaded by [MapTheme::load()].
+	Texture(String),
+	/// The color the square would have on the canvas for its kind of map.
+	Original,
+}//end enum ThemeFill
+
+/// Fills used for each class of cave square.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaveTheme {
+	pub wall: ThemeFill,
+	pub floor: ThemeFill,
+	pub stairs: ThemeFill,
+	pub other: ThemeFill,
+}//end struct CaveTheme
+
+impl Default for CaveTheme {
+	fn default() -> Self {
+		Self {
+			wall: ThemeFill::Color((62,54,48)),
+			floor: ThemeFill::Color((232,222,196)),
+			stairs: ThemeFill::Color((196,150,64)),
+			other: ThemeFill::Color((128,128,128)),
+		}//end struct construction
+	}//end default()
+}//end impl Default for CaveTheme
+
+/// Fills used for each class of room square.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomTheme {
+	pub wall: ThemeFill,
+	pub floor: ThemeFill,
+	pub stairs: ThemeFill,
+	pub other: ThemeFill,
+	pub door: ThemeFill,
+	pub secret_door: ThemeFill,
+	pub empty: ThemeFill,
+	pub room_start: ThemeFill,
+}//end struct RoomTheme
+
+impl Default for RoomTheme {
+	fn default() -> Self {
+		Self {
+			wall: ThemeFill::Color((52,52,58)),
+			floor: ThemeFill::Color((232,222,196)),
+			stairs: ThemeFill::Color((196,150,64)),
+			other: ThemeFill::Color((128,128,128)),
+			door: ThemeFill::Color((140,92,48)),
+			// players shouldn't be able to spot secret doors on the map
+			secret_door: ThemeFill::Color((52,52,58)),
+			empty: ThemeFill::Color((28,28,30)),
+			room_start: ThemeFill::Color((232,222,196)),
+		}//end struct construction
+	}//end default()
+}//end impl Default for RoomTheme
+
+/// Which fill each class of square is drawn with.
+/// Can be saved as JSON, so one file can be reused for every map exported in the same style.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MapTheme {
+	pub cave: CaveTheme,
+	pub room: RoomTheme,
+	/// Images for each [ThemeFill::Texture], by path.
+	#[serde(skip)]
+	textures: HashMap<String, RgbImage>,
+}//end struct MapTheme
+
+impl MapTheme {
+	/// Creates a theme using the same colors squares have while editing.
+	/// Squares of other kinds are drawn with the color they were drawn with on the canvas.
+	pub fn editing() -> MapTheme {
+		let cave = Palette::cave();
+		let room = Palette::room();
+		MapTheme {
+			cave: CaveTheme {
+				wall: ThemeFill::Color(cave.wall),
+				floor: ThemeFill::Color(cave.floor),
+				stairs: ThemeFill::Color(cave.stairs),
+				other: ThemeFill::Original,
+			},
+			room: RoomTheme {
+				wall: ThemeFill::Color(room.wall),
+				floor: ThemeFill::Color(room.floor),
+				stairs: ThemeFill::Color(room.stairs),
+				other: ThemeFill::Original,
+				door: ThemeFill::Color(room.door),
+				secret_door: ThemeFill::Color(room.secret_door),
+				empty: ThemeFill::Color(room.empty),
+				room_start: ThemeFill::Color(room.room_start),
+			},
+			textures: HashMap::new(),
+		}//end struct construction
+	}//end editing()
+
+	/// Loads a theme from a JSON file at path, along with any textures it uses.
+	/// Anything missing from the file keeps its default.
+	pub fn load(path: &Path) -> Result<MapTheme, String> {
+		let text = std::fs::read_to_string(path).map_err(|err| format!("Couldn't read theme from {}: {}", path.display(), err))?;
+		let mut theme: MapTheme = serde_json::from_str(&text).map_err(|err| format!("Couldn't read theme from {}: {}", path.display(), err))?;
+		let theme_dir = path.parent().unwrap_or(Path::new(""));
+		let texture_paths: Vec<String> = theme.fills().into_iter().filter_map(|fill| match fill {
+			ThemeFill::Texture(texture_path) => Some(texture_path.clone()),
+			ThemeFill::Color(_) | ThemeFill::Original => None,
+		}).collect();
+		for texture_path in texture_paths {
+			if theme.textures.contains_key(&texture_path) { continue; }
+			let texture = image::open(theme_dir.join(&texture_path)).map_err(|err| format!("Couldn't load texture {} for theme {}: {}", texture_path, path.display(), err))?;
+			theme.textures.insert(texture_path, texture.to_rgb8());
+		}//end loading each texture
+		Ok(theme)
+	}//end load(path)
+
+	/// Every fill in this theme.
+	fn fills(&self) -> Vec<&ThemeFill> {
+		vec![&self.cave.wall, &self.cave.floor, &self.cave.stairs, &self.cave.other,
+			&self.room.wall, &self.room.floor, &self.room.stairs, &self.room.other, &self.room.door, &self.room.secret_door, &self.room.empty, &self.room.room_start]
+	}//end fills(self)
+
+	/// Gets the fill for a square of cell_kind on a map of kind.
+	pub fn fill_for(&self, kind: MapKind, cell_kind: CellKind) -> &ThemeFill {
+		match kind {
+			MapKind::Cave => match CAC::from_kind(cell_kind) {
+				CAC::Wall => &self.cave.wall,
+				CAC::Floor => &self.cave.floor,
+				CAC::Stairs => &self.cave.stairs,
+				CAC::Other => &self.cave.other,
+			},
+			MapKind::Room => match CRGC::from_kind(cell_kind) {
+				CRGC::Wall => &self.room.wall,
+				CRGC::Floor => &self.room.floor,
+				CRGC::Stairs => &self.room.stairs,
+				CRGC::Door => &self.room.door,
+				CRGC::SecretDoor => &self.room.secret_door,
+				CRGC::Empty => &self.room.empty,
+				CRGC::RoomStart => &self.room.room_start,
+				CRGC::Other(_) => &self.room.other,
+			},
+		}//end matching kind of map
+	}//end fill_for(self, kind, cell_kind)
+}//end impl for MapTheme
+
+/// Settings for how a map is rendered to an image.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RenderOptions {
+	/// Width and height of each square in the output image, in pixels.
+	pub scale: u32,
+	/// Color of lines drawn along the top and left of every square and around the edge of the map, or None for no grid.
+	pub grid_lines: Option<(u8,u8,u8)>,
+	pub theme: MapTheme,
+}//end struct RenderOptions
+
+impl Default for RenderOptions {
+	fn default() -> Self {
+		Self { scale: 16, grid_lines: None, theme: MapTheme::default() }
+	}//end default()
+}//end impl Default for RenderOptions
+
+/// Renders map as an image with each square scale pixels across, drawn with the fills from theme.
+/// Squares are placed by row and column, so the size of the image doesn't depend on the size of the canvas
+/// the squares came from. A texture that wasn't loaded with [MapTheme::load()] is drawn as magenta.
+pub fn render_map(map: &SquareGrid, kind: MapKind, options: &RenderOptions) -> RgbImage {
+	let scale = options.scale.max(1);
+	let palette = kind.palette();
+	let mut img = RgbImage::new(*map.cols() as u32 * scale, *map.rows() as u32 * scale);
+	let (last_x, last_y) = (img.width().saturating_sub(1), img.height().saturating_sub(1));
+	for row in 0..*map.rows() {
+		for col in 0..*map.cols() {
+			let cell_kind = match map.get(&row, &col) { Some(square) => *square.kind(), None => continue };
+			let fill = options.theme.fill_for(kind, cell_kind);
+			for y in 0..scale {
+				for x in 0..scale {
+					let pixel_color = match fill {
+						ThemeFill::Color(color) => [color.0, color.1, color.2],
+						ThemeFill::Original => { let color = palette.color(cell_kind); [color.0, color.1, color.2] },
+						ThemeFill::Texture(texture_path) => match options.theme.textures.get(texture_path).filter(|texture| texture.width() > 0 && texture.height() > 0) {
+							Some(texture) => texture.get_pixel(x * texture.width() / scale, y * texture.height() / scale).0,
+							None => [255,0,255],
+						},
+					};
+					let (img_x, img_y) = (col as u32 * scale + x, row as u32 * scale + y);
+					// each square only draws its top and left line, so the last column and row close off the map
+					let on_grid_line = x == 0 || y == 0 || img_x == last_x || img_y == last_y;
+					let pixel_color = match options.grid_lines {
+						Some(line_color) if on_grid_line => [line_color.0, line_color.1, line_color.2],
+						_ => pixel_color,
+					};
+					img.put_pixel(img_x, img_y, image::Rgb(pixel_color));
+				}//end looping over x within square
+			}//end looping over y within square
+		}//end looping over cols
+	}//end looping over rows
+	img
+}//end render_map(map, kind, options)
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::export::text::{from_text, TextCharMap};
+
+	#[test]
+	fn grid_lines_close_off_the_map() {
+		let map = from_text("..\n..", MapKind::Cave, &TextCharMap::default(), 1).unwrap();
+		let line = [255,0,0];
+		let options = RenderOptions { scale: 4, grid_lines: Some((255,0,0)), theme: MapTheme::default() };
+		let img = render_map(&map, MapKind::Cave, &options);
+		assert_eq!(img.dimensions(), (8, 8));
+		for edge in 0..8 {
+			assert_eq!(img.get_pixel(edge, 0).0, line);
+			assert_eq!(img.get_pixel(0, edge).0, line);
+			assert_eq!(img.get_pixel(edge, 7).0, line);
+			assert_eq!(img.get_pixel(7, edge).0, line);
+			assert_eq!(img.get_pixel(4, edge).0, line);
+			assert_eq!(img.get_pixel(edge, 4).0, line);
+		}//end checking each pixel along the lines
+		assert_ne!(img.get_pixel(2, 2).0, line);
+		assert_ne!(img.get_pixel(3, 3).0, line);
+		assert_ne!(img.get_pixel(6, 6).0, line);
+	}//end grid_lines_close_off_the_map()
+
+	#[test]
+	fn no_grid_lines_without_a_color() {
+		let map = from_text("..\n..", MapKind::Cave, &TextCharMap::default(), 1).unwrap();
+		let options = RenderOptions { scale: 4, ..RenderOptions::default() };
+		let img = render_map(&map, MapKind::Cave, &options);
+		let fill = img.get_pixel(2, 2).0;
+		assert!(img.pixels().all(|pixel| pixel.0 == fill));
+	}//end no_grid_lines_without_a_color()
+}//end mod tests
@@ -0,0 +1,245 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::{cellular_automata::CAC, room_growth::CRGC, squares::{CellKind, SquareGrid}};
+
+use super::{xml_escape, MapKind};
+
+/// A bar drawn under the map showing how long some number of squares is.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ScaleBar {
+	/// Length of the bar in squares.
+	pub squares: usize,
+	/// Text shown next to the bar, such as "50 ft".
+	pub label: String,
+}//end struct ScaleBar
+
+/// Settings for how an SVG map is drawn.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SvgOptions {
+	/// Size of each square in SVG units.
+	pub square_size: f64,
+	/// Width of the outline drawn around walls, in SVG units.
+	pub wall_stroke_width: f64,
+	/// Whether to draw thin lines between every square.
+	pub grid_overlay: bool,
+	/// Bar drawn under the map to show its scale, or None for no bar. The image is made taller to fit it.
+	pub scale_bar: Option<ScaleBar>,
+}//end struct SvgOptions
+
+impl Default for SvgOptions {
+	fn default() -> Self {
+		Self { square_size: 10.0, wall_stroke_width: 2.0, grid_overlay: false, scale_bar: None }
+	}//end default()
+}//end impl Default for SvgOptions
+
+/// Space left below the map for the scale bar, in SVG units.
+const SCALE_BAR_MARGIN: f64 = 30.0;
+
+/// Converts map to an SVG image.
+/// Orthogonally adjacent squares of the same class are merged into a single outline, so the
+/// image stays sharp and small at any size. Walls are filled and then outlined with a stroke.
+pub fn to_svg(map: &SquareGrid, kind: MapKind, options: &SvgOptions) -> String {
+	let rows = *map.rows();
+	let cols = *map.cols();
+	let size = options.square_size;
+	let map_width = cols as f64 * size;
+	let map_height = rows as f64 * size;
+	let total_height = match options.scale_bar { Some(_) => map_height + SCALE_BAR_MARGIN, None => map_height };
+
+	// each square is stored as the kind of its class, so unknown colors each get their own class
+	let classes_of_squares: Vec<CellKind> = (0..rows * cols).map(|idx| {
+		let cell_kind = match map.get(&(idx / cols), &(idx % cols)) { Some(square) => *square.kind(), None => CAC::Other.kind() };
+		class_kind(kind, cell_kind)
+	}).collect();
+	let palette = kind.palette();
+
+	let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n", map_width, total_height);
+	// walls are drawn last so nothing covers up their outline
+	let mut classes: Vec<CellKind> = Vec::new();
+	for class in classes_of_squares.iter() {
+		if !classes.contains(class) { classes.push(*class); }
+	}//end finding each class in map
+	classes.sort_by_key(|class| *class == CellKind::Wall);
+	for class in classes.iter() {
+		let path = class_outline_path(&classes_of_squares, rows, cols, *class, size);
+		let fill_color = hex_color(palette.color(*class));
+		match *class == CellKind::Wall {
+			true => svg += &format!(" <path d=\"{}\" fill=\"{}\" fill-rule=\"evenodd\" stroke=\"{}\" stroke-width=\"{}\" stroke-linejoin=\"round\"/>\n",
+				path, fill_color, fill_color, options.wall_stroke_width),
+			false => svg += &format!(" <path d=\"{}\" fill=\"{}\" fill-rule=\"evenodd\"/>\n", path, fill_color),
+		}//end matching whether this class is walls
+	}//end drawing each class of square
+
+	if options.grid_overlay {
+		svg += " <g stroke=\"#808080\" stroke-width=\"0.5\" stroke-opacity=\"0.5\">\n";
+		for row in 0..=rows { svg += &format!("  <line x1=\"0\" y1=\"{0}\" x2=\"{1}\" y2=\"{0}\"/>\n", row as f64 * size, map_width); }
+		for col in 0..=cols { svg += &format!("  <line x1=\"{0}\" y1=\"0\" x2=\"{0}\" y2=\"{1}\"/>\n", col as f64 * size, map_height); }
+		svg += " </g>\n";
+	}//end if we should draw grid lines
+
+	if let Some(scale_bar) = &options.scale_bar {
+		let bar_length = scale_bar.squares as f64 * size;
+		let bar_y = map_height + SCALE_BAR_MARGIN / 2.0;
+		svg += &format!(" <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#000000\"/>\n", size / 2.0, bar_y - 2.0, bar_length, 4.0);
+		svg += &format!(" <text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"12\" dominant-baseline=\"middle\">{}</text>\n",
+			size / 2.0 + bar_length + 6.0, bar_y, xml_escape(&scale_bar.label));
+	}//end if we should draw a scale bar
+
+	svg += "</svg>\n";
+	svg
+}//end to_svg(map, kind, options)
+
+/// Saves map as an SVG image at path.
+pub fn save_svg_map(map: &SquareGrid, kind: MapKind, options: &SvgOptions, path: &Path) -> Result<(), String> {
+	std::fs::write(path, to_svg(map, kind, options)).map_err(|err| format!("Couldn't write SVG map to {}: {}", path.display(), err))
+}//end save_svg_map(map, kind, options, path)
+
+/// Gets the kind standing in for the class that a square of cell_kind falls into on a map of kind.
+fn class_kind(kind: MapKind, cell_kind: CellKind) -> CellKind {
+	match kind {
+		MapKind::Cave => CAC::from_kind(cell_kind).kind(),
+		MapKind::Room => CRGC::from_kind(cell_kind).kind(),
+	}//end matching kind of map
+}//end class_kind(kind, cell_kind)
+
+/// Builds SVG path data outlining every square whose class is target.
+/// Each edge between a target square and a square of another class (or the edge of the map)
+/// is traced exactly once, so the outlines are closed loops that draw correctly with evenodd filling.
+fn class_outline_path(classes_of_squares: &[CellKind], rows: usize, cols: usize, target: CellKind, size: f64) -> String {
+	let is_target = |row: isize, col: isize| {
+		row >= 0 && col >= 0 && (row as usize) < rows && (col as usize) < cols && classes_of_squares[row as usize * cols + col as usize] == target
+	};
+
+	// edges go clockwise around each square, as (start corner, end corner) in (x, y) corner coords
+	let mut edges_from: HashMap<(usize,usize), Vec<(usize,usize)>> = HashMap::new();
+	for row in 0..rows {
+		for col in 0..cols {
+			if classes_of_squares[row * cols + col] != target { continue; }
+			let (r, c) = (row as isize, col as isize);
+			if !is_target(r - 1, c) { edges_from.entry((col, row)).or_default().push((col + 1, row)); }
+			if !is_target(r, c + 1) { edges_from.entry((col + 1, row)).or_default().push((col + 1, row + 1)); }
+			if !is_target(r + 1, c) { edges_from.entry((col + 1, row + 1)).or_default().push((col, row + 1)); }
+			if !is_target(r, c - 1) { edges_from.entry((col, row + 1)).or_default().push((col, row)); }
+		}//end looping over cols
+	}//end looping over rows
+
+	// chain edges into loops, only keeping the corners where the outline turns
+	let mut starts: Vec<(usize,usize)> = edges_from.keys().cloned().collect();
+	starts.sort();
+	let mut path = String::new();
+	for start in starts {
+		while let Some(first_end) = edges_from.get_mut(&start).and_then(|ends| ends.pop()) {
+			let mut corners = vec![start];
+			let mut prev = start;
+			let mut cur = first_end;
+			while cur != start {
+				let next = match edges_from.get_mut(&cur).and_then(|ends| ends.pop()) {
+					Some(next) => next,
+					None => break,
+				};
+				let same_direction = (cur.0 as isize - prev.0 as isize, cur.1 as isize - prev.1 as isize)
+					== (next.0 as isize - cur.0 as isize, next.1 as isize - cur.1 as isize);
+				if !same_direction { corners.push(cur); }
+				prev = cur;
+				cur = next;
+			}//end following edges until we get back to start
+			for (idx, (x, y)) in corners.iter().enumerate() {
+				path += &format!("{}{} {} ", if idx == 0 {"M"} else {"L"}, *x as f64 * size, *y as f64 * size);
+			}//end adding each corner to path
+			path += "Z ";
+		}//end tracing each loop starting from this corner
+	}//end looping over corners
+	path.trim_end().to_string()
+}//end class_outline_path(classes_of_squares, rows, cols, target, size)
+
+/// Formats color as an SVG hex color, such as #8c8c8c.
+fn hex_color(color: (u8,u8,u8)) -> String {
+	format!("#{:02x}{:02x}{:02x}", color.0, color.1, color.2)
+}//end hex_color(color)
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::export::text::{from_text, TextCharMap};
+
+	/// Classes of squares for text, with '#' as walls and anything else as floor.
+	fn wall_classes(text: &str) -> (Vec<CellKind>, usize, usize) {
+		let lines: Vec<&str> = text.lines().collect();
+		let classes = lines.iter().flat_map(|line| line.chars()).map(|c| if c == '#' {CellKind::Wall} else {CellKind::Floor}).collect();
+		(classes, lines.len(), lines[0].len())
+	}//end wall_classes(text)
+
+	/// Splits path data into its loops, each a list of corners.
+	fn loops(path: &str) -> Vec<Vec<(f64,f64)>> {
+		path.split('Z').map(|part| part.trim()).filter(|part| !part.is_empty()).map(|part| {
+			let numbers: Vec<f64> = part.split(|c: char| c == 'M' || c == 'L' || c == ' ').filter(|num| !num.is_empty()).map(|num| num.parse().unwrap()).collect();
+			numbers.chunks(2).map(|point| (point[0], point[1])).collect()
+		}).collect()
+	}//end loops(path)
+
+	/// Area inside a loop, positive for clockwise loops and negative for counter-clockwise ones, such as holes.
+	fn signed_area(corners: &[(f64,f64)]) -> f64 {
+		let mut area = 0.0;
+		for (idx, (x, y)) in corners.iter().enumerate() {
+			let (next_x, next_y) = corners[(idx + 1) % corners.len()];
+			area += x * next_y - next_x * y;
+		}//end adding up each edge
+		area / 2.0
+	}//end signed_area(corners)
+
+	/// Checks every edge of every loop in path runs along a row or column, then returns the area path covers.
+	fn covered_area(path: &str) -> f64 {
+		let loops = loops(path);
+		for corners in loops.iter() {
+			for (idx, (x, y)) in corners.iter().enumerate() {
+				let (next_x, next_y) = corners[(idx + 1) % corners.len()];
+				assert!(*x == next_x || *y == next_y, "diagonal edge in {}", path);
+			}//end checking each edge
+		}//end checking each loop
+		loops.iter().map(|corners| signed_area(corners)).sum()
+	}//end covered_area(path)
+
+	#[test]
+	fn single_square_is_one_loop_of_four_corners() {
+		let (classes, rows, cols) = wall_classes("...\n.#.\n...");
+		let path = class_outline_path(&classes, rows, cols, CellKind::Wall, 10.0);
+		assert_eq!(path, "M10 10 L20 10 L20 20 L10 20 Z");
+		assert_eq!(covered_area(&path), 100.0);
+	}//end single_square_is_one_loop_of_four_corners()
+
+	#[test]
+	fn ring_has_outer_loop_and_hole() {
+		let (classes, rows, cols) = wall_classes("###\n#.#\n###");
+		let path = class_outline_path(&classes, rows, cols, CellKind::Wall, 1.0);
+		let loops = loops(&path);
+		assert_eq!(loops.len(), 2);
+		assert!(loops.iter().all(|corners| corners.len() == 4));
+		let mut areas: Vec<f64> = loops.iter().map(|corners| signed_area(corners)).collect();
+		areas.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		assert_eq!(areas, vec![-1.0, 9.0]);
+
+		let floor = class_outline_path(&classes, rows, cols, CellKind::Floor, 1.0);
+		assert_eq!(floor, "M1 1 L2 1 L2 2 L1 2 Z");
+	}//end ring_has_outer_loop_and_hole()
+
+	#[test]
+	fn squares_touching_at_corner_are_both_outlined() {
+		let (classes, rows, cols) = wall_classes("#.\n.#");
+		let path = class_outline_path(&classes, rows, cols, CellKind::Wall, 1.0);
+		assert_eq!(covered_area(&path), 2.0);
+		assert_eq!(loops(&path).iter().map(|corners| corners.len()).sum::<usize>(), 8);
+
+		let floor = class_outline_path(&classes, rows, cols, CellKind::Floor, 1.0);
+		assert_eq!(covered_area(&floor), 2.0);
+	}//end squares_touching_at_corner_are_both_outlined()
+
+	#[test]
+	fn scale_bar_label_is_escaped() {
+		let map = from_text("#.\n.#", MapKind::Cave, &TextCharMap::default(), 1).unwrap();
+		let options = SvgOptions { scale_bar: Some(ScaleBar { squares: 2, label: String::from("<10 & \"ft\">") }), ..SvgOptions::default() };
+		let svg = to_svg(&map, MapKind::Cave, &options);
+		assert!(svg.contains(">&lt;10 &amp; &quot;ft&quot;&gt;</text>"));
+		assert!(!svg.contains("<10"));
+		assert!(svg.contains("height=\"50\""));
+	}//end scale_bar_label_is_escaped()
+}//end mod tests
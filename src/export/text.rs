@@ -0,0 +1,220 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{cellular_automata::CAC, room_growth::CRGC, squares::{CellKind, Square, SquareGrid}, stairs::{StairDirection, StairLink}};
+
+use super::MapKind;
+
+/// Characters used for each class of cave square.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaveChars {
+	pub wall: char,
+	pub floor: char,
+	/// Used for stairs leading down, outside, or nowhere in particular.
+	pub stairs: char,
+	/// Used for stairs linked as leading up.
+	pub stairs_up: char,
+	pub other: char,
+}//end struct CaveChars
+
+impl Default for CaveChars {
+	fn default() -> Self {
+		Self { wall: '#', floor: '.', stairs: '>', stairs_up: '<', other: '?' }
+	}//end default()
+}//end impl Default for CaveChars
+
+/// Characters used for each class of room square.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomChars {
+	pub wall: char,
+	pub floor: char,
+	/// Used for stairs leading down, outside, or nowhere in particular.
+	pub stairs: char,
+	/// Used for stairs linked as leading up.
+	pub stairs_up: char,
+	pub other: char,
+	pub door: char,
+	pub secret_door: char,
+	pub empty: char,
+	pub room_start: char,
+}//end struct RoomChars
+
+impl Default for RoomChars {
+	fn default() -> Self {
+		Self { wall: '#', floor: '.', stairs: '>', stairs_up: '<', other: '?', door: '+', secret_door: 'S', empty: ' ', room_start: '*' }
+	}//end default()
+}//end impl Default for RoomChars
+
+/// Which character each class of square becomes in a text map.
+/// Can be saved as JSON, so one file can be reused for every map read by the same program.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextCharMap {
+	pub cave_chars: CaveChars,
+	pub room_chars: RoomChars,
+}//end struct TextCharMap
+
+impl TextCharMap {
+	/// Loads a character map from a JSON file at path. Anything missing from the file keeps its default.
+	pub fn load(path: &Path) -> Result<TextCharMap, String> {
+		let text = std::fs::read_to_string(path).map_err(|err| format!("Couldn't read character map from {}: {}", path.display(), err))?;
+		serde_json::from_str(&text).map_err(|err| format!("Couldn't read character map from {}: {}", path.display(), err))
+	}//end load(path)
+
+	/// Gets the character for a square of cell_kind on a map of kind.
+	/// stair_direction is only used if the square is stairs.
+	pub fn char_for(&self, kind: MapKind, cell_kind: CellKind, stair_direction: Option<StairDirection>) -> char {
+		match kind {
+			MapKind::Cave => match CAC::from_kind(cell_kind) {
+				CAC::Wall => self.cave_chars.wall,
+				CAC::Floor => self.cave_chars.floor,
+				CAC::Stairs if stair_direction == Some(StairDirection::Up) => self.cave_chars.stairs_up,
+				CAC::Stairs => self.cave_chars.stairs,
+				CAC::Other => self.cave_chars.other,
+			},
+			MapKind::Room => match CRGC::from_kind(cell_kind) {
+				CRGC::Wall => self.room_chars.wall,
+				CRGC::Floor => self.room_chars.floor,
+				CRGC::Stairs if stair_direction == Some(StairDirection::Up) => self.room_chars.stairs_up,
+				CRGC::Stairs => self.room_chars.stairs,
+				CRGC::Door => self.room_chars.door,
+				CRGC::SecretDoor => self.room_chars.secret_door,
+				CRGC::Empty => self.room_chars.empty,
+				CRGC::RoomStart => self.room_chars.room_start,
+				CRGC::Other(_) => self.room_chars.other,
+			},
+		}//end matching kind of map
+	}//end char_for(self, kind, cell_kind, stair_direction)
+
+	/// Gets the kind of square written as character on a map of kind, or None if character isn't in this map.
+	/// If more than one class uses the same character, the first one listed in [CaveChars] or [RoomChars] wins.
+	pub fn kind_for(&self, kind: MapKind, character: char) -> Option<CellKind> {
+		match kind {
+			MapKind::Cave => {
+				let chars = &self.cave_chars;
+				if character == chars.wall { Some(CAC::Wall.kind()) }
+				else if character == chars.floor { Some(CAC::Floor.kind()) }
+				else if character == chars.stairs || character == chars.stairs_up { Some(CAC::Stairs.kind()) }
+				else if character == chars.other { Some(CAC::Other.kind()) }
+				else { None }
+			},
+			MapKind::Room => {
+				let chars = &self.room_chars;
+				if character == chars.wall { Some(CRGC::Wall.kind()) }
+				else if character == chars.floor { Some(CRGC::Floor.kind()) }
+				else if character == chars.stairs || character == chars.stairs_up { Some(CRGC::Stairs.kind()) }
+				// Other room squares could have been drawn any color, so reuse the one cave maps use
+				else if character == chars.other { Some(CAC::Other.kind()) }
+				else if character == chars.door { Some(CRGC::Door.kind()) }
+				else if character == chars.secret_door { Some(CRGC::SecretDoor.kind()) }
+				else if character == chars.empty { Some(CRGC::Empty.kind()) }
+				else if character == chars.room_start { Some(CRGC::RoomStart.kind()) }
+				else { None }
+			},
+		}//end matching kind of map
+	}//end kind_for(self, kind, character)
+}//end impl for TextCharMap
+
+/// Converts map to a text grid, with one character per square and one line per row.
+/// stair_links are used to tell which stairs lead up, and can be empty if stairs aren't linked.
+pub fn to_text(map: &SquareGrid, kind: MapKind, stair_links: &[StairLink], char_map: &TextCharMap) -> String {
+	let mut text = String::with_capacity((*map.cols() + 1) * *map.rows());
+	for row in 0..*map.rows() {
+		for col in 0..*map.cols() {
+			let cell_kind = match map.get(&row, &col) { Some(square) => *square.kind(), None => continue };
+			let stair_direction = stair_links.iter().find(|link| link.row == row && link.col == col).map(|link| link.direction);
+			text.push(char_map.char_for(kind, cell_kind, stair_direction));
+		}//end looping over cols
+		text.push('\n');
+	}//end looping over rows
+	text
+}//end to_text(map, kind, stair_links, char_map)
+
+/// Rebuilds a [SquareGrid] from a text grid made by [to_text()], with each square square_size pixels across.
+/// Lines shorter than the longest line are filled out with empty squares on room maps and walls on cave maps,
+/// since editors often strip trailing spaces.
+///
+/// Returns Err if the text is empty, square_size is 0, or a character isn't in char_map.
+pub fn from_text(text: &str, kind: MapKind, char_map: &TextCharMap, square_size: usize) -> Result<SquareGrid, String> {
+	if square_size == 0 { return Err(String::from("Squares in a text map need to be at least 1 pixel across.")); }
+	let lines: Vec<&str> = text.lines().collect();
+	let rows = lines.len();
+	let cols = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+	if rows == 0 || cols == 0 { return Err(String::from("Text map doesn't have any squares in it.")); }
+
+	// caves don't have empty squares, so solid rock is the closest thing
+	let padding = match kind {
+		MapKind::Cave => char_map.cave_chars.wall,
+		MapKind::Room => char_map.room_chars.empty,
+	};
+	let mut squares = Vec::with_capacity(rows * cols);
+	for (row, line) in lines.iter().enumerate() {
+		for (col, character) in line.chars().chain(std::iter::repeat(padding)).take(cols).enumerate() {
+			let cell_kind = match char_map.kind_for(kind, character) {
+				Some(cell_kind) => cell_kind,
+				None => return Err(format!("Couldn't understand character {:?} at line {}, column {} of text map.", character, row + 1, col + 1)),
+			};
+			squares.push(Square::new(col * square_size, row * square_size, square_size, square_size).with_kind(cell_kind));
+		}//end looping over characters in line
+	}//end looping over lines
+
+	SquareGrid::from_squares(squares, cols * square_size, rows * square_size).map_err(|err_info| err_info.1)
+}//end from_text(text, kind, char_map, square_size)
+
+/// Saves map as a text grid at path.
+pub fn save_text_map(map: &SquareGrid, kind: MapKind, stair_links: &[StairLink], char_map: &TextCharMap, path: &Path) -> Result<(), String> {
+	std::fs::write(path, to_text(map, kind, stair_links, char_map)).map_err(|err| format!("Couldn't write text map to {}: {}", path.display(), err))
+}//end save_text_map(map, kind, stair_links, char_map, path)
+
+/// Loads a text grid from path as a [SquareGrid], with each square square_size pixels across.
+pub fn load_text_map(path: &Path, kind: MapKind, char_map: &TextCharMap, square_size: usize) -> Result<SquareGrid, String> {
+	let text = std::fs::read_to_string(path).map_err(|err| format!("Couldn't read text map from {}: {}", path.display(), err))?;
+	from_text(&text, kind, char_map, square_size)
+}//end load_text_map(path, kind, char_map, square_size)
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn room_text_round_trips() {
+		let char_map = TextCharMap::default();
+		let text = "#####\n#.+S#\n#>* #\n#####\n";
+		let map = from_text(text, MapKind::Room, &char_map, 3).unwrap();
+		assert_eq!((*map.rows(), *map.cols()), (4, 5));
+		assert_eq!(*map.get(&1, &2).unwrap().kind(), CellKind::Door);
+		assert_eq!(*map.get(&1, &3).unwrap().kind(), CellKind::SecretDoor);
+		assert_eq!(to_text(&map, MapKind::Room, &[], &char_map), text);
+	}//end room_text_round_trips()
+
+	#[test]
+	fn cave_text_round_trips_with_up_stairs() {
+		let char_map = TextCharMap::default();
+		let text = "#.<\n.>#\n";
+		let map = from_text(text, MapKind::Cave, &char_map, 1).unwrap();
+		let links = [StairLink::new(0, 2, StairDirection::Up, Some(0))];
+		assert_eq!(to_text(&map, MapKind::Cave, &links, &char_map), text);
+		assert_eq!(to_text(&map, MapKind::Cave, &[], &char_map), "#.>\n.>#\n");
+	}//end cave_text_round_trips_with_up_stairs()
+
+	#[test]
+	fn short_lines_pad_with_the_kinds_empty_square() {
+		let char_map = TextCharMap::default();
+		let cave = from_text("#..\n.", MapKind::Cave, &char_map, 1).unwrap();
+		assert_eq!(to_text(&cave, MapKind::Cave, &[], &char_map), "#..\n.##\n");
+		let room = from_text("#..\n.", MapKind::Room, &char_map, 1).unwrap();
+		assert_eq!(to_text(&room, MapKind::Room, &[], &char_map), "#..\n.  \n");
+	}//end short_lines_pad_with_the_kinds_empty_square()
+
+	#[test]
+	fn from_text_rejects_bad_input() {
+		let char_map = TextCharMap::default();
+		assert!(from_text("", MapKind::Room, &char_map, 1).is_err());
+		assert!(from_text("#.", MapKind::Room, &char_map, 0).is_err());
+		assert!(from_text("#x", MapKind::Room, &char_map, 1).is_err());
+		assert!(from_text("# ", MapKind::Cave, &char_map, 1).is_err());
+	}//end from_text_rejects_bad_input()
+}//end mod tests
@@ -0,0 +1,371 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{cellular_automata::CAC, room_growth::CRGC, squares::{CellKind, SquareGrid}, stairs::StairLink};
+
+use super::{xml_escape, MapKind};
+
+/// Version of the Tiled map format written by this exporter.
+const TILED_FORMAT_VERSION: &str = "1.10";
+
+/// The tileset image that tile IDs refer to.
+/// Tile IDs start at 0 for the tile in the upper left of the image, and count across each row.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TiledTileset {
+	pub name: String,
+	/// Path to the tileset image, relative to where the map is saved.
+	pub image: String,
+	pub image_width: u32,
+	pub image_height: u32,
+	pub tile_width: u32,
+	pub tile_height: u32,
+	pub columns: u32,
+	pub tile_count: u32,
+}//end struct TiledTileset
+
+impl Default for TiledTileset {
+	fn default() -> Self {
+		Self {
+			name: String::from("nice-map-tiles"),
+			image: String::from("tiles.png"),
+			image_width: 128,
+			image_height: 16,
+			tile_width: 16,
+			tile_height: 16,
+			columns: 8,
+			tile_count: 8,
+		}//end struct construction
+	}//end default()
+}//end impl Default for TiledTileset
+
+/// Tile IDs used for each class of cave square.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaveTileIds {
+	pub wall: u32,
+	pub floor: u32,
+	pub stairs: u32,
+	pub other: u32,
+}//end struct CaveTileIds
+
+impl Default for CaveTileIds {
+	fn default() -> Self {
+		Self { wall: 0, floor: 1, stairs: 2, other: 3 }
+	}//end default()
+}//end impl Default for CaveTileIds
+
+/// Tile IDs used for each class of room square.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomTileIds {
+	pub wall: u32,
+	pub floor: u32,
+	pub stairs: u32,
+	pub other: u32,
+	pub door: u32,
+	pub secret_door: u32,
+	pub empty: u32,
+	pub room_start: u32,
+}//end struct RoomTileIds
+
+impl Default for RoomTileIds {
+	fn default() -> Self {
+		Self { wall: 0, floor: 1, stairs: 2, other: 3, door: 4, secret_door: 6, empty: 5, room_start: 7 }
+	}//end default()
+}//end impl Default for RoomTileIds
+
+/// Which tileset to use, and which of its tiles each class of square becomes.
+/// Can be saved as JSON, so one file can be reused for every map made with the same tileset.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TiledSettings {
+	pub tileset: TiledTileset,
+	pub cave_tiles: CaveTileIds,
+	pub room_tiles: RoomTileIds,
+}//end struct TiledSettings
+
+impl TiledSettings {
+	/// Loads settings from a JSON file at path. Anything missing from the file keeps its default.
+	pub fn load(path: &Path) -> Result<TiledSettings, String> {
+		let text = std::fs::read_to_string(path).map_err(|err| format!("Couldn't read tileset settings from {}: {}", path.display(), err))?;
+		serde_json::from_str(&text).map_err(|err| format!("Couldn't read tileset settings from {}: {}", path.display(), err))
+	}//end load(path)
+
+	/// Gets the tile ID for a square of cell_kind on a map of kind.
+	pub fn tile_id(&self, kind: MapKind, cell_kind: CellKind) -> u32 {
+		match kind {
+			MapKind::Cave => match CAC::from_kind(cell_kind) {
+				CAC::Wall => self.cave_tiles.wall,
+				CAC::Floor => self.cave_tiles.floor,
+				CAC::Stairs => self.cave_tiles.stairs,
+				CAC::Other => self.cave_tiles.other,
+			},
+			MapKind::Room => match CRGC::from_kind(cell_kind) {
+				CRGC::Wall => self.room_tiles.wall,
+				CRGC::Floor => self.room_tiles.floor,
+				CRGC::Stairs => self.room_tiles.stairs,
+				CRGC::Door => self.room_tiles.door,
+				CRGC::SecretDoor => self.room_tiles.secret_door,
+				CRGC::Empty => self.room_tiles.empty,
+				CRGC::RoomStart => self.room_tiles.room_start,
+				CRGC::Other(_) => self.room_tiles.other,
+			},
+		}//end matching kind of map
+	}//end tile_id(self, kind, cell_kind)
+}//end impl for TiledSettings
+
+/// A stair or door, exported to the object layer.
+struct TiledObject {
+	name: &'static str,
+	class: &'static str,
+	row: usize,
+	col: usize,
+	/// name, Tiled property type, and value of each custom property
+	properties: Vec<(&'static str, &'static str, String)>,
+}//end struct TiledObject
+
+/// Tiled gid of the first tile in the tileset. gid 0 means no tile.
+const FIRST_GID: u32 = 1;
+
+/// Gets the stairs and doors in map as objects, with stairs carrying their link from stair_links if they have one.
+fn find_objects(map: &SquareGrid, kind: MapKind, stair_links: &[StairLink]) -> Vec<TiledObject> {
+	let mut objects = Vec::new();
+	for row in 0..*map.rows() {
+		for col in 0..*map.cols() {
+			let cell_kind = match map.get(&row, &col) { Some(square) => *square.kind(), None => continue };
+			let (is_stairs, is_door, is_secret) = match kind {
+				MapKind::Cave => (CAC::from_kind(cell_kind) == CAC::Stairs, false, false),
+				MapKind::Room => {
+					let class = CRGC::from_kind(cell_kind);
+					(class == CRGC::Stairs, class == CRGC::Door, class == CRGC::SecretDoor)
+				},
+			};
+			if is_stairs {
+				let mut properties = Vec::new();
+				if let Some(link) = stair_links.iter().find(|link| link.row == row && link.col == col) {
+					properties.push(("direction", "string", format!("{:?}", link.direction)));
+					if let Some(target_level) = link.target_level {
+						// levels are numbered from 1, like in the level controls
+						properties.push(("target_level", "int", (target_level + 1).to_string()));
+					}//end if link leads to another level
+				}//end if stairs are linked
+				objects.push(TiledObject { name: "Stairs", class: "stairs", row, col, properties });
+			} else if is_door {
+				objects.push(TiledObject { name: "Door", class: "door", row, col, properties: Vec::new() });
+			} else if is_secret {
+				objects.push(TiledObject { name: "Secret Door", class: "door", row, col, properties: vec![("secret", "bool", String::from("true"))] });
+			}//end else if this is a door
+		}//end looping over cols
+	}//end looping over rows
+	objects
+}//end find_objects(map, kind, stair_links)
+
+/// Gets the gid of every square in map, in row-major order.
+fn tile_gids(map: &SquareGrid, kind: MapKind, settings: &TiledSettings) -> Vec<u32> {
+	map.iter().map(|square| FIRST_GID + settings.tile_id(kind, *square.kind())).collect()
+}//end tile_gids(map, kind, settings)
+
+/// Converts map to a Tiled JSON map, with a tile layer for the squares and an object layer for stairs and doors.
+/// stair_links are used to tell where each stair leads, and can be empty if stairs aren't linked.
+pub fn to_tiled_json(map: &SquareGrid, kind: MapKind, stair_links: &[StairLink], settings: &TiledSettings) -> Result<String, String> {
+	let tileset = &settings.tileset;
+	let objects: Vec<serde_json::Value> = find_objects(map, kind, stair_links).into_iter().enumerate().map(|(idx, object)| json!({
+		"id": idx + 1,
+		"name": object.name,
+		"type": object.class,
+		"x": object.col as u32 * tileset.tile_width,
+		"y": object.row as u32 * tileset.tile_height,
+		"width": tileset.tile_width,
+		"height": tileset.tile_height,
+		"rotation": 0,
+		"visible": true,
+		"properties": object.properties.iter().map(|(name, prop_type, value)| json!({
+			"name": name,
+			"type": prop_type,
+			"value": match *prop_type {
+				"int" => json!(value.parse::<i64>().unwrap_or_default()),
+				"bool" => json!(value == "true"),
+				_ => json!(value),
+			},
+		})).collect::<Vec<_>>(),
+	})).collect();
+	let next_object_id = objects.len() + 1;
+
+	let tiled_map = json!({
+		"type": "map",
+		"version": TILED_FORMAT_VERSION,
+		"orientation": "orthogonal",
+		"renderorder": "right-down",
+		"infinite": false,
+		"width": map.cols(),
+		"height": map.rows(),
+		"tilewidth": tileset.tile_width,
+		"tileheight": tileset.tile_height,
+		"nextlayerid": 3,
+		"nextobjectid": next_object_id,
+		"layers": [
+			{
+				"type": "tilelayer",
+				"id": 1,
+				"name": "Tiles",
+				"x": 0,
+				"y": 0,
+				"width": map.cols(),
+				"height": map.rows(),
+				"opacity": 1,
+				"visible": true,
+				"data": tile_gids(map, kind, settings),
+			},
+			{
+				"type": "objectgroup",
+				"id": 2,
+				"name": "Connections",
+				"x": 0,
+				"y": 0,
+				"opacity": 1,
+				"visible": true,
+				"draworder": "topdown",
+				"objects": objects,
+			},
+		],
+		"tilesets": [
+			{
+				"firstgid": FIRST_GID,
+				"name": tileset.name,
+				"image": tileset.image,
+				"imagewidth": tileset.image_width,
+				"imageheight": tileset.image_height,
+				"tilewidth": tileset.tile_width,
+				"tileheight": tileset.tile_height,
+				"columns": tileset.columns,
+				"tilecount": tileset.tile_count,
+				"margin": 0,
+				"spacing": 0,
+			},
+		],
+	});
+	serde_json::to_string_pretty(&tiled_map).map_err(|err| format!("Couldn't convert map to Tiled JSON: {}", err))
+}//end to_tiled_json(map, kind, stair_links, settings)
+
+/// Converts map to a Tiled TMX map, with a tile layer for the squares and an object layer for stairs and doors.
+/// stair_links are used to tell where each stair leads, and can be empty if stairs aren't linked.
+pub fn to_tmx(map: &SquareGrid, kind: MapKind, stair_links: &[StairLink], settings: &TiledSettings) -> String {
+	let tileset = &settings.tileset;
+	let objects = find_objects(map, kind, stair_links);
+	let mut tmx = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+	tmx += &format!("<map version=\"{}\" orientation=\"orthogonal\" renderorder=\"right-down\" width=\"{}\" height=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" infinite=\"0\" nextlayerid=\"3\" nextobjectid=\"{}\">\n",
+		TILED_FORMAT_VERSION, map.cols(), map.rows(), tileset.tile_width, tileset.tile_height, objects.len() + 1);
+	tmx += &format!(" <tileset firstgid=\"{}\" name=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" tilecount=\"{}\" columns=\"{}\">\n",
+		FIRST_GID, xml_escape(&tileset.name), tileset.tile_width, tileset.tile_height, tileset.tile_count, tileset.columns);
+	tmx += &format!("  <image source=\"{}\" width=\"{}\" height=\"{}\"/>\n", xml_escape(&tileset.image), tileset.image_width, tileset.image_height);
+	tmx += " </tileset>\n";
+
+	tmx += &format!(" <layer id=\"1\" name=\"Tiles\" width=\"{}\" height=\"{}\">\n  <data encoding=\"csv\">\n", map.cols(), map.rows());
+	let gids = tile_gids(map, kind, settings);
+	let rows: Vec<String> = gids.chunks(*map.cols()).map(|row| row.iter().map(|gid| gid.to_string()).collect::<Vec<_>>().join(",")).collect();
+	tmx += &rows.join(",\n");
+	tmx += "\n</data>\n </layer>\n";
+
+	tmx += " <objectgroup id=\"2\" name=\"Connections\">\n";
+	for (idx, object) in objects.iter().enumerate() {
+		tmx += &format!("  <object id=\"{}\" name=\"{}\" type=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"",
+			idx + 1, object.name, object.class, object.col as u32 * tileset.tile_width, object.row as u32 * tileset.tile_height, tileset.tile_width, tileset.tile_height);
+		if object.properties.is_empty() {
+			tmx += "/>\n";
+			continue;
+		}//end if there are no properties to add
+		tmx += ">\n   <properties>\n";
+		for (name, prop_type, value) in &object.properties {
+			match *prop_type {
+				"string" => tmx += &format!("    <property name=\"{}\" value=\"{}\"/>\n", name, xml_escape(value)),
+				_ => tmx += &format!("    <property name=\"{}\" type=\"{}\" value=\"{}\"/>\n", name, prop_type, xml_escape(value)),
+			}//end matching property type
+		}//end adding each property
+		tmx += "   </properties>\n  </object>\n";
+	}//end adding each object
+	tmx += " </objectgroup>\n</map>\n";
+	tmx
+}//end to_tmx(map, kind, stair_links, settings)
+
+/// Saves map as a Tiled map at path.
+/// A path ending in .tmx is saved as TMX, and anything else is saved as Tiled JSON.
+pub fn save_tiled_map(map: &SquareGrid, kind: MapKind, stair_links: &[StairLink], settings: &TiledSettings, path: &Path) -> Result<(), String> {
+	let is_tmx = path.extension().map(|ext| ext.eq_ignore_ascii_case("tmx")).unwrap_or(false);
+	let text = match is_tmx {
+		true => to_tmx(map, kind, stair_links, settings),
+		false => to_tiled_json(map, kind, stair_links, settings)?,
+	};
+	std::fs::write(path, text).map_err(|err| format!("Couldn't write Tiled map to {}: {}", path.display(), err))
+}//end save_tiled_map(map, kind, stair_links, settings, path)
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{export::text::{from_text, TextCharMap}, stairs::StairDirection};
+
+	/// A room map with one square of every class, and its stairs linked down to the second level.
+	fn every_room_class() -> (SquareGrid, Vec<StairLink>) {
+		let map = from_text("#.> \n+S*#", MapKind::Room, &TextCharMap::default(), 1).unwrap();
+		(map, vec![StairLink::new(0, 2, StairDirection::Down, Some(1))])
+	}//end every_room_class()
+
+	/// gids expected for [every_room_class()] with the default tile IDs, which are one more than the tile IDs.
+	const EVERY_ROOM_CLASS_GIDS: [u32; 8] = [1, 2, 3, 6, 5, 7, 8, 1];
+
+	#[test]
+	fn default_room_tile_ids_are_unique() {
+		let ids = RoomTileIds::default();
+		let mut all_ids = vec![ids.wall, ids.floor, ids.stairs, ids.other, ids.door, ids.secret_door, ids.empty, ids.room_start];
+		all_ids.sort();
+		all_ids.dedup();
+		assert_eq!(all_ids.len(), 8);
+		assert!(all_ids.iter().all(|id| *id < TiledTileset::default().tile_count));
+	}//end default_room_tile_ids_are_unique()
+
+	#[test]
+	fn tiled_json_has_tiles_and_stairs() {
+		let (map, stair_links) = every_room_class();
+		let json = to_tiled_json(&map, MapKind::Room, &stair_links, &TiledSettings::default()).unwrap();
+		let tiled_map: serde_json::Value = serde_json::from_str(&json).unwrap();
+		assert_eq!((tiled_map["width"].as_u64(), tiled_map["height"].as_u64()), (Some(4), Some(2)));
+
+		let tiles = &tiled_map["layers"][0];
+		assert_eq!((tiles["width"].as_u64(), tiles["height"].as_u64()), (Some(4), Some(2)));
+		let gids: Vec<u64> = tiles["data"].as_array().unwrap().iter().map(|gid| gid.as_u64().unwrap()).collect();
+		assert_eq!(gids, EVERY_ROOM_CLASS_GIDS.map(u64::from));
+
+		let objects = tiled_map["layers"][1]["objects"].as_array().unwrap();
+		assert_eq!(objects.len(), 3);
+		let stairs = objects.iter().find(|object| object["type"] == "stairs").unwrap();
+		assert_eq!((stairs["x"].as_u64(), stairs["y"].as_u64()), (Some(32), Some(0)));
+		assert_eq!(stairs["properties"][0]["name"], "direction");
+		assert_eq!(stairs["properties"][0]["value"], "Down");
+		assert_eq!(stairs["properties"][1]["name"], "target_level");
+		assert_eq!(stairs["properties"][1]["value"].as_i64(), Some(2));
+		assert_eq!(objects.iter().filter(|object| object["type"] == "door").count(), 2);
+		assert_eq!(tiled_map["nextobjectid"].as_u64(), Some(4));
+	}//end tiled_json_has_tiles_and_stairs()
+
+	#[test]
+	fn tmx_has_tiles_and_stairs() {
+		let (map, stair_links) = every_room_class();
+		let tmx = to_tmx(&map, MapKind::Room, &stair_links, &TiledSettings::default());
+		assert!(tmx.contains("<layer id=\"1\" name=\"Tiles\" width=\"4\" height=\"2\">"));
+
+		let data_start = tmx.find("<data encoding=\"csv\">").unwrap() + "<data encoding=\"csv\">".len();
+		let data_end = tmx.find("</data>").unwrap();
+		let rows: Vec<&str> = tmx[data_start..data_end].trim().split(",\n").collect();
+		assert_eq!(rows.len(), 2);
+		let gids: Vec<u32> = rows.iter().flat_map(|row| row.split(',')).map(|gid| gid.parse().unwrap()).collect();
+		assert_eq!(gids, EVERY_ROOM_CLASS_GIDS);
+
+		assert_eq!(tmx.matches("<object ").count(), 3);
+		assert!(tmx.contains("<object id=\"1\" name=\"Stairs\" type=\"stairs\" x=\"32\" y=\"0\" width=\"16\" height=\"16\">"));
+		assert!(tmx.contains("<property name=\"direction\" value=\"Down\"/>"));
+		assert!(tmx.contains("<property name=\"target_level\" type=\"int\" value=\"2\"/>"));
+		assert!(tmx.contains("<property name=\"secret\" type=\"bool\" value=\"true\"/>"));
+	}//end tmx_has_tiles_and_stairs()
+}//end mod tests
@@ -1,8 +1,8 @@
 
-use std::{cell::RefCell, path::PathBuf, rc::Rc};
+use std::{cell::RefCell, path::{Path, PathBuf}, rc::Rc};
 
 use fltk::{app::{self, App, Receiver, Sender}, button::Button, dialog, enums::{Align, FrameType, Shortcut}, group::{Group, Tabs}, menu::{self, Choice, SysMenuBar}, prelude::{GroupExt, MenuExt, WidgetExt}, window::Window};
-use nice_map_generator::squares::SquareGrid;
+use nice_map_generator::{cellular_automata::{Boundary, Neighborhood}, levels::LevelStack, project::Project, room_growth::{RoomLimits, StartPlacement}, squares::SquareGrid, stairs::StairDirection, zones::ZoneSettings};
 
 use self::{room_gen_group::RoomGenGroup, cave_gen_group::CaveGenGroup, gui_utils::{get_default_menu_height, get_default_tab_padding, get_default_win_height, get_default_win_width}};
 
@@ -98,18 +98,25 @@ impl GUI {
 		
 		// set up all the emitters
 		self.ux_top_menu.add_emit(
-			"&File/Choice1...\t",
-			Shortcut::Ctrl | 'n',
+			"&File/Save Project...\t",
+			Shortcut::Ctrl | 's',
 			menu::MenuFlag::Normal,
 			self.msg_sender.clone(),
-			"MenuChoice::Choice1".to_string(),
+			"MenuChoice::SaveProject".to_string(),
 		);
 		self.ux_top_menu.add_emit(
-			"&File/Choice2...\t",
+			"&File/Open Project...\t",
 			Shortcut::Ctrl | 'o',
 			menu::MenuFlag::Normal,
 			self.msg_sender.clone(),
-			"MenuChoice::Choice2".to_string(),
+			"MenuChoice::OpenProject".to_string(),
+		);
+		self.ux_top_menu.add_emit(
+			"&File/Import Image...\t",
+			Shortcut::Ctrl | 'i',
+			menu::MenuFlag::Normal,
+			self.msg_sender.clone(),
+			"MenuChoice::ImportImage".to_string(),
 		);
 		self.ux_top_menu.add_emit(
 			"Regen",
@@ -196,6 +203,10 @@ impl GUI {
 		ux_format_choice.add_choice("JPEG");
 		ux_format_choice.add_choice("BMP");
 		ux_format_choice.add_choice("WEBP");
+		ux_format_choice.add_choice("SVG");
+		ux_format_choice.add_choice("Tiled JSON");
+		ux_format_choice.add_choice("Tiled TMX");
+		ux_format_choice.add_choice("Text");
 
 		let mut ux_export_format = Button::default()
 			.with_label("Export")
@@ -218,6 +229,10 @@ impl GUI {
 						let msg_sender = {msg_sender_ref.as_ref().borrow()};
 						match choice.as_str() {
 							"PNG" | "JPEG" | "BMP" | "WEBP" => msg_sender.send(format!("Export:{}", choice)),
+							"SVG" => msg_sender.send(String::from("Export:SVG")),
+							"Tiled JSON" => msg_sender.send(String::from("Export:TMJ")),
+							"Tiled TMX" => msg_sender.send(String::from("Export:TMX")),
+							"Text" => msg_sender.send(String::from("Export:TXT")),
 							_ => println!("Unrecognized output format {}", choice),
 						}//end matching choice
 					},
@@ -328,25 +343,132 @@ impl GUI {
 		self.ux_room_gen_tab.get_last_squareularization()
 	}//end get_room_canvas_squareularization()
 
-	/// Sets the canvas stuff in cave canvas based on [square_info].  
-	/// Format for [square_info] should be the same as format for [get_cave_canvas_squareularization()].  
-	/// This function calls CaveGenGroup::[squareularization_color_squares()], which might panic under a 
-	/// variety of circumstances, mostly from type conversion (usize to i32) or from the canvas size shrinking 
-	/// in between getting and setting squareularization. For more information on how the function might panic, 
-	/// refer back to [CaveGenGroup]::[squareularization_color_squares()].
-	pub fn set_cave_canvas_squareularization(&mut self, squares: &SquareGrid) {
-		self.ux_cave_gen_tab.set_squareularization(squares);
-	}//end set_cave_canvas_squareularization(self, square_info)
-
-	pub fn set_room_canvas_squareularization(&mut self, squares: &SquareGrid) {
-		self.ux_room_gen_tab.set_squareularization(squares);
-	}//end set_room_canvas_squareularization
-
 	/// Returns neighborhood size, neighborhood threshold, and generations to run from cave canvas group
 	pub fn get_cave_canvas_ca_settings(&self) -> (usize,usize,usize) {
 		self.ux_cave_gen_tab.get_cave_canvas_ca_settings()
 	}//end get_cave_canvas_ca_settings(self)
 
+	/// Returns neighborhood shape, edge handling, and rule schedule text from cave canvas group
+	pub fn get_cave_canvas_ca_rule_settings(&self) -> (Neighborhood, Boundary, String) {
+		self.ux_cave_gen_tab.get_cave_canvas_ca_rule_settings()
+	}//end get_cave_canvas_ca_rule_settings(self)
+
+	/// Returns random fill fraction and seed from cave canvas group
+	pub fn get_cave_canvas_fill_settings(&self) -> (f64, Option<u64>) {
+		self.ux_cave_gen_tab.get_cave_canvas_fill_settings()
+	}//end get_cave_canvas_fill_settings(self)
+
+	/// Returns the minimum floor region size from cave canvas group
+	pub fn get_cave_canvas_min_region_size(&self) -> usize {
+		self.ux_cave_gen_tab.get_cave_canvas_min_region_size()
+	}//end get_cave_canvas_min_region_size(self)
+
+	/// Displays seed in cave canvas group
+	pub fn set_cave_canvas_seed(&mut self, seed: u64) {
+		self.ux_cave_gen_tab.set_cave_canvas_seed(seed);
+	}//end set_cave_canvas_seed(self, seed)
+
+	/// Returns the minimum connectivity to enforce from room canvas group
+	pub fn get_room_canvas_connectivity_limit(&self) -> usize {
+		self.ux_room_gen_tab.get_connectivity_limit()
+	}//end get_room_canvas_connectivity_limit(self)
+
+	/// Returns the zone settings entered in room canvas group, or Err if the zone types aren't valid
+	pub fn get_room_canvas_zone_settings(&self) -> Result<ZoneSettings, String> {
+		self.ux_room_gen_tab.get_zone_settings()
+	}//end get_room_canvas_zone_settings(self)
+
+	/// Returns the chance of shortcut doors becoming secret from room canvas group
+	pub fn get_room_canvas_secret_door_chance(&self) -> f64 {
+		self.ux_room_gen_tab.get_secret_door_chance()
+	}//end get_room_canvas_secret_door_chance(self)
+
+	/// Returns the number of loops to aim for on the level at level_index from room canvas group, or Err if the targets aren't valid
+	pub fn get_room_canvas_loop_target(&self, level_index: usize) -> Result<usize, String> {
+		self.ux_room_gen_tab.get_loop_target(level_index)
+	}//end get_room_canvas_loop_target(self, level_index)
+
+	/// Returns how room starts should be spread out from room canvas group, and the distance below which starts are merged
+	pub fn get_room_canvas_start_settings(&self) -> (StartPlacement, f64) {
+		self.ux_room_gen_tab.get_start_settings()
+	}//end get_room_canvas_start_settings(self)
+
+	/// Returns the limits on the size and shape of rooms from room canvas group
+	pub fn get_room_canvas_room_limits(&self) -> RoomLimits {
+		self.ux_room_gen_tab.get_room_limits()
+	}//end get_room_canvas_room_limits(self)
+
+	/// Returns the seed entered in room canvas group, or None if it isn't valid
+	pub fn get_room_canvas_seed(&self) -> Option<u64> {
+		self.ux_room_gen_tab.get_seed()
+	}//end get_room_canvas_seed(self)
+
+	/// Displays seed in room canvas group
+	pub fn set_room_canvas_seed(&mut self, seed: u64) {
+		self.ux_room_gen_tab.set_seed(seed);
+	}//end set_room_canvas_seed(self, seed)
+
+	/// Returns true if CA processes should run on every level of the cave canvas
+	pub fn get_cave_canvas_all_levels(&self) -> bool {
+		self.ux_cave_gen_tab.get_cave_canvas_all_levels()
+	}//end get_cave_canvas_all_levels(self)
+
+	/// Returns true if room generation steps should run on every level of the room canvas
+	pub fn get_room_canvas_all_levels(&self) -> bool {
+		self.ux_room_gen_tab.get_all_levels()
+	}//end get_room_canvas_all_levels(self)
+
+	/// Gets every level of the cave canvas, with the current level taken from the canvas
+	pub fn get_cave_canvas_levels(&self) -> Option<LevelStack> {
+		self.ux_cave_gen_tab.get_level_stack()
+	}//end get_cave_canvas_levels(self)
+
+	/// Gets every level of the room canvas, with the current level taken from the canvas
+	pub fn get_room_canvas_levels(&self) -> Option<LevelStack> {
+		self.ux_room_gen_tab.get_level_stack()
+	}//end get_room_canvas_levels(self)
+
+	/// Stores levels in the cave canvas group and shows the current one
+	pub fn set_cave_canvas_levels(&mut self, level_stack: &LevelStack) {
+		self.ux_cave_gen_tab.set_level_stack(level_stack);
+	}//end set_cave_canvas_levels(self, level_stack)
+
+	/// Stores levels in the room canvas group and shows the current one
+	pub fn set_room_canvas_levels(&mut self, level_stack: &LevelStack) {
+		self.ux_room_gen_tab.set_level_stack(level_stack);
+	}//end set_room_canvas_levels(self, level_stack)
+
+	/// Switches the cave canvas to the level offset levels above or below the current one
+	pub fn change_cave_canvas_level(&mut self, offset: isize) {
+		self.ux_cave_gen_tab.change_level(offset);
+	}//end change_cave_canvas_level(self, offset)
+
+	/// Switches the room canvas to the level offset levels above or below the current one
+	pub fn change_room_canvas_level(&mut self, offset: isize) {
+		self.ux_room_gen_tab.change_level(offset);
+	}//end change_room_canvas_level(self, offset)
+
+	/// Gathers canvases and settings from every tab into a project that can be saved.
+	pub fn get_project(&self) -> Project {
+		Project::new(self.ux_cave_gen_tab.get_project_data(), self.ux_room_gen_tab.get_project_data())
+	}//end get_project(self)
+
+	/// Sets canvases and settings in every tab from a loaded project.
+	pub fn load_project(&mut self, project: &Project) {
+		self.ux_cave_gen_tab.load_project_data(&project.cave);
+		self.ux_room_gen_tab.load_project_data(&project.room);
+	}//end load_project(self, project)
+
+	/// Loads an image onto the cave canvas, with each square covering square_size pixels of the image.
+	pub fn import_cave_canvas_image(&mut self, path: &Path, square_size: usize) -> Result<(), String> {
+		self.ux_cave_gen_tab.import_image(path, square_size)
+	}//end import_cave_canvas_image(self, path, square_size)
+
+	/// Loads an image onto the room canvas, with each square covering square_size pixels of the image.
+	pub fn import_room_canvas_image(&mut self, path: &Path, square_size: usize) -> Result<(), String> {
+		self.ux_room_gen_tab.import_image(path, square_size)
+	}//end import_room_canvas_image(self, path, square_size)
+
 	pub fn get_cave_gen_stairs_selected(&self) -> Vec<String> {
 		self.ux_cave_gen_tab.get_cave_gen_stairs_selected()
 	}//end get_cave_gen_stairs_selected(self)
@@ -363,6 +485,16 @@ impl GUI {
 		self.ux_room_gen_tab.remove_stairs_selected()
 	}
 
+	/// Sets where the selected stairs in cave canvas group lead
+	pub fn set_cave_gen_stairs_selected_link(&mut self, direction: StairDirection, target_level: Option<usize>) {
+		self.ux_cave_gen_tab.set_cave_gen_stairs_selected_link(direction, target_level);
+	}//end set_cave_gen_stairs_selected_link(self, direction, target_level)
+
+	/// Sets where the selected stairs in room canvas group lead
+	pub fn set_room_gen_stairs_selected_link(&mut self, direction: StairDirection, target_level: Option<usize>) {
+		self.ux_room_gen_tab.set_stairs_selected_link(direction, target_level);
+	}//end set_room_gen_stairs_selected_link(self, direction, target_level)
+
 	/// Displays message to user, asking them yes or no.  
 	/// If user answers yes, then returns true.  
 	/// If user answers no or closes dialog early, returns false.
@@ -397,6 +529,122 @@ impl GUI {
 		} else { if dialog.error_message().unwrap_or_default() != "No error" {println!("{}", dialog.error_message().unwrap_or_default());} None}
 	}//end save_img_dialog
 
+	/// Displays dialog for getting a user to pick a theme file for image export.
+	pub fn open_theme_dialog() -> Option<PathBuf> {
+		let mut dialog = dialog::NativeFileChooser::new(dialog::FileDialogType::BrowseFile);
+		dialog.set_filter("*.json");
+		dialog.set_title("Open Theme");
+		dialog.show();
+		GUI::chosen_path(&dialog)
+	}//end open_theme_dialog
+
+	/// Displays dialog for getting a user to give a path where they'll save an SVG map.
+	pub fn save_svg_dialog() -> Option<PathBuf> {
+		let mut dialog = dialog::NativeFileChooser::new(dialog::FileDialogType::BrowseSaveFile);
+		dialog.set_filter("*.svg");
+		dialog.set_option(dialog::FileDialogOptions::SaveAsConfirm.union(dialog::FileDialogOptions::NewFolder));
+		dialog.set_title("Export SVG Map");
+		dialog.show();
+		GUI::chosen_path(&dialog)
+	}//end save_svg_dialog
+
+	/// Displays dialog for getting a user to give a path where they'll save a Tiled map.
+	pub fn save_tiled_dialog(file_extension: &str) -> Option<PathBuf> {
+		let mut dialog = dialog::NativeFileChooser::new(dialog::FileDialogType::BrowseSaveFile);
+		dialog.set_filter(&format!("*.{}", file_extension));
+		dialog.set_option(dialog::FileDialogOptions::SaveAsConfirm.union(dialog::FileDialogOptions::NewFolder));
+		dialog.set_title("Export Tiled Map");
+		dialog.show();
+		GUI::chosen_path(&dialog)
+	}//end save_tiled_dialog
+
+	/// Displays dialog for getting a user to pick a file with tileset settings for Tiled export.
+	pub fn open_tiled_settings_dialog() -> Option<PathBuf> {
+		let mut dialog = dialog::NativeFileChooser::new(dialog::FileDialogType::BrowseFile);
+		dialog.set_filter("*.json");
+		dialog.set_title("Open Tileset Settings");
+		dialog.show();
+		GUI::chosen_path(&dialog)
+	}//end open_tiled_settings_dialog
+
+	/// Displays dialog for getting a user to give a path where they'll save a text map.
+	pub fn save_text_dialog() -> Option<PathBuf> {
+		let mut dialog = dialog::NativeFileChooser::new(dialog::FileDialogType::BrowseSaveFile);
+		dialog.set_filter("*.txt");
+		dialog.set_option(dialog::FileDialogOptions::SaveAsConfirm.union(dialog::FileDialogOptions::NewFolder));
+		dialog.set_title("Export Text Map");
+		dialog.show();
+		GUI::chosen_path(&dialog)
+	}//end save_text_dialog
+
+	/// Displays dialog for getting a user to pick a file with the characters to use for text export.
+	pub fn open_char_map_dialog() -> Option<PathBuf> {
+		let mut dialog = dialog::NativeFileChooser::new(dialog::FileDialogType::BrowseFile);
+		dialog.set_filter("*.json");
+		dialog.set_title("Open Character Map");
+		dialog.show();
+		GUI::chosen_path(&dialog)
+	}//end open_char_map_dialog
+
+	/// Displays dialog for getting a user to give a path where they'll save a project.
+	pub fn save_project_dialog() -> Option<PathBuf> {
+		let mut dialog = dialog::NativeFileChooser::new(dialog::FileDialogType::BrowseSaveFile);
+		dialog.set_filter("*.json");
+		dialog.set_option(dialog::FileDialogOptions::SaveAsConfirm.union(dialog::FileDialogOptions::NewFolder));
+		dialog.set_title("Save Project");
+		dialog.show();
+		GUI::chosen_path(&dialog)
+	}//end save_project_dialog
+
+	/// Displays dialog for getting a user to pick a project file to open.
+	pub fn open_project_dialog() -> Option<PathBuf> {
+		let mut dialog = dialog::NativeFileChooser::new(dialog::FileDialogType::BrowseFile);
+		dialog.set_filter("*.json");
+		dialog.set_title("Open Project");
+		dialog.show();
+		GUI::chosen_path(&dialog)
+	}//end open_project_dialog
+
+	/// Displays dialog for getting a user to pick an image file to import.
+	pub fn open_img_dialog() -> Option<PathBuf> {
+		let mut dialog = dialog::NativeFileChooser::new(dialog::FileDialogType::BrowseFile);
+		dialog.set_filter("Images\t*.{png,jpg,jpeg,bmp,webp}");
+		dialog.set_title("Import Image");
+		dialog.show();
+		GUI::chosen_path(&dialog)
+	}//end open_img_dialog
+
+	/// Asks the user to type in a whole number, starting with default_value.  
+	/// Returns None if the user cancels or doesn't enter a whole number.
+	pub fn number_dialog(prompt: &str, default_value: usize) -> Option<usize> {
+		match dialog::input_default(prompt, &default_value.to_string()) {
+			Some(text) => text.trim().parse().ok(),
+			None => None,
+		}//end matching dialog result
+	}//end number_dialog(prompt, default_value)
+
+	/// Asks the user which way some stairs lead.  
+	/// Returns None if the user closes the dialog without choosing.
+	pub fn stair_direction_dialog() -> Option<StairDirection> {
+		match dialog::choice2_default("Where do the selected stairs lead?", "Up", "Down", "Outside") {
+			Some(0) => Some(StairDirection::Up),
+			Some(1) => Some(StairDirection::Down),
+			Some(2) => Some(StairDirection::Outside),
+			_ => None,
+		}//end matching dialog result
+	}//end stair_direction_dialog()
+
+	/// Gets the path chosen in a file dialog that has already been shown, or None if nothing was chosen.
+	fn chosen_path(dialog: &dialog::NativeFileChooser) -> Option<PathBuf> {
+		let error_message = dialog.error_message().unwrap_or_default();
+		if error_message.replace("No error", "").is_empty() && !dialog.filename().as_os_str().is_empty() {
+			Some(dialog.filename())
+		} else if error_message != "No error" {
+			println!("{}", error_message);
+			None
+		} else { None }
+	}//end chosen_path(dialog)
+
 	/// Displays dialog asking user to choose between two options or cancel.
 	/// If the user chooses one of the options, then the text of that option
 	/// will be returned.
@@ -1,9 +1,10 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, path::Path, rc::Rc};
 
-use fltk::{app::{self, Sender}, button::Button, dialog, draw::{draw_line, draw_point, set_draw_color, set_line_style, LineStyle}, enums::{Align, Color, Event, FrameType}, frame::Frame, group::{Flex, FlexType, Group, Scroll, Tile}, prelude::{DisplayExt, GroupExt, ImageExt, SurfaceDevice, ValuatorExt, WidgetBase, WidgetExt}, surface::ImageSurface, text::{TextBuffer, TextDisplay, TextEditor}, valuator::{Counter, CounterType}, widget_extends};
-use nice_map_generator::{cellular_automata::CAC, squares::SquareGrid};
+use fltk::{app::{self, Sender}, button::{Button, CheckButton}, draw::{draw_line, draw_point, set_draw_color, set_line_style, LineStyle}, enums::{Align, Color, Event, FrameType}, frame::Frame, group::{Flex, FlexType, Group, Scroll, Tile}, input::Input, menu::Choice, prelude::{DisplayExt, GroupExt, ImageExt, InputExt, MenuExt, SurfaceDevice, ValuatorExt, WidgetBase, WidgetExt}, surface::ImageSurface, text::{TextBuffer, TextDisplay, TextEditor}, valuator::{Counter, CounterType}, widget_extends};
+use nice_map_generator::{cellular_automata::{Boundary, Neighborhood, CAC}, import::{squareularization_get_dominant_color, squareularization_split_img_to_squares, squareularize_image_file}, levels::LevelStack, project::{CaveProject, CaveSettings}, squares::{CellKind, Palette, SquareGrid}, stairs::{StairDirection, StairLink}};
+use rand::Rng;
 
-use super::gui_utils::{get_default_tab_padding, squareularization_color_square, squareularization_color_squares, squareularization_get_dominant_color, squareularization_get_rgb_pixels, squareularization_split_img_to_squares, ux_squareularize_canvas, ListBox, SquareStairDisplay};
+use super::gui_utils::{get_default_tab_padding, squareularization_color_square, squareularization_color_squares, squareularization_get_rgb_pixels, ux_squareularize_canvas, keep_stair_links, ListBox, SquareStairDisplay};
 
 /// # enum DrawState
 /// This enum represents the current drawing state for the canvas.
@@ -28,6 +29,8 @@ pub struct CaveGenGroup {
 	ux_cave_canvas_brush_size: Rc<RefCell<i32>>,
 	/// contains last SquareGrid, plus vec with row, col coords of all stairs we found
 	ux_last_square_grid: Rc<RefCell<Option<SquareGrid>>>,
+	/// every level, as of the last time levels were switched. The level shown on the canvas is kept in [ux_last_square_grid]
+	ux_level_stack: Option<LevelStack>,
 	ux_level_cur_buf: TextBuffer,
 	ux_level_tot_buf: TextBuffer,
 	ux_squares_width_counter: Counter,
@@ -37,6 +40,13 @@ pub struct CaveGenGroup {
 	ux_ca_neighborhood_size_counter: Counter,
 	ux_ca_neighborhood_thresh_counter: Rc<RefCell<Counter>>,
 	ux_ca_generations_to_run_counter: Counter,
+	ux_ca_neighborhood_shape_choice: Choice,
+	ux_ca_boundary_choice: Choice,
+	ux_ca_rule_schedule_input: Input,
+	ux_ca_fill_percent_counter: Counter,
+	ux_ca_min_region_size_counter: Counter,
+	ux_ca_seed_input: Input,
+	ux_ca_all_levels_check: CheckButton,
 	ux_stairs_list: Rc<RefCell<ListBox<SquareStairDisplay>>>,
 	ux_wall_frame_ref: Rc<RefCell<Frame>>,
 	ux_floor_frame_ref: Rc<RefCell<Frame>>,
@@ -54,6 +64,7 @@ impl Default for CaveGenGroup {
 			ux_cave_canvas_draw_state: Rc::from(RefCell::from(DrawState::Disabled)),
 			ux_cave_canvas_brush_size: Rc::from(RefCell::from(1)),
 			ux_last_square_grid: Rc::from(RefCell::from(None)),
+			ux_level_stack: None,
 			ux_level_cur_buf: Default::default(),
 			ux_level_tot_buf: Default::default(),
 			ux_squares_width_counter: Default::default(),
@@ -63,6 +74,13 @@ impl Default for CaveGenGroup {
 			ux_ca_neighborhood_size_counter: Default::default(),
 			ux_ca_neighborhood_thresh_counter: Default::default(),
 			ux_ca_generations_to_run_counter: Default::default(),
+			ux_ca_neighborhood_shape_choice: Default::default(),
+			ux_ca_boundary_choice: Default::default(),
+			ux_ca_rule_schedule_input: Default::default(),
+			ux_ca_fill_percent_counter: Default::default(),
+			ux_ca_min_region_size_counter: Default::default(),
+			ux_ca_seed_input: Default::default(),
+			ux_ca_all_levels_check: Default::default(),
 			ux_stairs_list: Rc::from(RefCell::from(ListBox::new(0, 0, 10, 10, 10))),
 			ux_wall_frame_ref: Rc::from(RefCell::from(Frame::default())),
 			ux_floor_frame_ref: Rc::from(RefCell::from(Frame::default())),
@@ -233,15 +251,17 @@ impl CaveGenGroup {
 		ux_level_total_label_txt.set_buffer(self.ux_level_tot_buf.clone());
 		ux_interior_level_number_hor_flex_1.add(&ux_level_total_label_txt);
 		
-		let ux_level_down_btn = Button::default()
+		let mut ux_level_down_btn = Button::default()
 			.with_pos(ux_level_label_frame.x(), ux_level_label_frame.y() + ux_level_label_frame.height())
 			.with_size(25, 25)
 			.with_label("@line");
+		ux_level_down_btn.emit(msg_sender.clone(), "CaveGen:Level:Down".to_string());
 		ux_interior_level_number_hor_flex_2.add(&ux_level_down_btn);
-		let ux_level_up_btn = Button::default()
+		let mut ux_level_up_btn = Button::default()
 			.with_pos(ux_level_down_btn.x() + ux_level_down_btn.width() , ux_level_down_btn.y())
 			.with_size(25,25)
 			.with_label("@+");
+		ux_level_up_btn.emit(msg_sender.clone(), "CaveGen:Level:Up".to_string());
 		ux_interior_level_number_hor_flex_2.add(&ux_level_up_btn);
 
 		// stuff for setting size/resolution of squares
@@ -533,6 +553,24 @@ impl CaveGenGroup {
 		let mut ux_interior_flex_3 = Flex::default().with_type(FlexType::Row);
 		ux_interior_flex_3.end();
 		ux_exterior_flex.add(&ux_interior_flex_3);
+		let mut ux_interior_flex_4 = Flex::default().with_type(FlexType::Row);
+		ux_interior_flex_4.end();
+		ux_exterior_flex.add(&ux_interior_flex_4);
+		let mut ux_interior_flex_5 = Flex::default().with_type(FlexType::Row);
+		ux_interior_flex_5.end();
+		ux_exterior_flex.add(&ux_interior_flex_5);
+		let mut ux_interior_flex_6 = Flex::default().with_type(FlexType::Row);
+		ux_interior_flex_6.end();
+		ux_exterior_flex.add(&ux_interior_flex_6);
+		let mut ux_interior_flex_7 = Flex::default().with_type(FlexType::Row);
+		ux_interior_flex_7.end();
+		ux_exterior_flex.add(&ux_interior_flex_7);
+		let mut ux_interior_flex_8 = Flex::default().with_type(FlexType::Row);
+		ux_interior_flex_8.end();
+		ux_exterior_flex.add(&ux_interior_flex_8);
+		let mut ux_interior_flex_9 = Flex::default().with_type(FlexType::Row);
+		ux_interior_flex_9.end();
+		ux_exterior_flex.add(&ux_interior_flex_9);
 
 		// add the counter controls
 		let ux_neighbor_closeness_label = Frame::default().with_label("CA Neighbor Closeness");
@@ -570,6 +608,36 @@ impl CaveGenGroup {
 		ux_interior_flex_3.add(&ux_iterations_counter);
 		self.ux_ca_generations_to_run_counter = ux_iterations_counter;
 
+		let ux_neighborhood_shape_label = Frame::default().with_label("Neighborhood Shape");
+		ux_interior_flex_4.add(&ux_neighborhood_shape_label);
+
+		let mut ux_neighborhood_shape_choice = Choice::default();
+		ux_neighborhood_shape_choice.add_choice("Moore (Square)");
+		ux_neighborhood_shape_choice.add_choice("Von Neumann (Diamond)");
+		ux_neighborhood_shape_choice.set_value(0);
+		ux_interior_flex_4.add(&ux_neighborhood_shape_choice);
+		self.ux_ca_neighborhood_shape_choice = ux_neighborhood_shape_choice;
+
+		let ux_boundary_label = Frame::default().with_label("Edge Handling");
+		ux_interior_flex_5.add(&ux_boundary_label);
+
+		let mut ux_boundary_choice = Choice::default();
+		ux_boundary_choice.add_choice("Wall");
+		ux_boundary_choice.add_choice("Floor");
+		ux_boundary_choice.add_choice("Wrap Around");
+		ux_boundary_choice.add_choice("Mirror");
+		ux_boundary_choice.set_value(0);
+		ux_interior_flex_5.add(&ux_boundary_choice);
+		self.ux_ca_boundary_choice = ux_boundary_choice;
+
+		let ux_rule_schedule_label = Frame::default().with_label("Rule Schedule");
+		ux_interior_flex_6.add(&ux_rule_schedule_label);
+
+		let mut ux_rule_schedule_input = Input::default();
+		ux_rule_schedule_input.set_tooltip("Rules in B/S notation with generation counts, such as B5678/S45678:4; B5678/S5678:2\nLeave blank to use the neighbor threshold.");
+		ux_interior_flex_6.add(&ux_rule_schedule_input);
+		self.ux_ca_rule_schedule_input = ux_rule_schedule_input;
+
 		// add handler to counters to ensure bounds are updated
 		let ux_neighbor_threshold_ref = self.ux_ca_neighborhood_thresh_counter.clone();
 		ux_neighbor_closeness_counter.handle({
@@ -591,12 +659,73 @@ impl CaveGenGroup {
 		});
 
 		self.ux_ca_neighborhood_size_counter = ux_neighbor_closeness_counter;
-		
+
+		// controls for random initial fill
+		let ux_fill_percent_label = Frame::default().with_label("Random Wall Fill %");
+		ux_interior_flex_7.add(&ux_fill_percent_label);
+
+		let mut ux_fill_percent_counter = Counter::default();
+		ux_fill_percent_counter.set_value(50.0);
+		ux_fill_percent_counter.set_bounds(0.0, 100.0);
+		ux_fill_percent_counter.set_precision(0);
+		ux_fill_percent_counter.set_step(1.0, 5);
+		ux_fill_percent_counter.set_type(CounterType::Normal);
+		ux_interior_flex_7.add(&ux_fill_percent_counter);
+		self.ux_ca_fill_percent_counter = ux_fill_percent_counter;
+
+		let ux_seed_label = Frame::default().with_label("Seed");
+		ux_interior_flex_8.add(&ux_seed_label);
+		ux_interior_flex_8.fixed(&ux_seed_label, 70);
+
+		let mut ux_seed_input = Input::default();
+		ux_seed_input.set_value(&rand::thread_rng().gen::<u64>().to_string());
+		ux_seed_input.set_tooltip("Seed used for random fill. Re-enter a seed to reproduce a fill.");
+		ux_interior_flex_8.add(&ux_seed_input);
+
+		let mut ux_new_seed_btn = Button::default().with_label("New Seed");
+		ux_interior_flex_8.add(&ux_new_seed_btn);
+		ux_interior_flex_8.fixed(&ux_new_seed_btn, 90);
+		ux_new_seed_btn.set_callback({
+			let mut seed_input_ref = ux_seed_input.clone();
+			move |_| {
+				seed_input_ref.set_value(&rand::thread_rng().gen::<u64>().to_string());
+			}//end closure
+		});
+		self.ux_ca_seed_input = ux_seed_input;
+
+		let mut ux_all_levels_check = CheckButton::default().with_label("Apply to All Levels");
+		ux_all_levels_check.set_tooltip("Run random fill, generations, and region cleanup on every level instead of just the current one.");
+		ux_exterior_flex.add(&ux_all_levels_check);
+		self.ux_ca_all_levels_check = ux_all_levels_check;
+
+		let mut ux_random_fill_btn = Button::default().with_label("Random Fill");
+		ux_random_fill_btn.emit(msg_sender.clone(), "CaveGen:CA:RandomFill".to_string());
+		ux_exterior_flex.add(&ux_random_fill_btn);
+
 
 		// button for actually starting generation
 		let mut ux_run_ca_btn = Button::default().with_label("Run Generation");
 		ux_run_ca_btn.emit(msg_sender.clone(), "CaveGen:CA:RunGeneration".to_string());
 		ux_exterior_flex.add(&ux_run_ca_btn);
+
+		// controls for cleaning up disconnected regions afterwards
+		let mut ux_min_region_size_counter = Counter::default();
+		ux_min_region_size_counter.set_value(10.0);
+		ux_min_region_size_counter.set_bounds(1.0, 1000.0);
+		ux_min_region_size_counter.set_precision(0);
+		ux_min_region_size_counter.set_step(1.0, 10);
+		ux_min_region_size_counter.set_type(CounterType::Normal);
+		ux_min_region_size_counter.set_tooltip("Floor regions smaller than this without stairs are filled in with walls.");
+		ux_interior_flex_9.add(&ux_min_region_size_counter);
+		self.ux_ca_min_region_size_counter = ux_min_region_size_counter;
+
+		let mut ux_remove_regions_btn = Button::default().with_label("Remove Small Regions");
+		ux_remove_regions_btn.emit(msg_sender.clone(), "CaveGen:Regions:RemoveSmall".to_string());
+		ux_interior_flex_9.add(&ux_remove_regions_btn);
+
+		let mut ux_connect_regions_btn = Button::default().with_label("Connect Regions");
+		ux_connect_regions_btn.emit(msg_sender.clone(), "CaveGen:Regions:Connect".to_string());
+		ux_interior_flex_9.add(&ux_connect_regions_btn);
 	}//end initialize_cellular_automata_settings()
 
 	/// This function, as a helper function for initialize(), sets up widgets for level connections
@@ -622,27 +751,18 @@ impl CaveGenGroup {
 
 		let mut ux_level_connection_add_btn = Button::default()
 			.with_size(ux_exterior_flex.width(), 50)
-			.with_label("Add Level Connection");
+			.with_label("Auto-Pair Stairs");
+		ux_level_connection_add_btn.set_tooltip("Links unlinked stairs to stairs at the same spot on the level above or below.");
 		ux_exterior_flex.add(&ux_level_connection_add_btn);
 
-		ux_level_connection_add_btn.set_callback({
-			move |b| {
-				// dialog::choice2(b.x(), b.y(), "txt", "yes", "no", "");
-				// dialog::input(b.x(), b.y(), "Add stairs", "placeholder");
-				dialog::message(b.x(), b.y(), "placeholder, not yet implemented");
-			}//end moving for callback
-		});
+		ux_level_connection_add_btn.emit(msg_sender.clone(), String::from("CaveGen:Stairs:AutoPair"));
 
 		let mut ux_level_connection_edit_btn = Button::default()
 			.with_size(ux_exterior_flex.width(), 50)
 			.with_label("Edit Level Connection");
 		ux_exterior_flex.add(&ux_level_connection_edit_btn);
 
-		ux_level_connection_edit_btn.set_callback({
-			move |b| {
-				dialog::message(b.x(), b.y(), "Placeholder, not yet implemented");
-			}//end moving for callback
-		});
+		ux_level_connection_edit_btn.emit(msg_sender.clone(), String::from("CaveGen:Stairs:Edit"));
 
 		let mut ux_level_connection_remove_btn = Button::default()
 			.with_size(ux_exterior_flex.width(), 50)
@@ -651,10 +771,19 @@ impl CaveGenGroup {
 
 		ux_level_connection_remove_btn.emit(msg_sender.clone(), String::from("CaveGen:Stairs:Remove"));
 
+		let mut ux_level_connection_check_btn = Button::default()
+			.with_size(ux_exterior_flex.width(), 50)
+			.with_label("Check Stairs");
+		ux_level_connection_check_btn.set_tooltip("Lists stairs on every level that don't lead anywhere, don't line up, or don't lead back.");
+		ux_exterior_flex.add(&ux_level_connection_check_btn);
+
+		ux_level_connection_check_btn.emit(msg_sender.clone(), String::from("CaveGen:Stairs:Check"));
+
 		ux_exterior_flex.fixed(&ux_level_connection_settings_section_label, 50);
 		ux_exterior_flex.fixed(&ux_level_connection_add_btn, 50);
 		ux_exterior_flex.fixed(&ux_level_connection_edit_btn, 50);
 		ux_exterior_flex.fixed(&ux_level_connection_remove_btn, 50);
+		ux_exterior_flex.fixed(&ux_level_connection_check_btn, 50);
 	}//end initialize_level_connection_settings(&mut self, ux_exterior_flex)
 
 	pub fn get_cave_gen_stairs_selected(&self) -> Vec<String> {
@@ -664,6 +793,25 @@ impl CaveGenGroup {
 		stairs_list_borrow.get_selected_elements().into_iter().map(|val| format!("{}", val)).collect()
 	}//end get_cave_gen_stairs-selected(self)(
 
+	/// Sets where the selected stairs lead.
+	pub fn set_cave_gen_stairs_selected_link(&mut self, direction: StairDirection, target_level: Option<usize>) {
+		let stairs_list_ref = &self.ux_stairs_list;
+		let stairs_list_ref_clone = stairs_list_ref.clone();
+		let mut stairs_list_borrow = stairs_list_ref_clone.as_ref().borrow_mut();
+		stairs_list_borrow.update_selected_elements(|stair| SquareStairDisplay {
+			link: Some(StairLink::new(stair.row_idx, stair.col_idx, direction, target_level)),
+			..stair.clone()
+		});
+	}//end set_cave_gen_stairs_selected_link(self, direction, target_level)
+
+	/// Gets links for every stair in the list that has one.
+	fn get_stair_links(&self) -> Vec<StairLink> {
+		let stairs_list_ref = &self.ux_stairs_list;
+		let stairs_list_ref_clone = stairs_list_ref.clone();
+		let stairs_list_borrow = stairs_list_ref_clone.as_ref().borrow();
+		stairs_list_borrow.get_elements().into_iter().filter_map(|stair| stair.link).collect()
+	}//end get_stair_links(self)
+
 	/// Removes selected stairs from the list and canvas
 	pub fn remove_cave_gen_stairs_selected(&mut self) {
 		let last_square_stair_ref = &self.ux_last_square_grid;
@@ -679,7 +827,7 @@ impl CaveGenGroup {
 				for selected_element in stairs_list_selected_elements {
 					match squares.get_mut(&selected_element.row_idx, &selected_element.col_idx) {
 						Some(this_square) => {
-							this_square.set_color((0,0,0));
+							this_square.set_kind(CellKind::Wall);
 							squares_to_recolor.push(this_square.clone());
 						},
 						None => println!("Couldn't access square {:?} while removing stairs from list.", selected_element)
@@ -688,7 +836,7 @@ impl CaveGenGroup {
 				let canvas_ref = &self.ux_cave_canvas_image;
 				let canvas_ref_clone = canvas_ref.clone();
 				let canvas_borrow = canvas_ref_clone.borrow();
-				squareularization_color_square(&canvas_borrow, squares_to_recolor.iter());
+				squareularization_color_square(&canvas_borrow, squares_to_recolor.iter(), &Palette::cave());
 				self.ux_cave_canvas_frame.redraw();
 				stairs_list_borrow.remove_selected_elements()
 			},
@@ -789,7 +937,7 @@ impl CaveGenGroup {
 						let pixel_scale = pixel_scale as usize;
 						let mut last_square_grid_clone = last_square_grid.as_ref().borrow_mut();
 						let mut stairs_list_ref = stairs_list_ref_clone.as_ref().borrow_mut();
-						if let Some(squares) = ux_squareularize_canvas(&surface, Some(&CAC::colors_vec()), &pixel_scale, &sub_pixel_scale_ref) {
+						if let Some(squares) = ux_squareularize_canvas(&surface, &Palette::cave(), Some(&CAC::colors_vec()), &pixel_scale, &sub_pixel_scale_ref) {
 							let stairs_list = CaveGenGroup::ux_get_stair_coord_list(&squares);
 							CaveGenGroup::ux_update_stairs_list(stairs_list.clone(), &mut stairs_list_ref);
 							*last_square_grid_clone = Some(squares);
@@ -810,21 +958,22 @@ impl CaveGenGroup {
 		self.ux_cave_canvas_frame.redraw();
 	}//end update_image_size_and_height(prev_w, prev_h)
 
-	fn ux_update_stairs_list(stairs_list: Vec<SquareStairDisplay>, stairs_list_box: &mut ListBox<SquareStairDisplay>) {
+	fn ux_update_stairs_list(mut stairs_list: Vec<SquareStairDisplay>, stairs_list_box: &mut ListBox<SquareStairDisplay>) {
+		keep_stair_links(&mut stairs_list, &stairs_list_box.get_elements());
 		stairs_list_box.clear_elements();
 		stairs_list_box.set_elements(stairs_list);
 	}//end ux_update_stairs_list()
 
 	/// Gets a (row, col) list of coordinates pointing to every square in squares which
-	/// is classified as a stair via color and CA::CAC::classify().
+	/// is classified as a stair via its kind and CA::CAC::from_kind().
 	fn ux_get_stair_coord_list(squares: &SquareGrid) -> Vec<SquareStairDisplay> {
 		let mut stairs_list = Vec::new();
 		for row in 0..*squares.rows() {
 			for col in 0..*squares.cols() {
 				match squares.get(&row, &col) {
 					Some(square) => {
-						if CAC::Stairs == CAC::classify(*square.color()) {
-							stairs_list.push(SquareStairDisplay {square: *square, row_idx: row, col_idx: col});
+						if CAC::Stairs == CAC::from_kind(*square.kind()) {
+							stairs_list.push(SquareStairDisplay {square: *square, row_idx: row, col_idx: col, link: None});
 						}//end if we found a stair
 					},
 					None => println!("Failed to get an index when counting stairs?"),
@@ -855,7 +1004,7 @@ impl CaveGenGroup {
 
 				match squareularization_split_img_to_squares(&img_width, &img_height, &square_width, &square_height) {
 					Some(mut squares) => {
-						squareularization_get_dominant_color(&mut squares, Some(&CAC::colors_vec()), &pixels, &img_width, &square_width, &square_height);
+						squareularization_get_dominant_color(&mut squares, &Palette::cave(), Some(&CAC::colors_vec()), &pixels, &img_width, &square_width, &square_height);
 						// let stairs = CaveGenGroup::ux_get_stair_coord_list(&squares);
 						let last_square_grid_and_stair_list_ref = &self.ux_last_square_grid;
 						let last_square_grid_and_stair_list_ref_clone = last_square_grid_and_stair_list_ref.clone();
@@ -898,7 +1047,7 @@ impl CaveGenGroup {
 		let canvas_ref_clone = canvas_ref.clone();
 		let canvas_borrow = canvas_ref_clone.borrow();
 
-		squareularization_color_squares(&canvas_borrow, squares,&false);
+		squareularization_color_squares(&canvas_borrow, squares, &Palette::cave(), &false);
 		self.ux_cave_canvas_frame.redraw();
 
 		// update last squareularization
@@ -933,6 +1082,169 @@ impl CaveGenGroup {
 		let iterations = self.ux_ca_generations_to_run_counter.value() as usize;
 		(size, thresh, iterations)
 	}//end get_cave_canvas_ca_settings()
+
+	/// gets CA rule settings for cave canvas.
+	/// Returns neighborhood shape, edge handling, and the rule schedule text, which is empty if the threshold should be used
+	pub fn get_cave_canvas_ca_rule_settings(&self) -> (Neighborhood, Boundary, String) {
+		let neighborhood = match self.ux_ca_neighborhood_shape_choice.value() {
+			1 => Neighborhood::VonNeumann,
+			_ => Neighborhood::Moore,
+		};
+		let boundary = match self.ux_ca_boundary_choice.value() {
+			1 => Boundary::Floor,
+			2 => Boundary::Wrap,
+			3 => Boundary::Mirror,
+			_ => Boundary::Wall,
+		};
+		(neighborhood, boundary, self.ux_ca_rule_schedule_input.value().trim().to_string())
+	}//end get_cave_canvas_ca_rule_settings()
+
+	/// gets random fill settings for cave canvas.
+	/// Returns fraction of squares to fill with walls, and the seed entered, or None if it isn't a valid seed
+	pub fn get_cave_canvas_fill_settings(&self) -> (f64, Option<u64>) {
+		let fraction = self.ux_ca_fill_percent_counter.value() / 100.0;
+		let seed = self.ux_ca_seed_input.value().trim().parse().ok();
+		(fraction, seed)
+	}//end get_cave_canvas_fill_settings()
+
+	/// gets the minimum size for floor regions to avoid being removed.
+	pub fn get_cave_canvas_min_region_size(&self) -> usize {
+		self.ux_ca_min_region_size_counter.value() as usize
+	}//end get_cave_canvas_min_region_size()
+
+	/// Displays seed so the user can see or re-enter it later.
+	pub fn set_cave_canvas_seed(&mut self, seed: u64) {
+		self.ux_ca_seed_input.set_value(&seed.to_string());
+	}//end set_cave_canvas_seed(self, seed)
+
+	/// Returns true if CA processes should run on every level instead of just the current one.
+	pub fn get_cave_canvas_all_levels(&self) -> bool {
+		self.ux_ca_all_levels_check.is_checked()
+	}//end get_cave_canvas_all_levels()
+
+	/// Gets every level, with the current level taken from the canvas.  
+	/// The number of levels comes from the total level box, so levels are added or dropped to match it.
+	/// If the canvas size has changed since levels were last switched, the other levels start out blank.
+	pub fn get_level_stack(&self) -> Option<LevelStack> {
+		let canvas_squares = self.get_last_squareularization()?;
+		let current_level = self.ux_level_cur_buf.text().trim().parse::<usize>().unwrap_or(1).max(1);
+		let total_levels = self.ux_level_tot_buf.text().trim().parse::<usize>().unwrap_or(1).max(current_level);
+		let mut level_stack = match &self.ux_level_stack {
+			Some(level_stack) if level_stack.fits(&canvas_squares) => level_stack.clone(),
+			_ => LevelStack::with_blank_levels(&canvas_squares, total_levels, CAC::Wall.kind()),
+		};
+		level_stack.set_level_count(total_levels, CAC::Wall.kind());
+		level_stack.set_current_index(current_level - 1).ok()?;
+		level_stack.set_level(current_level - 1, canvas_squares).ok()?;
+		level_stack.set_stair_links(current_level - 1, self.get_stair_links()).ok()?;
+		Some(level_stack)
+	}//end get_level_stack(self)
+
+	/// Stores every level from level_stack, and shows its current level on the canvas.  
+	/// If the levels are a different size than the canvas, the canvas is resized to fit them.
+	pub fn set_level_stack(&mut self, level_stack: &LevelStack) {
+		let current_squares = level_stack.current();
+		match self.get_last_squareularization() {
+			Some(canvas_squares) if LevelStack::dimensions_match(&canvas_squares, current_squares) => self.set_squareularization(current_squares),
+			_ => self.load_squareularization(current_squares),
+		}//end matching whether levels fit the canvas
+		self.show_stairs(current_squares, level_stack.stair_links(*level_stack.current_index()).unwrap_or(&Vec::new()));
+		self.ux_level_cur_buf.set_text(&(level_stack.current_index() + 1).to_string());
+		self.ux_level_tot_buf.set_text(&level_stack.len().to_string());
+		self.ux_level_stack = Some(level_stack.clone());
+	}//end set_level_stack(self, level_stack)
+
+	/// Switches the canvas to the level offset levels above or below the current one.  
+	/// The current canvas is kept in its level, and the switch stops at the first or last level.
+	pub fn change_level(&mut self, offset: isize) {
+		if let Some(mut level_stack) = self.get_level_stack() {
+			let new_level = level_stack.current_index().saturating_add_signed(offset).min(level_stack.len() - 1);
+			if let Err(msg) = level_stack.set_current_index(new_level) { println!("Couldn't change level: {}", msg); }
+			self.set_level_stack(&level_stack);
+		}//end if we can get the levels
+	}//end change_level(self, offset)
+
+	/// Gathers the levels and settings of this tab so they can be saved to a project.
+	pub fn get_project_data(&self) -> CaveProject {
+		let (neighborhood_size, neighborhood_threshold, generations_to_run) = self.get_cave_canvas_ca_settings();
+		let (neighborhood, boundary, rule_schedule) = self.get_cave_canvas_ca_rule_settings();
+		let (fill_fraction, seed) = self.get_cave_canvas_fill_settings();
+		CaveProject {
+			levels: self.get_level_stack(),
+			settings: CaveSettings {
+				neighborhood_size,
+				neighborhood_threshold,
+				generations_to_run,
+				neighborhood,
+				boundary,
+				rule_schedule,
+				fill_fraction,
+				seed,
+				min_region_size: self.get_cave_canvas_min_region_size(),
+			},
+		}//end struct construction
+	}//end get_project_data(self)
+
+	/// Sets the levels and settings of this tab from a loaded project.
+	pub fn load_project_data(&mut self, project: &CaveProject) {
+		let settings = &project.settings;
+		self.ux_ca_neighborhood_size_counter.set_value(settings.neighborhood_size as f64);
+		{
+			let mut ux_neighbor_threshold = self.ux_ca_neighborhood_thresh_counter.as_ref().borrow_mut();
+			ux_neighbor_threshold.set_maximum(Neighborhood::Moore.max_neighbors(settings.neighborhood_size) as f64);
+			ux_neighbor_threshold.set_value(settings.neighborhood_threshold as f64);
+		}
+		self.ux_ca_generations_to_run_counter.set_value(settings.generations_to_run as f64);
+		self.ux_ca_neighborhood_shape_choice.set_value(match settings.neighborhood {
+			Neighborhood::Moore => 0,
+			Neighborhood::VonNeumann => 1,
+		});
+		self.ux_ca_boundary_choice.set_value(match settings.boundary {
+			Boundary::Wall => 0,
+			Boundary::Floor => 1,
+			Boundary::Wrap => 2,
+			Boundary::Mirror => 3,
+		});
+		self.ux_ca_rule_schedule_input.set_value(&settings.rule_schedule);
+		self.ux_ca_fill_percent_counter.set_value(settings.fill_fraction * 100.0);
+		if let Some(seed) = settings.seed { self.set_cave_canvas_seed(seed); }
+		self.ux_ca_min_region_size_counter.set_value(settings.min_region_size as f64);
+
+		if let Some(level_stack) = &project.levels { self.set_level_stack(level_stack); }
+	}//end load_project_data(self, project)
+
+	/// Replaces the canvas with squares, resizing the canvas and square settings to fit them.  
+	/// Unlike [set_squareularization()], squares doesn't need to match the current canvas size.
+	pub fn load_squareularization(&mut self, squares: &SquareGrid) {
+		let pixel_diameter = match squares.get(&0, &0) { Some(square) => *square.width() / self.ux_sub_pixel_scale, None => 1 };
+		self.ux_squares_width_counter.set_value(*squares.cols() as f64);
+		self.ux_squares_height_counter.set_value(*squares.rows() as f64);
+		self.ux_squares_pixel_diameter_counter.set_value(pixel_diameter.max(1) as f64);
+		self.update_canvas();
+		self.set_squareularization(squares);
+		self.show_stairs(squares, &[]);
+	}//end load_squareularization(self, squares)
+
+	/// Replaces the stairs list with the stairs found in squares, linked according to links.
+	fn show_stairs(&self, squares: &SquareGrid, links: &[StairLink]) {
+		let mut stairs_list = CaveGenGroup::ux_get_stair_coord_list(squares);
+		for stair in stairs_list.iter_mut() {
+			stair.link = links.iter().find(|link| link.row == stair.row_idx && link.col == stair.col_idx).copied();
+		}//end linking each stair
+		let stairs_list_ref = &self.ux_stairs_list;
+		let stairs_list_ref_clone = stairs_list_ref.clone();
+		let mut stairs_list_borrow = stairs_list_ref_clone.as_ref().borrow_mut();
+		stairs_list_borrow.clear_elements();
+		stairs_list_borrow.set_elements(stairs_list);
+	}//end show_stairs(self, squares, links)
+
+	/// Loads the image at path onto the canvas, with each square covering square_size pixels of the image.  
+	/// Colors in the image are matched to the closest colors used on this canvas.
+	pub fn import_image(&mut self, path: &Path, square_size: usize) -> Result<(), String> {
+		let squares = squareularize_image_file(path, &Palette::cave(), Some(&CAC::colors_vec()), &square_size)?;
+		self.load_squareularization(&squares);
+		Ok(())
+	}//end import_image(self, path, square_size)
 }//end impl for CaveGenGroup
 
 widget_extends!(CaveGenGroup, Tile, ux_whole_tab_group);
\ No newline at end of file
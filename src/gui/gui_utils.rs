@@ -2,7 +2,7 @@ use std::slice::Iter;
 
 use fltk::{button::Button, draw::{draw_rect_fill, draw_rect_with_color}, enums::{Align, Color, Event, FrameType}, group::{self, Flex, Pack, Scroll}, prelude::{GroupExt, ImageExt, SurfaceDevice, WidgetBase, WidgetExt}, surface::ImageSurface, widget::Widget, widget_extends};
 use grid::Grid;
-use nice_map_generator::squares::{Square, SquareGrid};
+use nice_map_generator::{import::{squareularization_get_dominant_color, squareularization_split_img_to_squares}, squares::{Palette, Square, SquareGrid}, stairs::StairLink};
 
 /// # default window width
 /// gives the default width in pixels of the main window
@@ -269,6 +269,16 @@ impl<T: std::fmt::Display> ListBox<T> {
 		temp_vec
 	}//end get_non_selected_elements
 
+	/// Replaces the value of each selected element with the result of update.
+	pub fn update_selected_elements(&mut self, mut update: impl FnMut(&T) -> T) {
+		for elem in &mut self.elements {
+			if elem.is_selected() {
+				let new_val = update(elem.get_val());
+				elem.set_val(new_val);
+			}//end if this element is selected
+		}//end looping over elements
+	}//end update_selected_elements(self, update)
+
 	pub fn remove_selected_elements(&mut self) where T: Clone {
 		let non_selected_elements: Vec<T> = self.get_non_selected_elements().into_iter().cloned().collect();
 		self.set_elements(non_selected_elements);
@@ -376,30 +386,46 @@ pub struct SquareStairDisplay {
 	pub square: Square,
 	pub row_idx: usize,
 	pub col_idx: usize,
+	/// where these stairs lead, or None if they haven't been linked yet
+	pub link: Option<StairLink>,
 }//end struct SquareStairDisplay
 
 impl Default for SquareStairDisplay {
     fn default() -> Self {
-		Self { square: Square::new(0, 0, 0, 0), row_idx: Default::default(), col_idx: Default::default() }
+		Self { square: Square::new(0, 0, 0, 0), row_idx: Default::default(), col_idx: Default::default(), link: None }
 	}//end default()
 } //end struct SquareStairDisplay
 
 impl std::fmt::Display for SquareStairDisplay {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		write!(f, "Row: {}, Col: {}", self.row_idx, self.col_idx)
+		match &self.link {
+			Some(link) => write!(f, "Row: {}, Col: {}, {}", self.row_idx, self.col_idx, link),
+			None => write!(f, "Row: {}, Col: {}, Unlinked", self.row_idx, self.col_idx),
+		}//end matching whether stairs are linked
 	}//end fmt
 }//end impl Display for SquareStairDisplay
 
+/// Carries over links from old_stairs to stairs at the same coordinates in new_stairs which aren't linked yet.  
+/// This keeps links from being lost when the stairs list is rebuilt after drawing.
+pub fn keep_stair_links(new_stairs: &mut [SquareStairDisplay], old_stairs: &[&SquareStairDisplay]) {
+	for new_stair in new_stairs.iter_mut().filter(|new_stair| new_stair.link.is_none()) {
+		if let Some(old_stair) = old_stairs.iter().find(|old_stair| old_stair.row_idx == new_stair.row_idx && old_stair.col_idx == new_stair.col_idx) {
+			new_stair.link = old_stair.link;
+		}//end if these stairs were listed before
+	}//end looping over new stairs
+}//end keep_stair_links(new_stairs, old_stairs)
+
 	/// This function takes a mutable reference to an image surface and performs operations on it
 	/// to squareularize it.  
 	/// This function will return false if some part of this process is unsuccessful, or true if things went okay.  
 	/// It is recommended to call redraw() on the frame holding [canvas] after calling this method.
 	/// Arguments:  
 	/// - canvas - the image surface you want to squareularize
+	/// - palette - the colors used to tell which kind of square each color means, and to draw squares back onto canvas
 	/// - preferred_colors - an optional list of colors to be preferred above others when determining colors
 	/// - pixel_scale - how many pixels are in one grid square
 	/// - sub_pixel_scale - scale of sub-grid within each grid square
-	pub fn ux_squareularize_canvas(canvas: &ImageSurface, preferred_colors: Option<&Vec<(u8,u8,u8)>>, pixel_scale: &usize, sub_pixel_scale: &usize) -> Option<SquareGrid> {
+	pub fn ux_squareularize_canvas(canvas: &ImageSurface, palette: &Palette, preferred_colors: Option<&[(u8,u8,u8)]>, pixel_scale: &usize, sub_pixel_scale: &usize) -> Option<SquareGrid> {
 		match squareularization_get_rgb_pixels(canvas) {
 			Some(image_and_pixels) => {
 				let image = image_and_pixels.0;
@@ -416,10 +442,10 @@ impl std::fmt::Display for SquareStairDisplay {
 				match squareularization_split_img_to_squares(&img_width, &img_height, &square_width, &square_height) {
 					Some(mut squares) => {
 						// figure out dominant color in each square, replacing color value in vec
-						squareularization_get_dominant_color(&mut squares, preferred_colors, &pixels, &img_width, &square_width, &square_height);
+						squareularization_get_dominant_color(&mut squares, palette, preferred_colors, &pixels, &img_width, &square_width, &square_height);
 		
 						// paint dominant color to entire square using the canvas
-						squareularization_color_squares(canvas, &squares, &false);
+						squareularization_color_squares(canvas, &squares, palette, &false);
 		
 						Some(squares)
 					},
@@ -439,6 +465,7 @@ impl std::fmt::Display for SquareStairDisplay {
 	/// It returns None if:  
 	/// - we can't get an image from canvas
 	/// - the image we get isn't an RGB image with color depth 3
+	///
 	/// It returns Some with a tuple containing:
 	/// - the image grabbed from canvas
 	/// - a Vector containing the RGB value of each pixel
@@ -469,139 +496,28 @@ impl std::fmt::Display for SquareStairDisplay {
 		} else {return None;}
 	}//end squareularization_get_rgb_pixels(canvas)
 
-	/// Helper function for ux_squareularize_canvas()
-	/// - This function, given the dimensions of a larger image and smaller square within that image, 
-	/// splits up the image into squares of roughly the dimensions provided.  
-	/// - This is returned as a vector containing the x and y coordinate of the 
-	/// upper left corner of each square.  
-	/// Each element also has a tuple with an rgb value,
-	/// to be used in later processing.
-	/// - There are cases when the image cannot be split evenly into squares of the same size.
-	/// In such a case, the squares along the bottom or right edge of the image will overlap slightly.
-	pub fn squareularization_split_img_to_squares(img_width: &usize, img_height: &usize, square_width: &usize, square_height: &usize) -> Option<SquareGrid> {
-		let mut squares: Vec<Square> = Vec::new();
-		// format of (x, y, Color), assume square_width and square_height, fill in color later
-		for mut y in (0..*img_height).step_by(*square_height) {
-			// Squares at edges might overlap, but they won't be out of bounds
-			if y + square_height > *img_height {y = img_height - square_height;}
-			for mut x in (0..*img_width).step_by(*square_width) {
-				// Squares at edges might overlap, but they won't be out of bounds
-				if x + square_width > *img_width {x = img_width - square_width;}
-				let square = Square::new(x,y, *square_width, *square_height);
-				squares.push(square);
-			}//end looping over all potential x values for sub-squares
-		}//end looping over all potential y values for sub-squares
-		match SquareGrid::from_squares(squares, *img_width, *img_height) {
-			Ok(square_grid) => {
-				Some(square_grid)
-			},
-			Err(err_info) => {
-				println!("Recieved an error when converting squares to SquareGrid. Printing it below:");
-				println!("{}", err_info.1);
-				None
-			}}
-	}//end squareularization_split_img_to_squares
-
-	/// Helper function for ux_squareularize_canvas
-	/// Determines a dominant color in each square, recording
-	/// this information in squares. 
-	/// ## Bias:
-	/// This function has a bias towards certain colors and will count them as being
-	/// x10 more dominant. Colors meaningful to CA are given bias. Colors close to CA colors
-	/// will be converted.  
-	/// Stairs a given a large bias due to conversion strength of wall and floor.
-	/// ## Panics:
-	/// - If this function panics, it is mostly likely a result of
-	/// the bounds of a square exceeding image bounds, causing the
-	/// function to attempt accessing a pixel that doesn't exist.  
-	/// This should not happen when using SquareGrids though.
-	pub fn squareularization_get_dominant_color(squares: &mut SquareGrid, preferred_colors: Option<&Vec<(u8,u8,u8)>>, pixels: &Vec<(u8,u8,u8)> ,img_width: &usize, square_width: &usize, square_height: &usize) {
-		/*
-		square.0 refers to square width, square.1 refers to square height
-		 */
-		for square in squares.iter_mut() {
-			// figure out dominant color here, set square.2 to that
-			// color_counts1 and color_counts2 are parallel
-			let mut color_counts_color: Vec<(u8,u8,u8)> = Vec::new();
-			let mut color_counts_count: Vec<u64> = Vec::new();
-			for y in *square.y()..(square_height + square.y()) {
-				for x in *square.x()..(square_width + square.x()) {
-					let this_overall_index = (y * img_width) + x;
-					let this_rgb = pixels[this_overall_index];
-					let mut this_color = (this_rgb.0, this_rgb.1, this_rgb.2);
-					
-					if let Some(pcs) = preferred_colors {
-						if !pcs.contains(&this_rgb) {
-							let pcs_dists = {
-								let mut tmp_dif_vec = Vec::new();
-								for color in pcs {
-									let r_diff = this_rgb.0 as f32 - color.0 as f32;
-									let g_diff = this_rgb.1 as f32 - color.1 as f32;
-									let b_diff = this_rgb.2 as f32 - color.2 as f32;
-									let t_diff = (r_diff.abs() + g_diff.abs() + b_diff.abs()) / 3.;
-									tmp_dif_vec.push(t_diff);
-								}//end getting total average difference to each color
-								tmp_dif_vec
-							};
-							let mut closest = (0, 1000.);
-							for (idx, pcs_dist) in pcs_dists.iter().enumerate() {
-								if *pcs_dist < closest.1 {
-									closest = (idx, *pcs_dist);
-								}//end if we have a better closest value
-							}//end finding closest of preferred colors
-							if closest.1 <= 150. && this_rgb != (255,0,255) { if let Some(color) = pcs.get(closest.0) { this_color = *color; } }
-						}//end if this color is not preferred
-					}//end if we're applying a bias
-
-					if let Some(color_index) = color_counts_color.iter().position(|&c| c == this_color) {
-						color_counts_count[color_index] += 1;
-					} else {
-						color_counts_color.push(this_color);
-						color_counts_count.push(1);
-					}//end else we need to add new entry to color counts
-				}//end looping over all x values within square
-			}//end looping over all y values within square
-
-			// bias for CA colors
-			for (i, color) in color_counts_color.iter().enumerate() {
-				match *color {
-					(0,255,0) => color_counts_count[i] *= 500,
-					(255,0,255) => color_counts_count[i] *= 0,
-					_ => {},
-				}//end matching bias to colors
-			}//end applying bias to color counts
-
-			// check to see which color has the highest count
-			let mut running_most = ((40,40,40), 0);
-			for (i, count) in color_counts_count.iter().enumerate() {
-				if *count > running_most.1 { running_most = (color_counts_color[i], color_counts_count[i]); }
-			}//end getting the color that's most common from color counts
-			square.set_color(running_most.0);
-		}//end figuring out which color is dominant
-	}//end squareularization_get_dominant_color()
-
 	/// Helper function for [ux_squareularize_canvas()]
 	/// - This function, given an ImageSurface and Vec of squares 
-	/// within that image, paints the color within the squares 
-	/// vec to that square.  
+	///   within that image, paints the color palette has for the kind 
+	///   of each square to that square.  
 	/// - If [use_debug_color] is true, then the whole [canvas] will be 
-	/// painted magenta before painting anything, in order to 
-	/// visually show if any space was missed.
+	///   painted magenta before painting anything, in order to 
+	///   visually show if any space was missed.
 	/// - Because this function accesses fltk drawing functions and
-	/// must do type conversions, there are a few potential panics
-	/// that could happen.
+	///   must do type conversions, there are a few potential panics
+	///   that could happen.
 	/// - It is recommended to call [redraw()] on the frame holding 
-	/// canvas after calling this function.
+	///   canvas after calling this function.
 	/// ## Panics:
 	/// - type conversion of [square_width] or [square_height] to i32
 	/// - type conversion of [squares]\[i\].0 or [squares]\[i\].1 to i32
 	/// - debug drawing tries to calculate image dimensions by 
-	/// getting the last element of [squares] and adding [square_width] 
-	/// and [square_height] appropriately. If squares cannot 
-	/// be accessed, the type conversion to i32 fails, or 
-	/// the dimensions calculated exceed the image bounds, a 
-	/// panic might happen.
-	pub fn squareularization_color_squares(canvas: &ImageSurface, squares: &SquareGrid, use_debug_color: &bool) {
+	///   getting the last element of [squares] and adding [square_width] 
+	///   and [square_height] appropriately. If squares cannot 
+	///   be accessed, the type conversion to i32 fails, or 
+	///   the dimensions calculated exceed the image bounds, a 
+	///   panic might happen.
+	pub fn squareularization_color_squares(canvas: &ImageSurface, squares: &SquareGrid, palette: &Palette, use_debug_color: &bool) {
 		ImageSurface::push_current(&canvas);
 		if *use_debug_color {
 			// paint magenta to entire canvas as debugging
@@ -610,7 +526,8 @@ impl std::fmt::Display for SquareStairDisplay {
 		// paint dominant color to entire square using the canvas
 		for square in squares.iter() {
 			let a = (*square.x() as i32, *square.y() as i32, *square.width() as i32, *square.height() as i32);
-			let c = Color::from_rgb(square.color().0, square.color().1, square.color().2);
+			let color = palette.color(*square.kind());
+			let c = Color::from_rgb(color.0, color.1, color.2);
 			draw_rect_fill(a.0, a.1, a.2, a.3, c);
 			if *use_debug_color { draw_rect_with_color(a.0, a.1, a.2, a.3, Color::Magenta); }
 		}//end painting dominant color to entirety of each square
@@ -619,11 +536,12 @@ impl std::fmt::Display for SquareStairDisplay {
 
 	/// Function similar to squareularization_color_squares, this function allows you
 	/// to only color a select few squares instead of redoing the whole canvas.
-	pub fn squareularization_color_square(canvas: &ImageSurface, squares: Iter<Square>) {
+	pub fn squareularization_color_square(canvas: &ImageSurface, squares: Iter<Square>, palette: &Palette) {
 		ImageSurface::push_current(&canvas);
 		for square in squares {
 			let coords = (*square.x() as i32, *square.y() as i32, *square.width() as i32, *square.height() as i32);
-			let color = Color::from_rgb(square.color().0, square.color().1, square.color().2);
+			let color = palette.color(*square.kind());
+			let color = Color::from_rgb(color.0, color.1, color.2);
 			draw_rect_fill(coords.0, coords.1, coords.2, coords.3, color);
 		}//end looping over each square
 		ImageSurface::pop_current();
@@ -1,9 +1,10 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, path::Path, rc::Rc};
 
-use fltk::{app::{self, Sender}, button::Button, dialog, draw::{draw_line, draw_point, draw_rect_fill, set_draw_color, set_line_style, LineStyle}, enums::{Align, Color, Event, FrameType}, frame::Frame, group::{Flex, FlexType, Group, Scroll, Tile}, prelude::{DisplayExt, GroupExt, ImageExt, SurfaceDevice, ValuatorExt, WidgetBase, WidgetExt}, surface::ImageSurface, text::{TextBuffer, TextDisplay, TextEditor}, valuator::{Counter, CounterType}, widget_extends};
-use nice_map_generator::{room_growth::CRGC, squares::SquareGrid};
+use fltk::{app::{self, Sender}, button::{Button, CheckButton}, dialog, draw::{draw_line, draw_point, draw_rect_fill, set_draw_color, set_line_style, LineStyle}, enums::{Align, Color, Event, FrameType}, frame::Frame, group::{Flex, FlexType, Group, Scroll, Tile}, input::Input, menu::Choice, prelude::{DisplayExt, GroupExt, ImageExt, InputExt, MenuExt, SurfaceDevice, ValuatorExt, WidgetBase, WidgetExt}, surface::ImageSurface, text::{TextBuffer, TextDisplay, TextEditor}, valuator::{Counter, CounterType}, widget_extends};
+use nice_map_generator::{import::squareularize_image_file, levels::LevelStack, project::{RoomProject, RoomSettings}, room_growth::{RoomLimits, StartPlacement, CRGC}, squares::{CellKind, Palette, SquareGrid}, stairs::{StairDirection, StairLink}, zones::{ZoneSettings, ZoneType}};
+use rand::Rng;
 
-use super::gui_utils::{get_default_tab_padding, squareularization_color_square, squareularization_color_squares, ux_squareularize_canvas, ListBox, SquareStairDisplay};
+use super::gui_utils::{get_default_tab_padding, squareularization_color_square, squareularization_color_squares, ux_squareularize_canvas, keep_stair_links, ListBox, SquareStairDisplay};
 
 
 /// # enum DrawState
@@ -21,26 +22,21 @@ enum DrawState {
 
 impl DrawState {
 	fn color(&self) -> Color {
-		match self {
-			DrawState::Wall => Color::from_rgb(CRGC::Wall.color().0,CRGC::Wall.color().1,CRGC::Wall.color().2),
-			DrawState::Floor => Color::from_rgb(CRGC::Floor.color().0,CRGC::Floor.color().1,CRGC::Floor.color().2),
-			DrawState::Stair => Color::from_rgb(CRGC::Stairs.color().0,CRGC::Stairs.color().1,CRGC::Stairs.color().2),
-			DrawState::Empty => Color::from_rgb(CRGC::Empty.color().0,CRGC::Empty.color().1,CRGC::Empty.color().2),
-			DrawState::Door => Color::from_rgb(CRGC::Door.color().0,CRGC::Door.color().1,CRGC::Door.color().2),
-			DrawState::RoomStart => Color::from_rgb(CRGC::RoomStart.color().0,CRGC::RoomStart.color().1,CRGC::RoomStart.color().2),
-			DrawState::Disabled => Color::White,
-		}//end matching self
+		let c = match self {
+			DrawState::Wall => Palette::room().color(CellKind::Wall),
+			DrawState::Floor => Palette::room().color(CellKind::Floor),
+			DrawState::Stair => Palette::room().color(CellKind::Stairs),
+			DrawState::Empty => Palette::room().color(CellKind::Empty),
+			DrawState::Door => Palette::room().color(CellKind::Door),
+			DrawState::RoomStart => Palette::room().color(CellKind::RoomStart),
+			DrawState::Disabled => return Color::White,
+		};//end matching self
+		Color::from_rgb(c.0,c.1,c.2)
 	}//end color()
 
 	fn get_color_vec_u8() -> Vec<(u8,u8,u8)> {
-		let mut color_vec = Vec::new();
-		color_vec.push(CRGC::Door.color());
-		color_vec.push(CRGC::Empty.color());
-		color_vec.push(CRGC::Floor.color());
-		color_vec.push(CRGC::RoomStart.color());
-		color_vec.push(CRGC::Stairs.color());
-		color_vec.push(CRGC::Wall.color());
-		return color_vec;
+		let palette = Palette::room();
+		vec![palette.door, palette.secret_door, palette.empty, palette.floor, palette.room_start, palette.stairs, palette.wall]
 	}//end get_color_vec_u8()
 }
 
@@ -52,6 +48,8 @@ pub struct RoomGenGroup {
 	ux_draw_state: Rc<RefCell<DrawState>>,
 	ux_brush_size: Rc<RefCell<i32>>,
 	ux_last_square_grid: Rc<RefCell<Option<SquareGrid>>>,
+	/// every level, as of the last time levels were switched. The level shown on the canvas is kept in [ux_last_square_grid]
+	ux_level_stack: Option<LevelStack>,
 	ux_level_cur_buf: TextBuffer,
 	ux_level_tot_buf: TextBuffer,
 	ux_squares_width_counter: Counter,
@@ -60,6 +58,23 @@ pub struct RoomGenGroup {
 	ux_sub_pixel_scale: usize,
 	ux_stairs_list: Rc<RefCell<ListBox<SquareStairDisplay>>>,
 	ux_draw_frame_ref: Rc<RefCell<Frame>>,
+	ux_connectivity_limit_counter: Counter,
+	ux_seed_input: Input,
+	ux_zone_types_input: Input,
+	ux_doors_within_counter: Counter,
+	ux_doors_between_counter: Counter,
+	ux_secret_door_chance_counter: Counter,
+	ux_loop_targets_input: Input,
+	ux_start_placement_choice: Choice,
+	ux_start_spacing_counter: Counter,
+	ux_start_merge_counter: Counter,
+	ux_min_width_counter: Counter,
+	ux_min_height_counter: Counter,
+	ux_max_width_counter: Counter,
+	ux_max_height_counter: Counter,
+	ux_max_area_counter: Counter,
+	ux_max_aspect_counter: Counter,
+	ux_all_levels_check: CheckButton,
 }//end struct RoomGenGroup
 
 impl Default for RoomGenGroup {
@@ -73,6 +88,7 @@ impl Default for RoomGenGroup {
 			ux_draw_state: Rc::from(RefCell::from(DrawState::Disabled)),
 			ux_brush_size: Rc::from(RefCell::from(1)),
 			ux_last_square_grid: Rc::from(RefCell::from(None)),
+			ux_level_stack: None,
 			ux_level_cur_buf: Default::default(),
 			ux_level_tot_buf: Default::default(),
 			ux_squares_width_counter: Default::default(),
@@ -81,6 +97,23 @@ impl Default for RoomGenGroup {
 			ux_sub_pixel_scale: 1,
 			ux_stairs_list: Rc::from(RefCell::from(ListBox::new(0,0,10,10,10))),
 			ux_draw_frame_ref: Rc::from(RefCell::from(Frame::default())),
+			ux_connectivity_limit_counter: Default::default(),
+			ux_seed_input: Default::default(),
+			ux_zone_types_input: Default::default(),
+			ux_doors_within_counter: Default::default(),
+			ux_doors_between_counter: Default::default(),
+			ux_secret_door_chance_counter: Default::default(),
+			ux_loop_targets_input: Default::default(),
+			ux_start_placement_choice: Default::default(),
+			ux_start_spacing_counter: Default::default(),
+			ux_start_merge_counter: Default::default(),
+			ux_min_width_counter: Default::default(),
+			ux_min_height_counter: Default::default(),
+			ux_max_width_counter: Default::default(),
+			ux_max_height_counter: Default::default(),
+			ux_max_area_counter: Default::default(),
+			ux_max_aspect_counter: Default::default(),
+			ux_all_levels_check: Default::default(),
 		};//end struct construction
 		build_gen_group.ux_whole_tab_group.end();
 		build_gen_group.ux_canvas_scroll.end();
@@ -240,15 +273,17 @@ impl RoomGenGroup {
 		ux_level_total_label_txt.set_buffer(self.ux_level_tot_buf.clone());
 		ux_interior_level_number_hor_flex_1.add(&ux_level_total_label_txt);
 
-		let ux_level_down_btn = Button::default()
+		let mut ux_level_down_btn = Button::default()
 			.with_pos(ux_level_label_frame.x(), ux_level_label_frame.y() + ux_level_label_frame.height())
 			.with_size(25, 25)
 			.with_label("@line");
+		ux_level_down_btn.emit(msg_sender.clone(), String::from("RoomGen:Level:Down"));
 		ux_interior_level_number_hor_flex_2.add(&ux_level_down_btn);
-		let ux_level_up_btn = Button::default()
+		let mut ux_level_up_btn = Button::default()
 			.with_pos(ux_level_down_btn.x() + ux_level_down_btn.width() , ux_level_down_btn.y())
 			.with_size(25,25)
 			.with_label("@+");
+		ux_level_up_btn.emit(msg_sender.clone(), String::from("RoomGen:Level:Up"));
 		ux_interior_level_number_hor_flex_2.add(&ux_level_up_btn);
 
 		// stuff for setting size/resolution of squares
@@ -344,7 +379,7 @@ impl RoomGenGroup {
 		// set up frames to show whether each drawing mode is active
 		let mut ux_draw_doors_btn = Button::default()
 			.with_label("Doors");
-		{let c = CRGC::Door.color();
+		{let c = Palette::room().color(CellKind::Door);
 		ux_draw_doors_btn.set_color(Color::from_rgb(c.0,c.1,c.2));}
 		ux_draw_doors_btn.set_label_color(Color::White);
 		ux_draw_doors_btn.set_frame(FrameType::FlatBox);
@@ -352,14 +387,14 @@ impl RoomGenGroup {
 
 		let mut ux_draw_room_start_btn = Button::default()
 			.with_label("Room Start");
-		{let c = CRGC::RoomStart.color();
+		{let c = Palette::room().color(CellKind::RoomStart);
 		ux_draw_room_start_btn.set_color(Color::from_rgb(c.0,c.1,c.2));}
 		ux_draw_room_start_btn.set_frame(FrameType::FlatBox);
 		ux_interior_flex_1.add(&ux_draw_room_start_btn);
 
 		let mut ux_draw_empty_btn = Button::default()
 			.with_label("Erase");
-		{let c = CRGC::Empty.color();
+		{let c = Palette::room().color(CellKind::Empty);
 		ux_draw_empty_btn.set_color(Color::from_rgb(c.0,c.1,c.2));}
 		ux_draw_empty_btn.set_frame(FrameType::FlatBox);
 		ux_interior_flex_1.add(&ux_draw_empty_btn);
@@ -367,7 +402,7 @@ impl RoomGenGroup {
 		// set up buttons to choose between different drawing modes
 		let mut ux_draw_wall_btn = Button::default()
 			.with_label("Wall");
-		{let c = CRGC::Wall.color();
+		{let c = Palette::room().color(CellKind::Wall);
 		ux_draw_wall_btn.set_color(Color::from_rgb(c.0,c.1,c.2));}
 		ux_draw_wall_btn.set_label_color(Color::White);
 		ux_interior_flex_2.add(&ux_draw_wall_btn);
@@ -375,13 +410,13 @@ impl RoomGenGroup {
 
 		let mut ux_draw_floor_btn = Button::default()
 			.with_label("Floor");
-		{let c = CRGC::Floor.color();
+		{let c = Palette::room().color(CellKind::Floor);
 		ux_draw_floor_btn.set_color(Color::from_rgb(c.0,c.1,c.2));}
 		ux_interior_flex_2.add(&ux_draw_floor_btn);
 
 		let mut ux_draw_stairs_btn = Button::default()
 			.with_label("Stairs");
-		{let c = CRGC::Stairs.color();
+		{let c = Palette::room().color(CellKind::Stairs);
 		ux_draw_stairs_btn.set_color(Color::from_rgb(c.0,c.1,c.2));}
 		ux_interior_flex_2.add(&ux_draw_stairs_btn);
 
@@ -525,9 +560,33 @@ impl RoomGenGroup {
 		let mut ux_interior_flex_3 = Flex::default().with_type(FlexType::Row);
 		ux_interior_flex_3.end();
 		ux_exterior_flex.add(&ux_interior_flex_3);
+		let mut ux_room_size_flex = Flex::default().with_type(FlexType::Row);
+		ux_room_size_flex.end();
+		ux_exterior_flex.add(&ux_room_size_flex);
+		let mut ux_room_shape_flex = Flex::default().with_type(FlexType::Row);
+		ux_room_shape_flex.end();
+		ux_exterior_flex.add(&ux_room_shape_flex);
 		let mut ux_interior_flex_4 = Flex::default().with_type(FlexType::Row);
 		ux_interior_flex_4.end();
 		ux_exterior_flex.add(&ux_interior_flex_4);
+		let mut ux_interior_flex_5 = Flex::default().with_type(FlexType::Row);
+		ux_interior_flex_5.end();
+		ux_exterior_flex.add(&ux_interior_flex_5);
+		let mut ux_interior_flex_6 = Flex::default().with_type(FlexType::Row);
+		ux_interior_flex_6.end();
+		ux_exterior_flex.add(&ux_interior_flex_6);
+		let mut ux_interior_flex_7 = Flex::default().with_type(FlexType::Row);
+		ux_interior_flex_7.end();
+		ux_exterior_flex.add(&ux_interior_flex_7);
+		let mut ux_interior_flex_8 = Flex::default().with_type(FlexType::Row);
+		ux_interior_flex_8.end();
+		ux_exterior_flex.add(&ux_interior_flex_8);
+		let mut ux_interior_flex_9 = Flex::default().with_type(FlexType::Row);
+		ux_interior_flex_9.end();
+		ux_exterior_flex.add(&ux_interior_flex_9);
+		let mut ux_interior_flex_10 = Flex::default().with_type(FlexType::Row);
+		ux_interior_flex_10.end();
+		ux_exterior_flex.add(&ux_interior_flex_10);
 
 		// add room starts controls
 		let mut ux_room_start_help_btn = Button::default().with_label("Help");
@@ -541,38 +600,95 @@ impl RoomGenGroup {
 		ux_room_start_place_btn.emit(msg_sender.clone(), String::from("RoomGen:RoomStarts"));
 		ux_interior_flex_1.add(&ux_room_start_place_btn);
 
+		// room start spacing controls
+		let room_defaults = RoomSettings::default();
+		let mut ux_start_placement_choice = Choice::default();
+		ux_start_placement_choice.add_choice("Random");
+		ux_start_placement_choice.add_choice("Poisson Disk");
+		ux_start_placement_choice.add_choice("Jittered Grid");
+		ux_start_placement_choice.set_tooltip("How room starts are spread out. Poisson Disk and Jittered Grid keep starts apart by the spacing.");
+		ux_interior_flex_2.add(&ux_start_placement_choice);
+		self.ux_start_placement_choice = ux_start_placement_choice;
+
+		let mut ux_start_spacing_counter = Counter::default().with_type(CounterType::Simple);
+		ux_start_spacing_counter.set_bounds(1.0, 100.0);
+		ux_start_spacing_counter.set_precision(0);
+		ux_start_spacing_counter.set_step(1.0, 5);
+		ux_start_spacing_counter.set_tooltip("Minimum distance between room starts for Poisson Disk, or the size of each cell for Jittered Grid.");
+		ux_interior_flex_2.add(&ux_start_spacing_counter);
+		self.ux_start_spacing_counter = ux_start_spacing_counter;
+
+		let mut ux_start_merge_counter = Counter::default().with_type(CounterType::Simple);
+		ux_start_merge_counter.set_bounds(0.0, 100.0);
+		ux_start_merge_counter.set_precision(0);
+		ux_start_merge_counter.set_step(1.0, 5);
+		ux_start_merge_counter.set_tooltip("Room starts closer together than this are merged into one. 0 never merges starts.");
+		ux_interior_flex_2.add(&ux_start_merge_counter);
+		self.ux_start_merge_counter = ux_start_merge_counter;
+		self.set_start_settings(room_defaults.start_placement, room_defaults.start_merge_distance);
+
 		// add constrained room growth controls
 		let mut ux_room_growth_help_btn = Button::default().with_label("Help");
-		ux_interior_flex_2.add(&ux_room_growth_help_btn);
-		ux_interior_flex_2.fixed(&ux_room_growth_help_btn, 70);
+		ux_interior_flex_3.add(&ux_room_growth_help_btn);
+		ux_interior_flex_3.fixed(&ux_room_growth_help_btn, 70);
 		ux_room_growth_help_btn.set_callback({move |_| {
 			dialog::message_default("Help dialog test for initial growth of rooms.");
 		}});
 
 		let mut ux_room_growth_start_btn = Button::default().with_label("Initial Room Growth");
 		ux_room_growth_start_btn.emit(msg_sender.clone(), String::from("RoomGen:InitialGrowth"));
-		ux_interior_flex_2.add(&ux_room_growth_start_btn);
+		ux_interior_flex_3.add(&ux_room_growth_start_btn);
+
+		// room size and shape limits, where 0 means no limit for the maximums
+		let limit_counter = |lower: f64, upper: f64, precision: i32, tooltip: &str| {
+			let mut counter = Counter::default().with_type(CounterType::Simple);
+			counter.set_bounds(lower, upper);
+			counter.set_precision(precision);
+			if precision == 0 { counter.set_step(1.0, 5); } else { counter.set_step(0.5, 1); }
+			counter.set_tooltip(tooltip);
+			counter
+		};
+		let ux_room_size_label = Frame::default().with_label("Size");
+		ux_room_size_flex.add(&ux_room_size_label);
+		ux_room_size_flex.fixed(&ux_room_size_label, 70);
+		self.ux_min_width_counter = limit_counter(1.0, 100.0, 0, "Minimum room width. Narrower rooms are merged into a neighbor or removed.");
+		self.ux_min_height_counter = limit_counter(1.0, 100.0, 0, "Minimum room height. Shorter rooms are merged into a neighbor or removed.");
+		self.ux_max_width_counter = limit_counter(0.0, 100.0, 0, "Maximum room width, or 0 for no limit.");
+		self.ux_max_height_counter = limit_counter(0.0, 100.0, 0, "Maximum room height, or 0 for no limit.");
+		ux_room_size_flex.add(&self.ux_min_width_counter);
+		ux_room_size_flex.add(&self.ux_min_height_counter);
+		ux_room_size_flex.add(&self.ux_max_width_counter);
+		ux_room_size_flex.add(&self.ux_max_height_counter);
+
+		let ux_room_shape_label = Frame::default().with_label("Shape");
+		ux_room_shape_flex.add(&ux_room_shape_label);
+		ux_room_shape_flex.fixed(&ux_room_shape_label, 70);
+		self.ux_max_area_counter = limit_counter(0.0, 10000.0, 0, "Maximum number of floor squares in a room, or 0 for no limit.");
+		self.ux_max_aspect_counter = limit_counter(0.0, 100.0, 1, "Maximum ratio of a room's long side to its short side, or 0 for no limit.");
+		ux_room_shape_flex.add(&self.ux_max_area_counter);
+		ux_room_shape_flex.add(&self.ux_max_aspect_counter);
+		self.set_room_limits(&room_defaults.room_limits);
 
 		// L growth controls
 		let mut ux_room_l_growth_help_btn = Button::default().with_label("Help");
-		ux_interior_flex_3.add(&ux_room_l_growth_help_btn);
-		ux_interior_flex_3.fixed(&ux_room_l_growth_help_btn, 70);
+		ux_interior_flex_4.add(&ux_room_l_growth_help_btn);
+		ux_interior_flex_4.fixed(&ux_room_l_growth_help_btn, 70);
 		ux_room_l_growth_help_btn.set_callback({move |_| {
 			dialog::message_default("Help dialog test for l growth of rooms.")
 		}});
 
 		let mut ux_room_l_growth_start_btn = Button::default().with_label("L Growth");
-		ux_interior_flex_3.add(&ux_room_l_growth_start_btn);
+		ux_interior_flex_4.add(&ux_room_l_growth_start_btn);
 		ux_room_l_growth_start_btn.emit(msg_sender.clone(), String::from("RoomGen:LGrowth"));
 
 		// add connectivity controls
 		let mut ux_connectivity_btn = Button::default().with_label("Connectivity");
 		ux_connectivity_btn.emit(msg_sender.clone(), String::from("RoomGen:Connectivity"));
-		ux_interior_flex_4.add(&ux_connectivity_btn);
+		ux_interior_flex_5.add(&ux_connectivity_btn);
 
 		let mut ux_connectivity_help_btn = Button::default().with_label("Help");
-		ux_interior_flex_4.add(&ux_connectivity_help_btn);
-		ux_interior_flex_4.fixed(&ux_connectivity_help_btn, 70);
+		ux_interior_flex_5.add(&ux_connectivity_help_btn);
+		ux_interior_flex_5.fixed(&ux_connectivity_help_btn, 70);
 		ux_connectivity_help_btn.set_callback({move |_| {
 			dialog::message_default("Help dialog test for connectivity settings.");
 		}});
@@ -582,7 +698,115 @@ impl RoomGenGroup {
 		ux_connectivity_limit_counter.set_bounds(1.0, 30.0);
 		ux_connectivity_limit_counter.set_precision(0);
 		ux_connectivity_limit_counter.set_step(1.0, 5);
-		ux_interior_flex_4.add(&ux_connectivity_limit_counter);
+		ux_interior_flex_5.add(&ux_connectivity_limit_counter);
+		self.ux_connectivity_limit_counter = ux_connectivity_limit_counter;
+
+		// add zone controls
+		let zone_defaults = ZoneSettings::default();
+		let mut ux_zones_help_btn = Button::default().with_label("Help");
+		ux_interior_flex_7.add(&ux_zones_help_btn);
+		ux_interior_flex_7.fixed(&ux_zones_help_btn, 70);
+		ux_zones_help_btn.set_callback({move |_| {
+			dialog::message_default("Zoned Rooms splits the open parts of the canvas into zones, then places room starts, grows rooms, and adds doors all at once.\nEnter one zone type for each zone, separated by commas. Zone types are public, private, sublevel, and boss.\nSublevel and boss zones are dead ends, linked to only one other zone where possible.\nThe door chances are how likely each extra door is within a zone and between zones, once every room can be reached.");
+		}});
+
+		let mut ux_zones_btn = Button::default().with_label("Zoned Rooms");
+		ux_zones_btn.emit(msg_sender.clone(), String::from("RoomGen:Zones"));
+		ux_interior_flex_7.add(&ux_zones_btn);
+		ux_interior_flex_7.fixed(&ux_zones_btn, 110);
+
+		let mut ux_zone_types_input = Input::default();
+		ux_zone_types_input.set_value(&ZoneType::list_names(&zone_defaults.zone_types));
+		ux_zone_types_input.set_tooltip("Type of each zone to grow, separated by commas: public, private, sublevel, or boss.");
+		ux_interior_flex_7.add(&ux_zone_types_input);
+		self.ux_zone_types_input = ux_zone_types_input;
+
+		let ux_door_chance_label = Frame::default().with_label("Doors");
+		ux_interior_flex_8.add(&ux_door_chance_label);
+		ux_interior_flex_8.fixed(&ux_door_chance_label, 70);
+
+		let mut ux_doors_within_counter = Counter::default().with_type(CounterType::Simple);
+		ux_doors_within_counter.set_bounds(0.0, 1.0);
+		ux_doors_within_counter.set_precision(2);
+		ux_doors_within_counter.set_step(0.05, 1);
+		ux_doors_within_counter.set_value(zone_defaults.doors_within);
+		ux_doors_within_counter.set_tooltip("Chance of extra doors between rooms in the same zone.");
+		ux_interior_flex_8.add(&ux_doors_within_counter);
+		self.ux_doors_within_counter = ux_doors_within_counter;
+
+		let mut ux_doors_between_counter = Counter::default().with_type(CounterType::Simple);
+		ux_doors_between_counter.set_bounds(0.0, 1.0);
+		ux_doors_between_counter.set_precision(2);
+		ux_doors_between_counter.set_step(0.05, 1);
+		ux_doors_between_counter.set_value(zone_defaults.doors_between);
+		ux_doors_between_counter.set_tooltip("Chance of extra doors between rooms in different zones.");
+		ux_interior_flex_8.add(&ux_doors_between_counter);
+		self.ux_doors_between_counter = ux_doors_between_counter;
+
+		// add secret door controls
+		let mut ux_secret_doors_help_btn = Button::default().with_label("Help");
+		ux_interior_flex_9.add(&ux_secret_doors_help_btn);
+		ux_interior_flex_9.fixed(&ux_secret_doors_help_btn, 70);
+		ux_secret_doors_help_btn.set_callback({move |_| {
+			dialog::message_default("Secret Doors turns some doors into secret doors, drawn in purple.\nOnly doors on shortcuts, whose rooms could still reach each other without them, are picked, each with the chance set in the counter.\nAfterwards, secret doors are turned back into normal doors until every set of stairs can be reached from the others without using a secret door.");
+		}});
+
+		let mut ux_secret_doors_btn = Button::default().with_label("Secret Doors");
+		ux_secret_doors_btn.emit(msg_sender.clone(), String::from("RoomGen:SecretDoors"));
+		ux_interior_flex_9.add(&ux_secret_doors_btn);
+
+		let mut ux_secret_door_chance_counter = Counter::default().with_type(CounterType::Simple);
+		ux_secret_door_chance_counter.set_bounds(0.0, 1.0);
+		ux_secret_door_chance_counter.set_precision(2);
+		ux_secret_door_chance_counter.set_step(0.05, 1);
+		ux_secret_door_chance_counter.set_value(RoomSettings::default().secret_door_chance);
+		ux_secret_door_chance_counter.set_tooltip("Chance that each door on a shortcut becomes a secret door.");
+		ux_interior_flex_9.add(&ux_secret_door_chance_counter);
+		self.ux_secret_door_chance_counter = ux_secret_door_chance_counter;
+
+		// add loop injection controls
+		let mut ux_loops_help_btn = Button::default().with_label("Help");
+		ux_interior_flex_10.add(&ux_loops_help_btn);
+		ux_interior_flex_10.fixed(&ux_loops_help_btn, 70);
+		ux_loops_help_btn.set_callback({move |_| {
+			dialog::message_default("Add Loops opens doors between neighboring rooms that are far apart, until the map has at least the target number of loops.\nEnter one target for each level, separated by commas, starting from level 1. Levels past the end of the list use the last target.");
+		}});
+
+		let mut ux_loops_btn = Button::default().with_label("Add Loops");
+		ux_loops_btn.emit(msg_sender.clone(), String::from("RoomGen:Loops"));
+		ux_interior_flex_10.add(&ux_loops_btn);
+
+		let mut ux_loop_targets_input = Input::default();
+		ux_loop_targets_input.set_value(&RoomSettings::default().loop_targets.iter().map(|target| target.to_string()).collect::<Vec<String>>().join(", "));
+		ux_loop_targets_input.set_tooltip("Number of independent loops to aim for on each level, separated by commas, starting from level 1.");
+		ux_interior_flex_10.add(&ux_loop_targets_input);
+		self.ux_loop_targets_input = ux_loop_targets_input;
+
+		// add seed controls
+		let ux_seed_label = Frame::default().with_label("Seed");
+		ux_interior_flex_6.add(&ux_seed_label);
+		ux_interior_flex_6.fixed(&ux_seed_label, 70);
+
+		let mut ux_seed_input = Input::default();
+		ux_seed_input.set_value(&rand::thread_rng().gen::<u64>().to_string());
+		ux_seed_input.set_tooltip("Seed used for every room generation step. Re-enter a seed to reproduce a map.");
+		ux_interior_flex_6.add(&ux_seed_input);
+
+		let mut ux_new_seed_btn = Button::default().with_label("New Seed");
+		ux_interior_flex_6.add(&ux_new_seed_btn);
+		ux_interior_flex_6.fixed(&ux_new_seed_btn, 90);
+		ux_new_seed_btn.set_callback({
+			let mut seed_input_ref = ux_seed_input.clone();
+			move |_| {
+				seed_input_ref.set_value(&rand::thread_rng().gen::<u64>().to_string());
+			}//end closure
+		});
+		self.ux_seed_input = ux_seed_input;
+
+		let mut ux_all_levels_check = CheckButton::default().with_label("Apply to All Levels");
+		ux_all_levels_check.set_tooltip("Run room generation steps on every level instead of just the current one.");
+		ux_exterior_flex.add(&ux_all_levels_check);
+		self.ux_all_levels_check = ux_all_levels_check;
 	}	
 
 	fn initialize_level_connection_settings(&mut self, ux_exterior_flex: &mut Flex, msg_sender: &Sender<String>) {
@@ -603,27 +827,18 @@ impl RoomGenGroup {
 
 		let mut ux_level_connection_add_btn = Button::default()
 			.with_size(ux_exterior_flex.width(), 50)
-			.with_label("Add Level Connection");
+			.with_label("Auto-Pair Stairs");
+		ux_level_connection_add_btn.set_tooltip("Links unlinked stairs to stairs at the same spot on the level above or below.");
 		ux_exterior_flex.add(&ux_level_connection_add_btn);
 
-		ux_level_connection_add_btn.set_callback({
-			move |b| {
-				// dialog::choice2(b.x(), b.y(), "txt", "yes", "no", "");
-				// dialog::input(b.x(), b.y(), "Add stairs", "placeholder");
-				dialog::message(b.x(), b.y(), "placeholder, not yet implemented");
-			}//end moving for callback
-		});
+		ux_level_connection_add_btn.emit(msg_sender.clone(), String::from("RoomGen:Stairs:AutoPair"));
 
 		let mut ux_level_connection_edit_btn = Button::default()
 			.with_size(ux_exterior_flex.width(), 50)
 			.with_label("Edit Level Connection");
 		ux_exterior_flex.add(&ux_level_connection_edit_btn);
 
-		ux_level_connection_edit_btn.set_callback({
-			move |b| {
-				dialog::message(b.x(), b.y(), "Placeholder, not yet implemented");
-			}//end moving for callback
-		});
+		ux_level_connection_edit_btn.emit(msg_sender.clone(), String::from("RoomGen:Stairs:Edit"));
 
 		let mut ux_level_connection_remove_btn = Button::default()
 			.with_size(ux_exterior_flex.width(), 50)
@@ -632,10 +847,19 @@ impl RoomGenGroup {
 
 		ux_level_connection_remove_btn.emit(msg_sender.clone(), String::from("RoomGen:Stairs:Remove"));
 
+		let mut ux_level_connection_check_btn = Button::default()
+			.with_size(ux_exterior_flex.width(), 50)
+			.with_label("Check Stairs");
+		ux_level_connection_check_btn.set_tooltip("Lists stairs on every level that don't lead anywhere, don't line up, or don't lead back.");
+		ux_exterior_flex.add(&ux_level_connection_check_btn);
+
+		ux_level_connection_check_btn.emit(msg_sender.clone(), String::from("RoomGen:Stairs:Check"));
+
 		ux_exterior_flex.fixed(&ux_level_connection_settings_section_label, 50);
 		ux_exterior_flex.fixed(&ux_level_connection_add_btn, 50);
 		ux_exterior_flex.fixed(&ux_level_connection_edit_btn, 50);
 		ux_exterior_flex.fixed(&ux_level_connection_remove_btn, 50);
+		ux_exterior_flex.fixed(&ux_level_connection_check_btn, 50);
 	}
 
 	fn update_image_size_and_drawing(&mut self) {
@@ -649,6 +873,7 @@ impl RoomGenGroup {
 		self.ux_last_square_grid = Rc::from(RefCell::from(
 			ux_squareularize_canvas(
 				&canvas_surface,
+				&Palette::room(),
 				Some(&DrawState::get_color_vec_u8()),
 				&(pixel_scale as usize),
 				&(self.ux_sub_pixel_scale as usize)
@@ -728,7 +953,7 @@ impl RoomGenGroup {
 						let pixel_scale = pixel_scale as usize;
 						let mut last_square_grid = last_square_grid.as_ref().borrow_mut();
 						let mut stairs_list = stairs_list.as_ref().borrow_mut();
-						if let Some(squares) = ux_squareularize_canvas(&surface, Some(&DrawState::get_color_vec_u8()), &pixel_scale, &sub_pixel_scale) {
+						if let Some(squares) = ux_squareularize_canvas(&surface, &Palette::room(), Some(&DrawState::get_color_vec_u8()), &pixel_scale, &sub_pixel_scale) {
 							let stair_vec = Self::ux_get_stair_coord_list(&squares);
 							Self::ux_update_stairs_list(stair_vec, &mut stairs_list);
 							*last_square_grid = Some(squares);
@@ -753,8 +978,8 @@ impl RoomGenGroup {
 			for col in 0..*squares.cols() {
 				match squares.get(&row, &col) {
 					Some(square) => {
-						if *square.color() == (0,255,0) {
-							stairs_list.push(SquareStairDisplay {square: *square, row_idx: row, col_idx: col});
+						if *square.kind() == CellKind::Stairs {
+							stairs_list.push(SquareStairDisplay {square: *square, row_idx: row, col_idx: col, link: None});
 						}//end if we found a stair
 					},
 					None => println!("Failed to get an index when counting stairs?"),
@@ -764,7 +989,8 @@ impl RoomGenGroup {
 		return stairs_list;
 	}//end ux_get_stair_coord_list()
 
-	fn ux_update_stairs_list(stairs_list: Vec<SquareStairDisplay>, stairs_list_box: &mut ListBox<SquareStairDisplay>) {
+	fn ux_update_stairs_list(mut stairs_list: Vec<SquareStairDisplay>, stairs_list_box: &mut ListBox<SquareStairDisplay>) {
+		keep_stair_links(&mut stairs_list, &stairs_list_box.get_elements());
 		stairs_list_box.clear_elements();
 		stairs_list_box.set_elements(stairs_list);
 	}//end ux_update_stairs_list(s)
@@ -776,6 +1002,38 @@ impl RoomGenGroup {
 		stairs_list_borrow.get_selected_elements().into_iter().map(|val| format!("{}", val)).collect()
 	}//end get_stairs_selected
 
+	/// Sets where the selected stairs lead.
+	pub fn set_stairs_selected_link(&mut self, direction: StairDirection, target_level: Option<usize>) {
+		let stairs_list_ref = &self.ux_stairs_list;
+		let stairs_list_ref_clone = stairs_list_ref.clone();
+		let mut stairs_list_borrow = stairs_list_ref_clone.as_ref().borrow_mut();
+		stairs_list_borrow.update_selected_elements(|stair| SquareStairDisplay {
+			link: Some(StairLink::new(stair.row_idx, stair.col_idx, direction, target_level)),
+			..stair.clone()
+		});
+	}//end set_stairs_selected_link(self, direction, target_level)
+
+	/// Gets links for every stair in the list that has one.
+	fn get_stair_links(&self) -> Vec<StairLink> {
+		let stairs_list_ref = &self.ux_stairs_list;
+		let stairs_list_ref_clone = stairs_list_ref.clone();
+		let stairs_list_borrow = stairs_list_ref_clone.as_ref().borrow();
+		stairs_list_borrow.get_elements().into_iter().filter_map(|stair| stair.link).collect()
+	}//end get_stair_links(self)
+
+	/// Replaces the stairs list with the stairs found in squares, linked according to links.
+	fn show_stairs(&self, squares: &SquareGrid, links: &[StairLink]) {
+		let mut stairs_list = RoomGenGroup::ux_get_stair_coord_list(squares);
+		for stair in stairs_list.iter_mut() {
+			stair.link = links.iter().find(|link| link.row == stair.row_idx && link.col == stair.col_idx).copied();
+		}//end linking each stair
+		let stairs_list_ref = &self.ux_stairs_list;
+		let stairs_list_ref_clone = stairs_list_ref.clone();
+		let mut stairs_list_borrow = stairs_list_ref_clone.as_ref().borrow_mut();
+		stairs_list_borrow.clear_elements();
+		stairs_list_borrow.set_elements(stairs_list);
+	}//end show_stairs(self, squares, links)
+
 	pub fn remove_stairs_selected(&mut self) {
 		let last_square_stair_ref = &self.ux_last_square_grid;
 		let last_square_stair_ref_clone = last_square_stair_ref.clone();
@@ -790,7 +1048,7 @@ impl RoomGenGroup {
 				for selected_element in stairs_list_selected_elements {
 					match squares.get_mut(&selected_element.row_idx, &selected_element.col_idx) {
 						Some(this_square) => {
-							this_square.set_color((255,255,255));
+							this_square.set_kind(CellKind::Empty);
 							squares_to_recolor.push(this_square.clone());
 						},
 						None => println!("Couldn't access square {:?} while removing stairs from list.", selected_element)
@@ -799,7 +1057,7 @@ impl RoomGenGroup {
 				let canvas_ref = &self.ux_canvas_image;
 				let canvas_ref_clone = canvas_ref.clone();
 				let canvas_borrow = canvas_ref_clone.borrow();
-				squareularization_color_square(&canvas_borrow, squares_to_recolor.iter());
+				squareularization_color_square(&canvas_borrow, squares_to_recolor.iter(), &Palette::room());
 				self.ux_canvas_frame.redraw();
 				stairs_list_borrow.remove_selected_elements()
 			},
@@ -821,6 +1079,210 @@ impl RoomGenGroup {
 		self.update_image_size_and_drawing();
 	}//end update_canvas
 
+	/// Gets the minimum connectivity the user wants enforced.
+	pub fn get_connectivity_limit(&self) -> usize {
+		self.ux_connectivity_limit_counter.value() as usize
+	}//end get_connectivity_limit(self)
+
+	/// Gets the zone settings the user entered.  
+	/// Returns Err if the zone types couldn't be understood.
+	pub fn get_zone_settings(&self) -> Result<ZoneSettings, String> {
+		Ok(ZoneSettings {
+			zone_types: ZoneType::parse_list(&self.ux_zone_types_input.value())?,
+			rooms_per_zone: None,
+			doors_within: self.ux_doors_within_counter.value(),
+			doors_between: self.ux_doors_between_counter.value(),
+		})
+	}//end get_zone_settings(self)
+
+	/// Displays zone_settings so the user can see or change them.
+	pub fn set_zone_settings(&mut self, zone_settings: &ZoneSettings) {
+		self.ux_zone_types_input.set_value(&ZoneType::list_names(&zone_settings.zone_types));
+		self.ux_doors_within_counter.set_value(zone_settings.doors_within);
+		self.ux_doors_between_counter.set_value(zone_settings.doors_between);
+	}//end set_zone_settings(self, zone_settings)
+
+	/// Gets the chance the user wants each shortcut door to have of becoming secret.
+	pub fn get_secret_door_chance(&self) -> f64 {
+		self.ux_secret_door_chance_counter.value()
+	}//end get_secret_door_chance(self)
+
+	/// Gets how the user wants room starts spread out, and the distance below which starts are merged.
+	pub fn get_start_settings(&self) -> (StartPlacement, f64) {
+		let spacing = self.ux_start_spacing_counter.value();
+		let placement = match self.ux_start_placement_choice.value() {
+			1 => StartPlacement::PoissonDisk { min_distance: spacing },
+			2 => StartPlacement::JitteredGrid { spacing: spacing as usize },
+			_ => StartPlacement::Random,
+		};
+		(placement, self.ux_start_merge_counter.value())
+	}//end get_start_settings(self)
+
+	/// Displays how room starts are spread out, and the distance below which starts are merged.
+	pub fn set_start_settings(&mut self, placement: StartPlacement, merge_distance: f64) {
+		match placement {
+			StartPlacement::Random => { self.ux_start_placement_choice.set_value(0); },
+			StartPlacement::PoissonDisk { min_distance } => {
+				self.ux_start_placement_choice.set_value(1);
+				self.ux_start_spacing_counter.set_value(min_distance);
+			},
+			StartPlacement::JitteredGrid { spacing } => {
+				self.ux_start_placement_choice.set_value(2);
+				self.ux_start_spacing_counter.set_value(spacing as f64);
+			},
+		}//end matching placement
+		self.ux_start_merge_counter.set_value(merge_distance);
+	}//end set_start_settings(self, placement, merge_distance)
+
+	/// Gets the limits the user wants on the size and shape of rooms grown from starts.
+	pub fn get_room_limits(&self) -> RoomLimits {
+		let optional = |value: f64| if value > 0.0 { Some(value) } else { None };
+		RoomLimits {
+			min_width: self.ux_min_width_counter.value() as usize,
+			min_height: self.ux_min_height_counter.value() as usize,
+			max_width: optional(self.ux_max_width_counter.value()).map(|value| value as usize),
+			max_height: optional(self.ux_max_height_counter.value()).map(|value| value as usize),
+			max_area: optional(self.ux_max_area_counter.value()).map(|value| value as usize),
+			max_aspect_ratio: optional(self.ux_max_aspect_counter.value()),
+		}//end struct construction
+	}//end get_room_limits(self)
+
+	/// Displays the limits on the size and shape of rooms grown from starts.
+	pub fn set_room_limits(&mut self, room_limits: &RoomLimits) {
+		self.ux_min_width_counter.set_value(room_limits.min_width as f64);
+		self.ux_min_height_counter.set_value(room_limits.min_height as f64);
+		self.ux_max_width_counter.set_value(room_limits.max_width.unwrap_or(0) as f64);
+		self.ux_max_height_counter.set_value(room_limits.max_height.unwrap_or(0) as f64);
+		self.ux_max_area_counter.set_value(room_limits.max_area.unwrap_or(0) as f64);
+		self.ux_max_aspect_counter.set_value(room_limits.max_aspect_ratio.unwrap_or(0.0));
+	}//end set_room_limits(self, room_limits)
+
+	/// Gets the number of loops the user wants on each level, starting from level 1.  
+	/// Returns Err if any of the targets isn't a whole number.
+	pub fn get_loop_targets(&self) -> Result<Vec<usize>, String> {
+		self.ux_loop_targets_input.value().split(',').map(|target| target.trim()).filter(|target| !target.is_empty())
+			.map(|target| target.parse().map_err(|_| format!("Couldn't understand loop target {}. Each target should be a whole number.", target)))
+			.collect()
+	}//end get_loop_targets(self)
+
+	/// Gets the number of loops the user wants on the level at level_index.  
+	/// Returns Err if the loop targets couldn't be understood.
+	pub fn get_loop_target(&self, level_index: usize) -> Result<usize, String> {
+		let settings = RoomSettings { loop_targets: self.get_loop_targets()?, ..Default::default() };
+		Ok(settings.loop_target(level_index))
+	}//end get_loop_target(self, level_index)
+
+	/// Gets the seed the user entered, or None if it isn't a valid seed.
+	pub fn get_seed(&self) -> Option<u64> {
+		self.ux_seed_input.value().trim().parse().ok()
+	}//end get_seed(self)
+
+	/// Displays seed so the user can see or re-enter it later.
+	pub fn set_seed(&mut self, seed: u64) {
+		self.ux_seed_input.set_value(&seed.to_string());
+	}//end set_seed(self, seed)
+
+	/// Returns true if room generation steps should run on every level instead of just the current one.
+	pub fn get_all_levels(&self) -> bool {
+		self.ux_all_levels_check.is_checked()
+	}//end get_all_levels(self)
+
+	/// Gets every level, with the current level taken from the canvas.  
+	/// The number of levels comes from the total level box, so levels are added or dropped to match it.
+	/// If the canvas size has changed since levels were last switched, the other levels start out blank.
+	pub fn get_level_stack(&self) -> Option<LevelStack> {
+		let canvas_squares = self.get_last_squareularization()?;
+		let current_level = self.ux_level_cur_buf.text().trim().parse::<usize>().unwrap_or(1).max(1);
+		let total_levels = self.ux_level_tot_buf.text().trim().parse::<usize>().unwrap_or(1).max(current_level);
+		let mut level_stack = match &self.ux_level_stack {
+			Some(level_stack) if level_stack.fits(&canvas_squares) => level_stack.clone(),
+			_ => LevelStack::with_blank_levels(&canvas_squares, total_levels, CRGC::Empty.kind()),
+		};
+		level_stack.set_level_count(total_levels, CRGC::Empty.kind());
+		level_stack.set_current_index(current_level - 1).ok()?;
+		level_stack.set_level(current_level - 1, canvas_squares).ok()?;
+		level_stack.set_stair_links(current_level - 1, self.get_stair_links()).ok()?;
+		Some(level_stack)
+	}//end get_level_stack(self)
+
+	/// Stores every level from level_stack, and shows its current level on the canvas.  
+	/// If the levels are a different size than the canvas, the canvas is resized to fit them.
+	pub fn set_level_stack(&mut self, level_stack: &LevelStack) {
+		let current_squares = level_stack.current();
+		match self.get_last_squareularization() {
+			Some(canvas_squares) if LevelStack::dimensions_match(&canvas_squares, current_squares) => self.set_squareularization(current_squares),
+			_ => self.load_squareularization(current_squares),
+		}//end matching whether levels fit the canvas
+		self.show_stairs(current_squares, level_stack.stair_links(*level_stack.current_index()).unwrap_or(&Vec::new()));
+		self.ux_level_cur_buf.set_text(&(level_stack.current_index() + 1).to_string());
+		self.ux_level_tot_buf.set_text(&level_stack.len().to_string());
+		self.ux_level_stack = Some(level_stack.clone());
+	}//end set_level_stack(self, level_stack)
+
+	/// Switches the canvas to the level offset levels above or below the current one.  
+	/// The current canvas is kept in its level, and the switch stops at the first or last level.
+	pub fn change_level(&mut self, offset: isize) {
+		if let Some(mut level_stack) = self.get_level_stack() {
+			let new_level = level_stack.current_index().saturating_add_signed(offset).min(level_stack.len() - 1);
+			if let Err(msg) = level_stack.set_current_index(new_level) { println!("Couldn't change level: {}", msg); }
+			self.set_level_stack(&level_stack);
+		}//end if we can get the levels
+	}//end change_level(self, offset)
+
+	/// Gathers the levels and settings of this tab so they can be saved to a project.
+	pub fn get_project_data(&self) -> RoomProject {
+		let (start_placement, start_merge_distance) = self.get_start_settings();
+		RoomProject {
+			levels: self.get_level_stack(),
+			settings: RoomSettings {
+				seed: self.get_seed(),
+				connectivity_limit: self.get_connectivity_limit(),
+				zones: self.get_zone_settings().unwrap_or_default(),
+				secret_door_chance: self.get_secret_door_chance(),
+				loop_targets: self.get_loop_targets().unwrap_or_default(),
+				start_placement,
+				start_merge_distance,
+				room_limits: self.get_room_limits(),
+			},
+		}//end struct construction
+	}//end get_project_data(self)
+
+	/// Sets the levels and settings of this tab from a loaded project.
+	pub fn load_project_data(&mut self, project: &RoomProject) {
+		if let Some(seed) = project.settings.seed { self.set_seed(seed); }
+		self.ux_connectivity_limit_counter.set_value(project.settings.connectivity_limit as f64);
+		self.set_zone_settings(&project.settings.zones);
+		self.ux_secret_door_chance_counter.set_value(project.settings.secret_door_chance);
+		self.set_start_settings(project.settings.start_placement, project.settings.start_merge_distance);
+		self.set_room_limits(&project.settings.room_limits);
+		self.ux_loop_targets_input.set_value(&project.settings.loop_targets.iter().map(|target| target.to_string()).collect::<Vec<String>>().join(", "));
+
+		if let Some(level_stack) = &project.levels { self.set_level_stack(level_stack); }
+	}//end load_project_data(self, project)
+
+	/// Replaces the canvas with squares, resizing the canvas and square settings to fit them.  
+	/// Unlike [set_squareularization()], squares doesn't need to match the current canvas size.
+	pub fn load_squareularization(&mut self, squares: &SquareGrid) {
+		let pixel_diameter = match squares.get(&0, &0) { Some(square) => *square.width() / self.ux_sub_pixel_scale, None => 1 };
+		self.ux_squares_width_counter.set_value(*squares.cols() as f64);
+		self.ux_squares_height_counter.set_value(*squares.rows() as f64);
+		self.ux_squares_pixel_diameter_counter.set_value(pixel_diameter.max(1) as f64);
+		self.update_canvas();
+		self.set_squareularization(squares);
+		let stairs_list_ref = &self.ux_stairs_list;
+		let stairs_list_ref_clone = stairs_list_ref.clone();
+		let mut stairs_list_borrow = stairs_list_ref_clone.as_ref().borrow_mut();
+		RoomGenGroup::ux_update_stairs_list(RoomGenGroup::ux_get_stair_coord_list(squares), &mut stairs_list_borrow);
+	}//end load_squareularization(self, squares)
+
+	/// Loads the image at path onto the canvas, with each square covering square_size pixels of the image.  
+	/// Colors in the image are matched to the closest colors used on this canvas.
+	pub fn import_image(&mut self, path: &Path, square_size: usize) -> Result<(), String> {
+		let squares = squareularize_image_file(path, &Palette::room(), Some(&DrawState::get_color_vec_u8()), &square_size)?;
+		self.load_squareularization(&squares);
+		Ok(())
+	}//end import_image(self, path, square_size)
+
 	pub fn get_last_squareularization(&self) -> Option<SquareGrid> {
 		let last_squares = &self.ux_last_square_grid;
 		let last_squares = last_squares.clone();
@@ -838,7 +1300,7 @@ impl RoomGenGroup {
 		let canvas_ref_clone = canvas_ref.clone();
 		let canvas_borrow = canvas_ref_clone.as_ref().borrow();
 
-		squareularization_color_squares(&canvas_borrow, squares, &false);
+		squareularization_color_squares(&canvas_borrow, squares, &Palette::room(), &false);
 		self.ux_canvas_frame.redraw();
 
 		// update list of stairs
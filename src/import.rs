@@ -0,0 +1,211 @@
+use std::path::Path;
+
+use image::RgbImage;
+
+use crate::squares::{Palette, Square, SquareGrid};
+
+/// Loads the image file at path and squareularizes it with [squareularize_image()].  
+/// Any format the image crate was built with can be read, which covers PNG, BMP, JPEG and WEBP.  
+/// Returns Err if the file can't be read as an image, or if [squareularize_image()] fails.
+pub fn squareularize_image_file(path: &Path, palette: &Palette, preferred_colors: Option<&[(u8,u8,u8)]>, square_size: &usize) -> Result<SquareGrid, String> {
+	let image = match image::open(path) {
+		Ok(image) => image.to_rgb8(),
+		Err(err) => return Err(format!("Couldn't open image {}: {}", path.display(), err)),
+	};
+	squareularize_image(&image, palette, preferred_colors, square_size)
+}//end squareularize_image_file(path, palette, preferred_colors, square_size)
+
+/// Squareularizes image, with each square covering square_size pixels of the image.  
+/// Colors are matched with the same bias towards preferred_colors as the canvas in the GUI, then read as kinds with palette.  
+/// The image is cropped down to a multiple of square_size, so that every square is the same size.  
+/// Returns Err if square_size is 0 or the image is smaller than a single square.
+pub fn squareularize_image(image: &RgbImage, palette: &Palette, preferred_colors: Option<&[(u8,u8,u8)]>, square_size: &usize) -> Result<SquareGrid, String> {
+	if *square_size == 0 { return Err(String::from("Squares must be at least 1 pixel across.")); }
+	let img_width = (image.width() as usize / square_size) * square_size;
+	let img_height = (image.height() as usize / square_size) * square_size;
+	if img_width == 0 || img_height == 0 {
+		return Err(format!("Image is {}x{} pixels, which is too small for squares {} pixels across.", image.width(), image.height(), square_size));
+	}//end if image can't fit a single square
+
+	let mut pixels = Vec::new();
+	for y in 0..img_height {
+		for x in 0..img_width {
+			let pixel = image.get_pixel(x as u32, y as u32);
+			pixels.push((pixel[0], pixel[1], pixel[2]));
+		}//end looping over x values
+	}//end looping over y values
+
+	match squareularization_split_img_to_squares(&img_width, &img_height, square_size, square_size) {
+		Some(mut squares) => {
+			squareularization_get_dominant_color(&mut squares, palette, preferred_colors, &pixels, &img_width, square_size, square_size);
+			Ok(squares)
+		}, None => Err(String::from("Couldn't split image into squares.")),
+	}//end matching whether we could split image into squares
+}//end squareularize_image(image, palette, preferred_colors, square_size)
+
+/// Helper function for [squareularize_image()] and canvas squareularizing in the GUI
+/// - This function, given the dimensions of a larger image and smaller square within that image, 
+///   splits up the image into squares of roughly the dimensions provided.  
+/// - This is returned as a vector containing the x and y coordinate of the 
+///   upper left corner of each square.  
+///   Each element also has a tuple with an rgb value,
+///   to be used in later processing.
+/// - There are cases when the image cannot be split evenly into squares of the same size.
+///   In such a case, the squares along the bottom or right edge of the image will overlap slightly.
+pub fn squareularization_split_img_to_squares(img_width: &usize, img_height: &usize, square_width: &usize, square_height: &usize) -> Option<SquareGrid> {
+	let mut squares: Vec<Square> = Vec::new();
+	// format of (x, y, Color), assume square_width and square_height, fill in color later
+	for mut y in (0..*img_height).step_by(*square_height) {
+		// Squares at edges might overlap, but they won't be out of bounds
+		if y + square_height > *img_height {y = img_height - square_height;}
+		for mut x in (0..*img_width).step_by(*square_width) {
+			// Squares at edges might overlap, but they won't be out of bounds
+			if x + square_width > *img_width {x = img_width - square_width;}
+			let square = Square::new(x,y, *square_width, *square_height);
+			squares.push(square);
+		}//end looping over all potential x values for sub-squares
+	}//end looping over all potential y values for sub-squares
+	match SquareGrid::from_squares(squares, *img_width, *img_height) {
+		Ok(square_grid) => {
+			Some(square_grid)
+		},
+		Err(err_info) => {
+			println!("Recieved an error when converting squares to SquareGrid. Printing it below:");
+			println!("{}", err_info.1);
+			None
+		}}
+}//end squareularization_split_img_to_squares
+
+/// Helper function for [squareularize_image()] and canvas squareularizing in the GUI
+/// Determines a dominant color in each square, recording
+/// the kind that color means in palette in squares. 
+/// ## Bias:
+/// This function has a bias towards certain colors and will count them as being
+/// x10 more dominant. Colors meaningful to CA are given bias. Colors close to CA colors
+/// will be converted.  
+/// Stairs a given a large bias due to conversion strength of wall and floor.
+/// ## Panics:
+/// - If this function panics, it is mostly likely a result of
+///   the bounds of a square exceeding image bounds, causing the
+///   function to attempt accessing a pixel that doesn't exist.  
+///   This should not happen when using SquareGrids though.
+pub fn squareularization_get_dominant_color(squares: &mut SquareGrid, palette: &Palette, preferred_colors: Option<&[(u8,u8,u8)]>, pixels: &[(u8,u8,u8)], img_width: &usize, square_width: &usize, square_height: &usize) {
+	/*
+	square.0 refers to square width, square.1 refers to square height
+	 */
+	for square in squares.iter_mut() {
+		// figure out dominant color here, set square.2 to that
+		// color_counts1 and color_counts2 are parallel
+		let mut color_counts_color: Vec<(u8,u8,u8)> = Vec::new();
+		let mut color_counts_count: Vec<u64> = Vec::new();
+		for y in *square.y()..(square_height + square.y()) {
+			for x in *square.x()..(square_width + square.x()) {
+				let this_overall_index = (y * img_width) + x;
+				let this_rgb = pixels[this_overall_index];
+				let mut this_color = (this_rgb.0, this_rgb.1, this_rgb.2);
+				
+				if let Some(pcs) = preferred_colors {
+					if !pcs.contains(&this_rgb) {
+						let pcs_dists = {
+							let mut tmp_dif_vec = Vec::new();
+							for color in pcs {
+								let r_diff = this_rgb.0 as f32 - color.0 as f32;
+								let g_diff = this_rgb.1 as f32 - color.1 as f32;
+								let b_diff = this_rgb.2 as f32 - color.2 as f32;
+								let t_diff = (r_diff.abs() + g_diff.abs() + b_diff.abs()) / 3.;
+								tmp_dif_vec.push(t_diff);
+							}//end getting total average difference to each color
+							tmp_dif_vec
+						};
+						let mut closest = (0, 1000.);
+						for (idx, pcs_dist) in pcs_dists.iter().enumerate() {
+							if *pcs_dist < closest.1 {
+								closest = (idx, *pcs_dist);
+							}//end if we have a better closest value
+						}//end finding closest of preferred colors
+						if closest.1 <= 150. && this_rgb != (255,0,255) { if let Some(color) = pcs.get(closest.0) { this_color = *color; } }
+					}//end if this color is not preferred
+				}//end if we're applying a bias
+
+				if let Some(color_index) = color_counts_color.iter().position(|&c| c == this_color) {
+					color_counts_count[color_index] += 1;
+				} else {
+					color_counts_color.push(this_color);
+					color_counts_count.push(1);
+				}//end else we need to add new entry to color counts
+			}//end looping over all x values within square
+		}//end looping over all y values within square
+
+		// bias for CA colors
+		for (i, color) in color_counts_color.iter().enumerate() {
+			match *color {
+				stairs if stairs == palette.stairs => color_counts_count[i] *= 500,
+				(255,0,255) => color_counts_count[i] *= 0,
+				_ => {},
+			}//end matching bias to colors
+		}//end applying bias to color counts
+
+		// check to see which color has the highest count
+		let mut running_most = ((40,40,40), 0);
+		for (i, count) in color_counts_count.iter().enumerate() {
+			if *count > running_most.1 { running_most = (color_counts_color[i], color_counts_count[i]); }
+		}//end getting the color that's most common from color counts
+		square.set_kind(palette.classify(running_most.0));
+	}//end figuring out which color is dominant
+}//end squareularization_get_dominant_color()
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::export::{generate_img_from_map, text::{from_text, to_text, TextCharMap}, MapKind};
+
+	const ROOMS: &str = "#######\n#..>#.#\n#.+.S.#\n#######";
+
+	#[test]
+	fn exported_image_imports_as_same_squares() {
+		let char_map = TextCharMap::default();
+		let map = from_text(ROOMS, MapKind::Room, &char_map, 4).unwrap();
+		let palette = MapKind::Room.palette();
+		let img = generate_img_from_map(&map, &palette);
+
+		let path = std::env::temp_dir().join(format!("nice-map-import-{}.png", std::process::id()));
+		img.save(&path).unwrap();
+		let imported = squareularize_image_file(&path, &palette, None, &4);
+		std::fs::remove_file(&path).unwrap();
+		let imported = imported.unwrap();
+
+		assert_eq!((imported.rows(), imported.cols()), (map.rows(), map.cols()));
+		assert_eq!(to_text(&imported, MapKind::Room, &[], &char_map), to_text(&map, MapKind::Room, &[], &char_map));
+	}//end exported_image_imports_as_same_squares()
+
+	#[test]
+	fn leftover_pixels_are_cropped() {
+		let char_map = TextCharMap::default();
+		let map = from_text(ROOMS, MapKind::Room, &char_map, 4).unwrap();
+		let palette = MapKind::Room.palette();
+		let img = generate_img_from_map(&map, &palette);
+		let padded = RgbImage::from_fn(img.width() + 3, img.height() + 2, |x, y| *img.get_pixel(x.min(img.width() - 1), y.min(img.height() - 1)));
+
+		let imported = squareularize_image(&padded, &palette, None, &4).unwrap();
+		assert_eq!(to_text(&imported, MapKind::Room, &[], &char_map), to_text(&map, MapKind::Room, &[], &char_map));
+	}//end leftover_pixels_are_cropped()
+
+	#[test]
+	fn bad_input_is_an_error() {
+		let palette = MapKind::Cave.palette();
+		let img = RgbImage::new(3, 3);
+		assert!(squareularize_image(&img, &palette, None, &0).is_err());
+		assert!(squareularize_image(&img, &palette, None, &4).is_err());
+		assert!(squareularize_image(&img, &palette, None, &3).is_ok());
+
+		let dir = std::env::temp_dir();
+		let missing = dir.join(format!("nice-map-import-missing-{}.png", std::process::id()));
+		assert!(squareularize_image_file(&missing, &palette, None, &4).is_err());
+
+		let garbage = dir.join(format!("nice-map-import-garbage-{}.png", std::process::id()));
+		std::fs::write(&garbage, b"\x89PNG\r\n\x1a\nnot really a png").unwrap();
+		let result = squareularize_image_file(&garbage, &palette, None, &4);
+		std::fs::remove_file(&garbage).unwrap();
+		assert!(result.is_err());
+	}//end bad_input_is_an_error()
+}//end mod tests
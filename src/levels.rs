@@ -0,0 +1,413 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{squares::{CellKind, SquareGrid}, stairs::{find_stairs, is_stairs, StairDirection, StairLink, StairProblem}};
+
+/// An ordered set of levels, each one a [SquareGrid].
+/// Every level shares the same dimensions, so squares line up between levels.
+/// There is always at least one level, and one of them is the current level being worked on.  
+/// Each level also has a list of [StairLink]s recording where its stairs lead.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(try_from = "SavedLevelStack", into = "SavedLevelStack")]
+pub struct LevelStack {
+	levels: Vec<SquareGrid>,
+	current: usize,
+	stair_links: Vec<Vec<StairLink>>,
+}//end struct LevelStack
+
+impl LevelStack {
+	/// Creates a stack with a single level, which is the current level.
+	pub fn new(first_level: SquareGrid) -> LevelStack {
+		LevelStack { levels: vec![first_level], current: 0, stair_links: vec![Vec::new()] }
+	}//end new(first_level)
+
+	/// Creates a stack from existing levels, with the first level as the current level.
+	/// Returns Err if there are no levels, or if any level has different dimensions than the first.
+	pub fn from_levels(levels: Vec<SquareGrid>) -> Result<LevelStack, String> {
+		let first_level = match levels.first() {
+			Some(first_level) => first_level,
+			None => return Err(String::from("A level stack needs at least one level.")),
+		};
+		for (idx, level) in levels.iter().enumerate() {
+			if !LevelStack::dimensions_match(first_level, level) {
+				return Err(format!("Level {} has different dimensions than level 1.", idx + 1));
+			}//end if dimensions differ
+		}//end checking each level
+		let stair_links = vec![Vec::new(); levels.len()];
+		Ok(LevelStack { levels, current: 0, stair_links })
+	}//end from_levels(levels)
+
+	/// Creates a stack of count levels with the same dimensions as template, with every square set to kind.
+	/// The first level is the current level. At least one level is always created.
+	pub fn with_blank_levels(template: &SquareGrid, count: usize, kind: CellKind) -> LevelStack {
+		let blank_level = LevelStack::blank_level(template, kind);
+		LevelStack { levels: vec![blank_level; count.max(1)], current: 0, stair_links: vec![Vec::new(); count.max(1)] }
+	}//end with_blank_levels(template, count, kind)
+
+	/// Returns true if a and b have the same rows, columns, and image size.
+	pub fn dimensions_match(a: &SquareGrid, b: &SquareGrid) -> bool {
+		a.rows() == b.rows() && a.cols() == b.cols() && a.img_width() == b.img_width() && a.img_height() == b.img_height()
+	}//end dimensions_match(a, b)
+
+	/// Returns true if level has the same dimensions as the levels in this stack.
+	pub fn fits(&self, level: &SquareGrid) -> bool {
+		LevelStack::dimensions_match(&self.levels[0], level)
+	}//end fits(self, level)
+
+	/// All levels, in order.
+	pub fn levels(&self) -> &Vec<SquareGrid> { &self.levels }
+	/// The number of levels.
+	pub fn len(&self) -> usize { self.levels.len() }
+	/// Always false, since a stack keeps at least one level.
+	pub fn is_empty(&self) -> bool { self.levels.is_empty() }
+	/// Index of the current level, starting at 0.
+	pub fn current_index(&self) -> &usize { &self.current }
+	/// The level currently being worked on.
+	pub fn current(&self) -> &SquareGrid { &self.levels[self.current] }
+	/// The level currently being worked on.
+	pub fn current_mut(&mut self) -> &mut SquareGrid { &mut self.levels[self.current] }
+	/// The level at idx, if there is one.
+	pub fn get(&self, idx: usize) -> Option<&SquareGrid> { self.levels.get(idx) }
+	/// The level at idx, if there is one.
+	pub fn get_mut(&mut self, idx: usize) -> Option<&mut SquareGrid> { self.levels.get_mut(idx) }
+
+	/// Changes which level is the current level.
+	pub fn set_current_index(&mut self, idx: usize) -> Result<(), String> {
+		if idx >= self.levels.len() {
+			return Err(format!("Can't switch to level {}, since there are only {} levels.", idx + 1, self.levels.len()));
+		}//end if idx is out of bounds
+		self.current = idx;
+		Ok(())
+	}//end set_current_index(self, idx)
+
+	/// Replaces the level at idx with level.
+	/// Returns Err if idx is out of bounds or level has different dimensions.
+	pub fn set_level(&mut self, idx: usize, level: SquareGrid) -> Result<(), String> {
+		if !self.fits(&level) {
+			return Err(format!("Can't set level {}, since it has different dimensions than the other levels.", idx + 1));
+		}//end if level doesn't fit
+		match self.levels.get_mut(idx) {
+			Some(old_level) => { *old_level = level; Ok(()) },
+			None => Err(format!("Can't set level {}, since there are only {} levels.", idx + 1, self.levels.len())),
+		}//end matching whether idx is in bounds
+	}//end set_level(self, idx, level)
+
+	/// Adds level to the end of the stack.
+	/// Returns Err if level has different dimensions.
+	pub fn push_level(&mut self, level: SquareGrid) -> Result<(), String> {
+		self.insert_level(self.levels.len(), level)
+	}//end push_level(self, level)
+
+	/// Inserts level at idx, moving later levels up by one.
+	/// The current level stays the same level, even if its index changes.
+	pub fn insert_level(&mut self, idx: usize, level: SquareGrid) -> Result<(), String> {
+		if !self.fits(&level) {
+			return Err(format!("Can't add level {}, since it has different dimensions than the other levels.", idx + 1));
+		}//end if level doesn't fit
+		if idx > self.levels.len() {
+			return Err(format!("Can't add level {}, since there are only {} levels.", idx + 1, self.levels.len()));
+		}//end if idx is out of bounds
+		self.levels.insert(idx, level);
+		self.stair_links.insert(idx, Vec::new());
+		if idx <= self.current { self.current += 1; }
+		self.retarget_stair_links(|target| Some(if target >= idx { target + 1 } else { target }));
+		Ok(())
+	}//end insert_level(self, idx, level)
+
+	/// Removes and returns the level at idx.
+	/// Returns Err if idx is out of bounds or it is the only level left.
+	pub fn remove_level(&mut self, idx: usize) -> Result<SquareGrid, String> {
+		if idx >= self.levels.len() {
+			return Err(format!("Can't remove level {}, since there are only {} levels.", idx + 1, self.levels.len()));
+		}//end if idx is out of bounds
+		if self.levels.len() == 1 {
+			return Err(String::from("Can't remove the only level."));
+		}//end if this is the last level
+		let removed = self.levels.remove(idx);
+		self.stair_links.remove(idx);
+		if idx < self.current || self.current >= self.levels.len() { self.current -= 1; }
+		self.retarget_stair_links(|target| match target.cmp(&idx) {
+			std::cmp::Ordering::Less => Some(target),
+			std::cmp::Ordering::Equal => None,
+			std::cmp::Ordering::Greater => Some(target - 1),
+		});
+		Ok(removed)
+	}//end remove_level(self, idx)
+
+	/// Adds or removes levels at the end of the stack until there are count levels.
+	/// New levels have every square set to kind. At least one level is always kept.
+	pub fn set_level_count(&mut self, count: usize, kind: CellKind) {
+		let count = count.max(1);
+		let blank_level = LevelStack::blank_level(&self.levels[0], kind);
+		self.levels.resize(count, blank_level);
+		self.stair_links.resize(count, Vec::new());
+		if self.current >= count { self.current = count - 1; }
+		self.retarget_stair_links(|target| if target < count { Some(target) } else { None });
+	}//end set_level_count(self, count, kind)
+
+	/// Links for the stairs on level, if there is such a level.
+	pub fn stair_links(&self, level: usize) -> Option<&Vec<StairLink>> { self.stair_links.get(level) }
+
+	/// The link for the stair at row, col on level, if it has one.
+	pub fn stair_link(&self, level: usize, row: usize, col: usize) -> Option<&StairLink> {
+		self.stair_links.get(level)?.iter().find(|link| link.row == row && link.col == col)
+	}//end stair_link(self, level, row, col)
+
+	/// Sets the link for a stair on level, replacing any link already at the same coordinates.
+	/// Returns Err if level doesn't exist or the link is outside the level.
+	pub fn set_stair_link(&mut self, level: usize, link: StairLink) -> Result<(), String> {
+		if link.row >= *self.levels[0].rows() || link.col >= *self.levels[0].cols() {
+			return Err(format!("Can't link stairs at Row: {}, Col: {}, since they're outside the level.", link.row, link.col));
+		}//end if link is out of bounds
+		let level_links = match self.stair_links.get_mut(level) {
+			Some(level_links) => level_links,
+			None => return Err(format!("Can't link stairs on level {}, since there are only {} levels.", level + 1, self.levels.len())),
+		};
+		level_links.retain(|old_link| old_link.row != link.row || old_link.col != link.col);
+		level_links.push(link);
+		Ok(())
+	}//end set_stair_link(self, level, link)
+
+	/// Replaces every stair link on level with links.
+	pub fn set_stair_links(&mut self, level: usize, links: Vec<StairLink>) -> Result<(), String> {
+		match self.stair_links.get_mut(level) {
+			Some(level_links) => level_links.clear(),
+			None => return Err(format!("Can't link stairs on level {}, since there are only {} levels.", level + 1, self.levels.len())),
+		}//end matching whether level exists
+		for link in links {
+			self.set_stair_link(level, link)?;
+		}//end setting each link
+		Ok(())
+	}//end set_stair_links(self, level, links)
+
+	/// Removes and returns the link for the stair at row, col on level, if it had one.
+	pub fn remove_stair_link(&mut self, level: usize, row: usize, col: usize) -> Option<StairLink> {
+		let level_links = self.stair_links.get_mut(level)?;
+		let link_idx = level_links.iter().position(|link| link.row == row && link.col == col)?;
+		Some(level_links.remove(link_idx))
+	}//end remove_stair_link(self, level, row, col)
+
+	/// Links unlinked stairs on adjacent levels which are at the same coordinates.  
+	/// The stair on the higher level leads down, and the stair on the lower level leads up.
+	/// Levels are paired from the top down, so a stair matching stairs both above and below is paired with the one above.
+	/// Stairs which already have links are left alone. Returns the number of pairs made.
+	pub fn auto_pair_stairs(&mut self) -> usize {
+		let mut pairs_made = 0;
+		for upper_level in 0..self.levels.len().saturating_sub(1) {
+			let lower_level = upper_level + 1;
+			for (row, col) in find_stairs(&self.levels[upper_level]) {
+				if self.stair_link(upper_level, row, col).is_some() { continue; }
+				if !is_stairs(&self.levels[lower_level], row, col) { continue; }
+				if self.stair_link(lower_level, row, col).is_some() { continue; }
+				self.stair_links[upper_level].push(StairLink::new(row, col, StairDirection::Down, Some(lower_level)));
+				self.stair_links[lower_level].push(StairLink::new(row, col, StairDirection::Up, Some(upper_level)));
+				pairs_made += 1;
+			}//end checking each stair on upper level
+		}//end looping over each pair of adjacent levels
+		pairs_made
+	}//end auto_pair_stairs(self)
+
+	/// Checks every level for stairs that don't lead anywhere, don't line up with their target, or don't lead back.
+	/// Returns an empty list if every stair is fine.
+	pub fn validate_stairs(&self) -> Vec<StairProblem> {
+		let mut problems = Vec::new();
+		for level in 0..self.levels.len() {
+			for (row, col) in find_stairs(&self.levels[level]) {
+				if self.stair_link(level, row, col).is_none() { problems.push(StairProblem::Dangling { level, row, col }); }
+			}//end checking each stair for a link
+
+			for link in &self.stair_links[level] {
+				let (row, col) = (link.row, link.col);
+				if !is_stairs(&self.levels[level], row, col) {
+					problems.push(StairProblem::Dangling { level, row, col });
+					continue;
+				}//end if link isn't on a stair
+				let target_level = match (link.direction, link.target_level) {
+					(StairDirection::Outside, _) => continue,
+					(_, Some(target_level)) if target_level < self.levels.len() => target_level,
+					_ => { problems.push(StairProblem::Dangling { level, row, col }); continue; },
+				};
+				let points_right_way = match link.direction {
+					StairDirection::Up => target_level < level,
+					_ => target_level > level,
+				};
+				if !points_right_way || !is_stairs(&self.levels[target_level], row, col) {
+					problems.push(StairProblem::Misaligned { level, row, col, target_level });
+					continue;
+				}//end if target doesn't line up
+				let links_back = match self.stair_link(target_level, row, col) {
+					Some(target_link) => target_link.target_level == Some(level) && target_link.direction == link.direction.opposite(),
+					None => false,
+				};
+				if !links_back { problems.push(StairProblem::OneWay { level, row, col, target_level }); }
+			}//end checking each link
+		}//end checking each level
+		problems
+	}//end validate_stairs(self)
+
+	/// Changes the target level of every stair link using retarget, which returns None if the target no longer exists.
+	fn retarget_stair_links(&mut self, retarget: impl Fn(usize) -> Option<usize>) {
+		for link in self.stair_links.iter_mut().flatten() {
+			link.target_level = link.target_level.and_then(&retarget);
+		}//end retargeting each link
+	}//end retarget_stair_links(self, retarget)
+
+	/// Makes a copy of template with every square set to kind.
+	fn blank_level(template: &SquareGrid, kind: CellKind) -> SquareGrid {
+		let mut blank_level = template.clone();
+		for square in blank_level.iter_mut() {
+			square.set_kind(kind);
+		}//end blanking each square
+		blank_level
+	}//end blank_level(template, kind)
+}//end impl for LevelStack
+
+/// The form a [LevelStack] is saved in.
+/// Loading goes through [LevelStack::from_levels], so mismatched levels are rejected.
+#[derive(Serialize, Deserialize)]
+struct SavedLevelStack {
+	levels: Vec<SquareGrid>,
+	current: usize,
+	#[serde(default)]
+	stair_links: Vec<Vec<StairLink>>,
+}//end struct SavedLevelStack
+
+impl From<LevelStack> for SavedLevelStack {
+	fn from(stack: LevelStack) -> Self {
+		SavedLevelStack { levels: stack.levels, current: stack.current, stair_links: stack.stair_links }
+	}//end from(stack)
+}//end impl From<LevelStack> for SavedLevelStack
+
+impl TryFrom<SavedLevelStack> for LevelStack {
+	type Error = String;
+	fn try_from(saved: SavedLevelStack) -> Result<Self, Self::Error> {
+		let mut stack = LevelStack::from_levels(saved.levels)?;
+		stack.set_current_index(saved.current)?;
+		if saved.stair_links.len() > stack.len() {
+			return Err(format!("There are stair links for {} levels, but only {} levels.", saved.stair_links.len(), stack.len()));
+		}//end if there are too many stair links
+		for (level, links) in saved.stair_links.into_iter().enumerate() {
+			stack.set_stair_links(level, links)?;
+		}//end setting links for each level
+		Ok(stack)
+	}//end try_from(saved)
+}//end impl TryFrom<SavedLevelStack> for LevelStack
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::export::{text::{from_text, TextCharMap}, MapKind};
+
+	fn stair_level() -> SquareGrid {
+		from_text(".>\n>.", MapKind::Room, &TextCharMap::default(), 1).unwrap()
+	}//end stair_level()
+
+	/// Three levels, with the stair at 0,1 joining levels 0 and 1, and the stair at 1,0 joining levels 1 and 2.
+	fn linked_stack() -> LevelStack {
+		let mut stack = LevelStack::from_levels(vec![stair_level(), stair_level(), stair_level()]).unwrap();
+		stack.set_stair_link(0, StairLink::new(0, 1, StairDirection::Down, Some(1))).unwrap();
+		stack.set_stair_link(1, StairLink::new(0, 1, StairDirection::Up, Some(0))).unwrap();
+		stack.set_stair_link(1, StairLink::new(1, 0, StairDirection::Down, Some(2))).unwrap();
+		stack.set_stair_link(2, StairLink::new(1, 0, StairDirection::Up, Some(1))).unwrap();
+		stack
+	}//end linked_stack()
+
+	fn target_of(stack: &LevelStack, level: usize, row: usize, col: usize) -> Option<usize> {
+		stack.stair_link(level, row, col).and_then(|link| link.target_level)
+	}//end target_of(stack, level, row, col)
+
+	#[test]
+	fn insert_level_moves_later_targets_up() {
+		let mut stack = linked_stack();
+		stack.set_current_index(1).unwrap();
+		stack.insert_level(1, stair_level()).unwrap();
+		assert_eq!(stack.len(), 4);
+		assert_eq!(*stack.current_index(), 2);
+		assert!(stack.stair_links(1).unwrap().is_empty());
+		assert_eq!(target_of(&stack, 0, 0, 1), Some(2));
+		assert_eq!(target_of(&stack, 2, 0, 1), Some(0));
+		assert_eq!(target_of(&stack, 2, 1, 0), Some(3));
+		assert_eq!(target_of(&stack, 3, 1, 0), Some(2));
+	}//end insert_level_moves_later_targets_up()
+
+	#[test]
+	fn remove_level_drops_links_to_it() {
+		let mut stack = linked_stack();
+		stack.set_current_index(2).unwrap();
+		stack.remove_level(1).unwrap();
+		assert_eq!(stack.len(), 2);
+		assert_eq!(*stack.current_index(), 1);
+		assert!(stack.stair_link(0, 0, 1).is_some());
+		assert_eq!(target_of(&stack, 0, 0, 1), None);
+		assert_eq!(target_of(&stack, 1, 1, 0), None);
+		assert!(stack.validate_stairs().iter().all(|problem| matches!(problem, StairProblem::Dangling { .. })));
+	}//end remove_level_drops_links_to_it()
+
+	#[test]
+	fn remove_level_keeps_earlier_targets() {
+		let mut stack = linked_stack();
+		stack.remove_level(2).unwrap();
+		assert_eq!(target_of(&stack, 0, 0, 1), Some(1));
+		assert_eq!(target_of(&stack, 1, 0, 1), Some(0));
+		assert_eq!(target_of(&stack, 1, 1, 0), None);
+		assert!(stack.remove_level(1).is_ok());
+		assert!(stack.remove_level(0).is_err());
+	}//end remove_level_keeps_earlier_targets()
+
+	#[test]
+	fn insert_level_rejects_bad_index_and_size() {
+		let mut stack = linked_stack();
+		assert!(stack.insert_level(4, stair_level()).is_err());
+		let bigger = from_text("...\n...", MapKind::Room, &TextCharMap::default(), 1).unwrap();
+		assert!(stack.insert_level(0, bigger).is_err());
+		assert_eq!(stack, linked_stack());
+	}//end insert_level_rejects_bad_index_and_size()
+	/// [linked_stack()] with the two stairs it leaves unlinked leading outside, so every stair is valid.
+	fn valid_stack() -> LevelStack {
+		let mut stack = linked_stack();
+		stack.set_stair_link(0, StairLink::outside(1, 0)).unwrap();
+		stack.set_stair_link(2, StairLink::outside(0, 1)).unwrap();
+		stack
+	}//end valid_stack()
+
+	#[test]
+	fn valid_stack_has_no_stair_problems() {
+		assert_eq!(valid_stack().validate_stairs(), Vec::new());
+		let mut stack = LevelStack::from_levels(vec![stair_level(), stair_level()]).unwrap();
+		assert_eq!(stack.auto_pair_stairs(), 2);
+		assert_eq!(stack.validate_stairs(), Vec::new());
+	}//end valid_stack_has_no_stair_problems()
+
+	#[test]
+	fn missing_stair_is_a_problem() {
+		let mut stack = valid_stack();
+		stack.get_mut(1).unwrap().get_mut(&0, &1).unwrap().set_kind(CellKind::Floor);
+		let problems = stack.validate_stairs();
+		assert_eq!(problems.len(), 2);
+		assert!(problems.contains(&StairProblem::Misaligned { level: 0, row: 0, col: 1, target_level: 1 }));
+		assert!(problems.contains(&StairProblem::Dangling { level: 1, row: 0, col: 1 }));
+
+		let mut stack = valid_stack();
+		stack.remove_stair_link(2, 0, 1);
+		assert_eq!(stack.validate_stairs(), vec![StairProblem::Dangling { level: 2, row: 0, col: 1 }]);
+	}//end missing_stair_is_a_problem()
+
+	#[test]
+	fn stair_without_partner_is_a_problem() {
+		let mut stack = valid_stack();
+		stack.remove_stair_link(1, 0, 1);
+		let problems = stack.validate_stairs();
+		assert_eq!(problems.len(), 2);
+		assert!(problems.contains(&StairProblem::OneWay { level: 0, row: 0, col: 1, target_level: 1 }));
+		assert!(problems.contains(&StairProblem::Dangling { level: 1, row: 0, col: 1 }));
+
+		let mut stack = valid_stack();
+		stack.set_stair_link(1, StairLink::new(0, 1, StairDirection::Down, Some(2))).unwrap();
+		let problems = stack.validate_stairs();
+		assert!(problems.contains(&StairProblem::OneWay { level: 0, row: 0, col: 1, target_level: 1 }));
+		assert!(problems.contains(&StairProblem::OneWay { level: 1, row: 0, col: 1, target_level: 2 }));
+
+		let mut stack = valid_stack();
+		stack.set_stair_link(0, StairLink::new(0, 1, StairDirection::Up, Some(1))).unwrap();
+		assert_eq!(stack.validate_stairs(), vec![StairProblem::Misaligned { level: 0, row: 0, col: 1, target_level: 1 }, StairProblem::OneWay { level: 1, row: 0, col: 1, target_level: 0 }]);
+	}//end stair_without_partner_is_a_problem()
+}//end mod tests
@@ -5,4 +5,14 @@ pub mod cellular_automata;
 
 pub mod room_growth;
 
-pub mod export;
\ No newline at end of file
+pub mod export;
+
+pub mod import;
+
+pub mod project;
+
+pub mod levels;
+
+pub mod stairs;
+
+pub mod zones;
\ No newline at end of file
@@ -1,6 +1,7 @@
 
 use gui::GUI;
-use nice_map_generator::{cellular_automata::CA, export, room_growth::CRG};
+use rand::Rng;
+use nice_map_generator::{cellular_automata::{CARule, CA}, export::{render::{self, MapTheme, RenderOptions}, svg::{self, ScaleBar, SvgOptions}, text::{self, TextCharMap}, tiled::{self, TiledSettings}, MapKind}, levels::LevelStack, project::Project, room_growth::CRG, stairs::StairDirection};
 
 mod gui;
 
@@ -15,11 +16,38 @@ fn main() {
         if let Some(val) = gui.msg_receiver.recv() {
             // Todo: match message
             match val.as_str() {
-                "MenuChoice::Choice1" => {
-                    gui.switch_tab(0);
+                "MenuChoice::SaveProject" => {
+                    if let Some(mut pathbuf) = GUI::save_project_dialog() {
+                        if pathbuf.extension().is_none() { pathbuf.set_extension("json"); }
+                        if let Err(msg) = gui.get_project().save(&pathbuf) {
+                            GUI::alert(&format!("Error message:\n{}", msg));
+                        }//end if saving failed
+                    }//end if we have a file path to save to
                 },
-                "MenuChoice::Choice2" => {
-                    gui.switch_tab(1);
+                "MenuChoice::OpenProject" => {
+                    if let Some(pathbuf) = GUI::open_project_dialog() {
+                        match Project::load(&pathbuf) {
+                            Ok(project) => gui.load_project(&project),
+                            Err(msg) => GUI::alert(&format!("Error message:\n{}", msg)),
+                        }//end matching whether we could load project
+                    }//end if we have a file path to open
+                },
+                "MenuChoice::ImportImage" => {
+                    let cave_room_choice = GUI::choice_dialog("Do you want to import the image as a cave map or a room map?", "Cave", "Room");
+                    if let Some(cave_room_choice) = cave_room_choice {
+                        if let Some(pathbuf) = GUI::open_img_dialog() {
+                            match GUI::number_dialog("How many pixels across is each square in the image?", 10) {
+                                Some(square_size) => {
+                                    let import_res = match cave_room_choice {
+                                        "Cave" => gui.import_cave_canvas_image(&pathbuf, square_size),
+                                        "Room" => gui.import_room_canvas_image(&pathbuf, square_size),
+                                        _ => Err(format!("Unrecognized Cave or Room choice: {}", cave_room_choice)),
+                                    };
+                                    if let Err(msg) = import_res { GUI::alert(&format!("Error message:\n{}", msg)); }
+                                }, None => GUI::alert("Square size should be a whole number of pixels."),
+                            }//end matching whether we got a square size
+                        }//end if we have a file path to import
+                    }//end if user is deciding between cave and room map to import
                 },
                 "CaveGen:Canvas:Update" => {
                     gui.update_cave_canvas();
@@ -29,34 +57,93 @@ fn main() {
                     gui.update_room_canvas();
                     println!("Told build canvas to update");
                 },
+                "CaveGen:Level:Down" => gui.change_cave_canvas_level(-1),
+                "CaveGen:Level:Up" => gui.change_cave_canvas_level(1),
+                "RoomGen:Level:Down" => gui.change_room_canvas_level(-1),
+                "RoomGen:Level:Up" => gui.change_room_canvas_level(1),
                 "CaveGen:CA:RunGeneration" => {
-                    match gui.get_cave_canvas_squareularization() {
-                        Some(squares) => {
+                    match gui.get_cave_canvas_levels() {
+                        Some(mut level_stack) => {
                             println!("Go some squareularization info from the GUI. Getting ready to run some CA generations.");
 
                             let ca_info = gui.get_cave_canvas_ca_settings();
-                            // TODO: Run those squares through the CA
-                            let mut ca_runner = CA::new(ca_info.0, ca_info.1).with_squares(squares);
+                            let (neighborhood, boundary, schedule_text) = gui.get_cave_canvas_ca_rule_settings();
+                            let rule_schedule = match schedule_text.is_empty() {
+                                true => Ok(vec![(CARule::from_thresholds(ca_info.1, ca_info.1, neighborhood, ca_info.0), 1)]),
+                                false => CARule::parse_schedule(&schedule_text, neighborhood, ca_info.0),
+                            };
+                            let rule_schedule = match rule_schedule {
+                                Ok(rule_schedule) => rule_schedule,
+                                Err(msg) => { GUI::alert(&format!("Error message:\n{}", msg)); continue; },
+                            };
 
-                            for _ in 0..ca_info.2 {
-                                ca_runner.run_generation();
-                                match ca_runner.get_squares() {
-                                    Some(changed_squares) => {
-                                        // return out squares back to gui
-                                        gui.set_cave_canvas_squareularization(changed_squares);
-                                        
-                                        // TODO: Find a way to make gui update on each generation
+                            for level_idx in levels_to_process(&level_stack, gui.get_cave_canvas_all_levels()) {
+                                let squares = match level_stack.get(level_idx) { Some(squares) => squares.clone(), None => continue };
+                                let mut ca_runner = CA::new(ca_info.0, ca_info.1).with_rule_schedule(rule_schedule.clone()).with_boundary(boundary).with_squares(squares);
 
-                                        println!("Finished CA generation {} and sent squareularization back to the GUI to display.", ca_runner.generations_so_far());
-                                    }, None => println!("CA Gen Failed or couldn't get squares."),
-                                }//end matching based on squares
+                                for _ in 0..ca_info.2 {
+                                    ca_runner.run_generation();
+                                    // TODO: Find a way to make gui update on each generation
+                                    println!("Finished CA generation {} on level {}.", ca_runner.generations_so_far(), level_idx + 1);
+                                }//end running for each generation
 
-                            }//end running for each generation
+                                match ca_runner.get_squares() {
+                                    Some(changed_squares) => if let Err(msg) = level_stack.set_level(level_idx, changed_squares.clone()) { println!("{}", msg); },
+                                    None => println!("CA Gen Failed or couldn't get squares."),
+                                }//end matching based on squares
+                            }//end running for each level
 
+                            // return our squares back to gui
+                            gui.set_cave_canvas_levels(&level_stack);
                         },
                         None => {println!("Couldn't get square info from cave gen canvas. We can't start doing CA like this.");}
                     };
                 },
+                "CaveGen:CA:RandomFill" => {
+                    match gui.get_cave_canvas_levels() {
+                        Some(mut level_stack) => {
+                            let (fraction, seed) = gui.get_cave_canvas_fill_settings();
+                            let seed = match seed { Some(seed) => seed, None => rand::thread_rng().gen() };
+                            gui.set_cave_canvas_seed(seed);
+                            for level_idx in levels_to_process(&level_stack, gui.get_cave_canvas_all_levels()) {
+                                let squares = match level_stack.get(level_idx) { Some(squares) => squares.clone(), None => continue };
+                                let mut ca_runner = CA::default().with_squares(squares);
+                                // offset seed by level so that levels don't all get the same fill
+                                match ca_runner.fill_random_walls(fraction, seed.wrapping_add(level_idx as u64), None) {
+                                    Ok(_) => match ca_runner.get_squares() {
+                                        Some(changed_squares) => if let Err(msg) = level_stack.set_level(level_idx, changed_squares.clone()) { println!("{}", msg); },
+                                        None => println!("Couldn't get squares back after random fill."),
+                                    }, Err(msg) => { GUI::alert(&format!("Error message:\n{}", msg)); break; },
+                                }//end matching result of random fill
+                            }//end filling each level
+                            gui.set_cave_canvas_levels(&level_stack);
+                        },
+                        None => println!("Couldn't get square info from cave gen canvas. We can't do a random fill like this."),
+                    }//end matching whether we could get the cave canvas
+                },
+                "CaveGen:Regions:RemoveSmall" | "CaveGen:Regions:Connect" => {
+                    match gui.get_cave_canvas_levels() {
+                        Some(mut level_stack) => {
+                            for level_idx in levels_to_process(&level_stack, gui.get_cave_canvas_all_levels()) {
+                                let squares = match level_stack.get(level_idx) { Some(squares) => squares.clone(), None => continue };
+                                let mut ca_runner = CA::default().with_squares(squares);
+                                let proc_res = match val.as_str() {
+                                    "CaveGen:Regions:RemoveSmall" => ca_runner.remove_small_regions(gui.get_cave_canvas_min_region_size()),
+                                    "CaveGen:Regions:Connect" => ca_runner.connect_regions(),
+                                    _ => Err(format!("Unrecognized command/message {}", val))
+                                };
+                                match proc_res {
+                                    Ok(_) => match ca_runner.get_squares() {
+                                        Some(changed_squares) => if let Err(msg) = level_stack.set_level(level_idx, changed_squares.clone()) { println!("{}", msg); },
+                                        None => println!("Couldn't get squares back after processing regions."),
+                                    }, Err(msg) => { GUI::alert(&format!("Error message on level {}:\n{}", level_idx + 1, msg)); break; },
+                                }//end matching result of process
+                            }//end processing each level
+                            gui.set_cave_canvas_levels(&level_stack);
+                        },
+                        None => println!("Couldn't get square info from cave gen canvas. We can't process regions like this."),
+                    }//end matching whether we could get the cave canvas
+                },
                 "CaveGen:Stairs:Remove" => {
                     let selected_elem_list = gui.get_cave_gen_stairs_selected();
                     if selected_elem_list.len() != 0 {
@@ -81,51 +168,217 @@ fn main() {
                         GUI::message("No Level Connections Selected. Please select connections to remove.");
                     }//end else we just need to say that there are no level connections selected
                 },
+                "CaveGen:Stairs:Edit" | "RoomGen:Stairs:Edit" => {
+                    let is_cave = val.starts_with("CaveGen:");
+                    let selected_elem_list = match is_cave {
+                        true => gui.get_cave_gen_stairs_selected(),
+                        false => gui.get_room_gen_stairs_selected(),
+                    };
+                    if selected_elem_list.is_empty() {
+                        GUI::message("No Level Connections Selected. Please select connections to edit.");
+                        continue;
+                    }//end if there are no level connections selected
+                    let level_stack = match is_cave { true => gui.get_cave_canvas_levels(), false => gui.get_room_canvas_levels() };
+                    let current_level = level_stack.map(|level_stack| *level_stack.current_index() + 1).unwrap_or(1);
+                    if let Some(direction) = GUI::stair_direction_dialog() {
+                        let target_level = match direction {
+                            StairDirection::Outside => None,
+                            StairDirection::Up => GUI::number_dialog("Which level do these stairs lead up to?", current_level.saturating_sub(1).max(1)),
+                            StairDirection::Down => GUI::number_dialog("Which level do these stairs lead down to?", current_level + 1),
+                        };
+                        let target_level = match (direction, target_level) {
+                            (StairDirection::Outside, _) => None,
+                            (_, Some(target_level)) if target_level >= 1 => Some(target_level - 1),
+                            _ => { GUI::alert("Level should be a whole number, starting from 1."); continue; },
+                        };
+                        match is_cave {
+                            true => gui.set_cave_gen_stairs_selected_link(direction, target_level),
+                            false => gui.set_room_gen_stairs_selected_link(direction, target_level),
+                        }//end matching which tab to edit stairs on
+                    }//end if user chose a direction
+                },
+                "CaveGen:Stairs:AutoPair" | "RoomGen:Stairs:AutoPair" | "CaveGen:Stairs:Check" | "RoomGen:Stairs:Check" => {
+                    let is_cave = val.starts_with("CaveGen:");
+                    let level_stack = match is_cave {
+                        true => gui.get_cave_canvas_levels(),
+                        false => gui.get_room_canvas_levels(),
+                    };
+                    match level_stack {
+                        Some(mut level_stack) => {
+                            if val.ends_with(":AutoPair") {
+                                let pairs_made = level_stack.auto_pair_stairs();
+                                match is_cave {
+                                    true => gui.set_cave_canvas_levels(&level_stack),
+                                    false => gui.set_room_canvas_levels(&level_stack),
+                                }//end matching which tab to update
+                                GUI::message(&format!("Linked {} pairs of stairs.", pairs_made));
+                            } else {
+                                let problems = level_stack.validate_stairs();
+                                if problems.is_empty() {
+                                    GUI::message("Every stair leads somewhere and lines up with its target.");
+                                } else {
+                                    let mut message = format!("Found {} problems with stairs:\n", problems.len());
+                                    for problem in problems.iter().take(20) { message += &format!("{}\n", problem); }
+                                    if problems.len() > 20 { message += &format!("...and {} more.", problems.len() - 20); }
+                                    GUI::message(&message);
+                                }//end else we need to list problems
+                            }//end else we're checking stairs
+                        }, None => println!("Couldn't get levels for {}.", val),
+                    }//end matching whether we could get levels
+                },
                 "Export:PNG" | "Export:JPEG" | "Export:BMP" | "Export:WEBP" => {
                     let img_format = val.split(":").last().unwrap_or("PNG").to_lowercase();
                     let cave_room_choice = GUI::choice_dialog("Do you want to save a cave map or a room map?", "Cave", "Room");
                     if let Some(cave_room_choice) = cave_room_choice {
+                        let (squares, map_kind) = match cave_room_choice {
+                            "Cave" => (gui.get_cave_canvas_squareularization(), MapKind::Cave),
+                            _ => (gui.get_room_canvas_squareularization(), MapKind::Room),
+                        };
+                        let squares = match squares {
+                            Some(squares) => squares,
+                            None => { println!("Couldn't get {} canvas to export.", cave_room_choice); continue; },
+                        };
+                        let theme = match GUI::choice_dialog("Which colors do you want to export with?", "Editing", "Themed") {
+                            Some("Editing") => MapTheme::editing(),
+                            Some(_) => match GUI::yes_no("Do you want to use a theme file?\nOtherwise, the default theme will be used.") {
+                                true => match GUI::open_theme_dialog() {
+                                    Some(pathbuf) => match MapTheme::load(&pathbuf) {
+                                        Ok(theme) => theme,
+                                        Err(msg) => { GUI::alert(&format!("Error message:\n{}", msg)); continue; },
+                                    }, None => continue,
+                                }, false => MapTheme::default(),
+                            }, None => continue,
+                        };
+                        let default_scale = squares.iter().next().map(|square| *square.width()).unwrap_or(1);
+                        let scale = match GUI::number_dialog("How many pixels across should each square be?", default_scale) {
+                            Some(scale) if scale > 0 => scale as u32,
+                            _ => continue,
+                        };
+                        let grid_lines = match GUI::yes_no("Do you want to draw grid lines between squares?") { true => Some((96,96,96)), false => None };
                         if let Some(mut pathbuf) = GUI::save_img_dialog(&img_format) {
                             pathbuf.set_extension(img_format.to_lowercase());
-                            match cave_room_choice {
-                                "Cave" => {
-                                    if let Some(squares) = gui.get_cave_canvas_squareularization() {
-                                        let square_img = export::generate_img_from_map(&squares);
-                                        square_img.save(pathbuf).expect("Failed to save img");
-                                    }//end if we can get a SquareGrid from the cave canvas
-                                }, "Room" => {
-                                    if let Some(squares) = gui.get_room_canvas_squareularization() {
-                                        let square_img = export::generate_img_from_map(&squares);
-                                        square_img.save(pathbuf).expect("Failed to save img");
-                                    }
-                                }, _ => println!("Unrecognized Cave or Room choice: {}", cave_room_choice),
-                            }//end matching user choice
+                            let render_options = RenderOptions { scale, grid_lines, theme };
+                            if let Err(err) = render::render_map(&squares, map_kind, &render_options).save(&pathbuf) {
+                                GUI::alert(&format!("Error message:\n{}", err));
+                            }//end if saving failed
                         }//end if we have a file path to save to
                     }//end if user is deciding between cave and room map to save
                 },
-                "RoomGen:RoomStarts" | "RoomGen:InitialGrowth" | "RoomGen:LGrowth" | "RoomGen:Connectivity" => {
-                    match gui.get_room_canvas_squareularization() {
-                        Some(squares) => {
-                            let mut rg = CRG::default().with_squares(squares);
-                            let proc_res = match val.as_str() {
-                                "RoomGen:RoomStarts" => rg.add_random_room_starts(None),
-                                "RoomGen:InitialGrowth" => {
-                                    match rg.grow_rooms_from_starts(){
-                                        Ok(_) => rg.place_random_doors(0.02),
-                                        Err(er) => Err(er),
-                                    }},
-                                "RoomGen:LGrowth" => rg.grow_rooms_l_growth(),
-                                "RoomGen:Connectivity" => rg.enforce_connectivity(5),
-                                _ => Err(format!("Unrecognized command/message {}", val))
-                            };
-                            match proc_res {
-                                Ok(_) => {
-                                    match rg.get_squares() {
-                                        Some(squares) => gui.set_room_canvas_squareularization(squares),
-                                        None => println!("Couldn't get room squares back from constrained_room_growth???"),
-                                    }//end matching whether we can get squares from rg
-                                }, Err(msg) => GUI::alert(&format!("Error message:\n{}", msg))
-                            }//end matching result of process
+                "Export:SVG" => {
+                    let cave_room_choice = GUI::choice_dialog("Do you want to save a cave map or a room map?", "Cave", "Room");
+                    if let Some(cave_room_choice) = cave_room_choice {
+                        let (squares, map_kind) = match cave_room_choice {
+                            "Cave" => (gui.get_cave_canvas_squareularization(), MapKind::Cave),
+                            _ => (gui.get_room_canvas_squareularization(), MapKind::Room),
+                        };
+                        match squares {
+                            Some(squares) => {
+                                let grid_overlay = GUI::yes_no("Do you want to draw grid lines between squares?");
+                                let scale_bar = match GUI::number_dialog("How many squares long should the scale bar be?\nUse 0 for no scale bar.", 0) {
+                                    Some(0) | None => None,
+                                    Some(scale_bar_squares) => Some(ScaleBar { squares: scale_bar_squares, label: format!("{} squares", scale_bar_squares) }),
+                                };
+                                let square_size = squares.iter().next().map(|square| *square.width() as f64).unwrap_or(10.0);
+                                let svg_options = SvgOptions { square_size, grid_overlay, scale_bar, ..SvgOptions::default() };
+                                if let Some(mut pathbuf) = GUI::save_svg_dialog() {
+                                    pathbuf.set_extension("svg");
+                                    if let Err(msg) = svg::save_svg_map(&squares, map_kind, &svg_options, &pathbuf) {
+                                        GUI::alert(&format!("Error message:\n{}", msg));
+                                    }//end if saving failed
+                                }//end if we have a file path to save to
+                            }, None => println!("Couldn't get {} canvas to export.", cave_room_choice),
+                        }//end matching whether we could get the canvas
+                    }//end if user is deciding between cave and room map to save
+                },
+                "Export:TMJ" | "Export:TMX" => {
+                    let file_extension = val.split(":").last().unwrap_or("TMJ").to_lowercase();
+                    let cave_room_choice = GUI::choice_dialog("Do you want to save a cave map or a room map?", "Cave", "Room");
+                    if let Some(cave_room_choice) = cave_room_choice {
+                        let (level_stack, map_kind) = match cave_room_choice {
+                            "Cave" => (gui.get_cave_canvas_levels(), MapKind::Cave),
+                            _ => (gui.get_room_canvas_levels(), MapKind::Room),
+                        };
+                        let settings = match GUI::yes_no("Do you want to use a tileset settings file?\nOtherwise, default tile IDs will be used.") {
+                            true => match GUI::open_tiled_settings_dialog() {
+                                Some(pathbuf) => match TiledSettings::load(&pathbuf) {
+                                    Ok(settings) => settings,
+                                    Err(msg) => { GUI::alert(&format!("Error message:\n{}", msg)); continue; },
+                                }, None => continue,
+                            }, false => TiledSettings::default(),
+                        };
+                        match level_stack {
+                            Some(level_stack) => if let Some(mut pathbuf) = GUI::save_tiled_dialog(&file_extension) {
+                                pathbuf.set_extension(&file_extension);
+                                let stair_links = level_stack.stair_links(*level_stack.current_index()).cloned().unwrap_or_default();
+                                if let Err(msg) = tiled::save_tiled_map(level_stack.current(), map_kind, &stair_links, &settings, &pathbuf) {
+                                    GUI::alert(&format!("Error message:\n{}", msg));
+                                }//end if saving failed
+                            }, None => println!("Couldn't get {} canvas to export.", cave_room_choice),
+                        }//end matching whether we could get the canvas
+                    }//end if user is deciding between cave and room map to save
+                },
+                "Export:TXT" => {
+                    let cave_room_choice = GUI::choice_dialog("Do you want to save a cave map or a room map?", "Cave", "Room");
+                    if let Some(cave_room_choice) = cave_room_choice {
+                        let (level_stack, map_kind) = match cave_room_choice {
+                            "Cave" => (gui.get_cave_canvas_levels(), MapKind::Cave),
+                            _ => (gui.get_room_canvas_levels(), MapKind::Room),
+                        };
+                        let char_map = match GUI::yes_no("Do you want to use a character map file?\nOtherwise, default characters will be used.") {
+                            true => match GUI::open_char_map_dialog() {
+                                Some(pathbuf) => match TextCharMap::load(&pathbuf) {
+                                    Ok(char_map) => char_map,
+                                    Err(msg) => { GUI::alert(&format!("Error message:\n{}", msg)); continue; },
+                                }, None => continue,
+                            }, false => TextCharMap::default(),
+                        };
+                        match level_stack {
+                            Some(level_stack) => if let Some(mut pathbuf) = GUI::save_text_dialog() {
+                                pathbuf.set_extension("txt");
+                                let stair_links = level_stack.stair_links(*level_stack.current_index()).cloned().unwrap_or_default();
+                                if let Err(msg) = text::save_text_map(level_stack.current(), map_kind, &stair_links, &char_map, &pathbuf) {
+                                    GUI::alert(&format!("Error message:\n{}", msg));
+                                }//end if saving failed
+                            }, None => println!("Couldn't get {} canvas to export.", cave_room_choice),
+                        }//end matching whether we could get the canvas
+                    }//end if user is deciding between cave and room map to save
+                },
+                "RoomGen:RoomStarts" | "RoomGen:InitialGrowth" | "RoomGen:LGrowth" | "RoomGen:Connectivity" | "RoomGen:Zones" | "RoomGen:SecretDoors" | "RoomGen:Loops" => {
+                    match gui.get_room_canvas_levels() {
+                        Some(mut level_stack) => {
+                            let seed = match gui.get_room_canvas_seed() { Some(seed) => seed, None => rand::thread_rng().gen() };
+                            gui.set_room_canvas_seed(seed);
+                            for level_idx in levels_to_process(&level_stack, gui.get_room_canvas_all_levels()) {
+                                let squares = match level_stack.get(level_idx) { Some(squares) => squares.clone(), None => continue };
+                                // offset seed by level so that levels don't all get the same rooms
+                                let mut rg = CRG::default().with_seed(seed.wrapping_add(level_idx as u64)).with_room_limits(gui.get_room_canvas_room_limits()).with_squares(squares);
+                                let proc_res = match val.as_str() {
+                                    "RoomGen:RoomStarts" => {
+                                        let (placement, merge_distance) = gui.get_room_canvas_start_settings();
+                                        rg.add_spaced_room_starts(None, placement).and_then(|_| rg.merge_close_room_starts(merge_distance))
+                                    },
+                                    "RoomGen:InitialGrowth" => {
+                                        match rg.grow_rooms_from_starts(){
+                                            Ok(_) => rg.place_random_doors(0.02),
+                                            Err(er) => Err(er),
+                                        }},
+                                    "RoomGen:LGrowth" => rg.grow_rooms_l_growth(),
+                                    "RoomGen:Connectivity" => rg.enforce_connectivity(gui.get_room_canvas_connectivity_limit()),
+                                    "RoomGen:Zones" => gui.get_room_canvas_zone_settings().and_then(|settings| rg.generate_zoned_rooms(&settings)),
+                                    "RoomGen:SecretDoors" => rg.place_secret_doors(gui.get_room_canvas_secret_door_chance()),
+                                    "RoomGen:Loops" => gui.get_room_canvas_loop_target(level_idx).and_then(|target| rg.inject_loops(target)),
+                                    _ => Err(format!("Unrecognized command/message {}", val))
+                                };
+                                match proc_res {
+                                    Ok(_) => {
+                                        match rg.get_squares() {
+                                            Some(squares) => if let Err(msg) = level_stack.set_level(level_idx, squares.clone()) { println!("{}", msg); },
+                                            None => println!("Couldn't get room squares back from constrained_room_growth???"),
+                                        }//end matching whether we can get squares from rg
+                                    }, Err(msg) => { GUI::alert(&format!("Error message on level {}:\n{}", level_idx + 1, msg)); break; },
+                                }//end matching result of process
+                            }//end processing each level
+                            gui.set_room_canvas_levels(&level_stack);
                         }, None => println!("Couldn't get room canvas for some reason."),
                     }//end matching whether we could get the room canvas
                 },
@@ -136,3 +389,11 @@ fn main() {
         }//end if we received a message
     }//end main app loop
 }//end main method
+
+/// Indices of the levels a process should run on, either every level or just the current one.
+fn levels_to_process(level_stack: &LevelStack, all_levels: bool) -> Vec<usize> {
+    match all_levels {
+        true => (0..level_stack.len()).collect(),
+        false => vec![*level_stack.current_index()],
+    }//end matching whether to process every level
+}//end levels_to_process(level_stack, all_levels)
@@ -0,0 +1,277 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{cellular_automata::{Boundary, Neighborhood}, levels::LevelStack, room_growth::{RoomLimits, StartPlacement}, squares::Palette, zones::ZoneSettings};
+
+/// Version of the project format written by this program.
+/// This should be increased whenever the format changes in a way that older versions can't read.
+pub const PROJECT_FORMAT_VERSION: u32 = 3;
+
+/// Everything needed to pick a map session back up later.
+/// Saved as JSON, with [version] recording which format the file uses.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Project {
+	/// Version of the project format this was saved with.
+	pub version: u32,
+	#[serde(default)]
+	pub cave: CaveProject,
+	#[serde(default)]
+	pub room: RoomProject,
+}//end struct Project
+
+/// Levels and settings for the cave generation tab.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaveProject {
+	/// Every level, including which one was being worked on, or None if the canvas was never set up.
+	pub levels: Option<LevelStack>,
+	pub settings: CaveSettings,
+}//end struct CaveProject
+
+/// Levels and settings for the room generation tab.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomProject {
+	/// Every level, including which one was being worked on, or None if the canvas was never set up.
+	pub levels: Option<LevelStack>,
+	pub settings: RoomSettings,
+}//end struct RoomProject
+
+/// Parameters for cellular automata and the steps around it.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaveSettings {
+	pub neighborhood_size: usize,
+	pub neighborhood_threshold: usize,
+	pub generations_to_run: usize,
+	pub neighborhood: Neighborhood,
+	pub boundary: Boundary,
+	/// Rule schedule in B/S notation, or empty to use [neighborhood_threshold].
+	pub rule_schedule: String,
+	/// Fraction of squares made into walls by random fill.
+	pub fill_fraction: f64,
+	pub seed: Option<u64>,
+	pub min_region_size: usize,
+}//end struct CaveSettings
+
+impl Default for CaveSettings {
+	fn default() -> Self {
+		Self {
+			neighborhood_size: 1,
+			neighborhood_threshold: 5,
+			generations_to_run: 2,
+			neighborhood: Neighborhood::Moore,
+			boundary: Boundary::Wall,
+			rule_schedule: String::new(),
+			fill_fraction: 0.5,
+			seed: None,
+			min_region_size: 10,
+		}//end struct construction
+	}//end default()
+}//end impl Default for CaveSettings
+
+/// Parameters for constrained room growth.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomSettings {
+	pub seed: Option<u64>,
+	pub connectivity_limit: usize,
+	pub zones: ZoneSettings,
+	/// Chance that each door on a shortcut becomes a secret door.
+	pub secret_door_chance: f64,
+	/// Independent cycles loop injection aims for on each level, starting from the first level.
+	/// Levels past the end of the list use the last target.
+	pub loop_targets: Vec<usize>,
+	/// How room starts are spread out when they're placed.
+	pub start_placement: StartPlacement,
+	/// Room starts closer together than this many squares are merged, or 0 to never merge them.
+	pub start_merge_distance: f64,
+	/// Limits on the size and shape of rooms grown from starts.
+	pub room_limits: RoomLimits,
+}//end struct RoomSettings
+
+impl Default for RoomSettings {
+	fn default() -> Self {
+		Self { seed: None, connectivity_limit: 5, zones: ZoneSettings::default(), secret_door_chance: 0.25, loop_targets: vec![3],
+			start_placement: StartPlacement::PoissonDisk { min_distance: 5.0 }, start_merge_distance: 3.0, room_limits: RoomLimits::default() }
+	}//end default()
+}//end impl Default for RoomSettings
+
+impl RoomSettings {
+	/// Gets the number of independent cycles loop injection should aim for on the level at level_index.  
+	/// Levels past the end of loop_targets use the last target, and every level uses 0 if there are no targets.
+	pub fn loop_target(&self, level_index: usize) -> usize {
+		self.loop_targets.get(level_index).or(self.loop_targets.last()).copied().unwrap_or(0)
+	}//end loop_target(self, level_index)
+}//end impl for RoomSettings
+
+impl Project {
+	/// Creates a project using the current format version.
+	pub fn new(cave: CaveProject, room: RoomProject) -> Project {
+		Project { version: PROJECT_FORMAT_VERSION, cave, room }
+	}//end new()
+
+	/// Converts this project to JSON text.
+	pub fn to_json(&self) -> Result<String, String> {
+		serde_json::to_string_pretty(self).map_err(|err| format!("Couldn't convert project to JSON: {}", err))
+	}//end to_json(self)
+
+	/// Reads a project from JSON text.
+	/// Returns Err if the text isn't a project, or if it was saved with a newer format version than this program understands.
+	pub fn from_json(text: &str) -> Result<Project, String> {
+		let mut value: serde_json::Value = serde_json::from_str(text).map_err(|err| format!("Couldn't read project file: {}", err))?;
+		let version = match value.get("version").and_then(|version| version.as_u64()) {
+			Some(version) => version,
+			None => return Err(String::from("Project file doesn't have a format version, so it might not be a project file.")),
+		};
+		if version > PROJECT_FORMAT_VERSION as u64 {
+			return Err(format!("Project file uses format version {}, but this program only understands up to version {}.", version, PROJECT_FORMAT_VERSION));
+		}//end if project is from a newer version
+
+		if version < 2 { Project::upgrade_from_v1(&mut value); }
+		if version < 3 { Project::upgrade_from_v2(&mut value); }
+		serde_json::from_value(value).map_err(|err| format!("Couldn't read project file: {}", err))
+	}//end from_json(text)
+
+	/// Converts a version 1 project into version 2 in place.
+	/// Version 1 only saved the canvas being shown, so it becomes the only level of a [LevelStack].
+	fn upgrade_from_v1(value: &mut serde_json::Value) {
+		for tab in ["cave", "room"] {
+			if let Some(serde_json::Value::Object(tab_obj)) = value.get_mut(tab) {
+				let squares = tab_obj.remove("squares").unwrap_or(serde_json::Value::Null);
+				let levels = match squares {
+					serde_json::Value::Null => serde_json::Value::Null,
+					squares => serde_json::json!({ "levels": [squares], "current": 0 }),
+				};
+				tab_obj.insert(String::from("levels"), levels);
+			}//end if this tab was saved
+		}//end upgrading each tab
+		value["version"] = serde_json::Value::from(2);
+	}//end upgrade_from_v1(value)
+
+	/// Converts a version 2 project into version 3 in place.
+	/// Version 2 saved the color of each square, which is read back as a kind using the palette of the tab it was saved from.
+	fn upgrade_from_v2(value: &mut serde_json::Value) {
+		for (tab, palette) in [("cave", Palette::cave()), ("room", Palette::room())] {
+			let levels = match value.get_mut(tab).and_then(|tab_obj| tab_obj.get_mut("levels")).and_then(|stack| stack.get_mut("levels")) {
+				Some(serde_json::Value::Array(levels)) => levels,
+				_ => continue,
+			};
+			for level in levels.iter_mut() {
+				let squares = match level.get_mut("squares") {
+					Some(serde_json::Value::Array(squares)) => squares,
+					_ => continue,
+				};
+				for square in squares.iter_mut() {
+					let square_obj = match square {
+						serde_json::Value::Object(square_obj) => square_obj,
+						_ => continue,
+					};
+					let color = match square_obj.remove("color").map(serde_json::from_value::<(u8,u8,u8)>) {
+						Some(Ok(color)) => color,
+						_ => continue,
+					};
+					let kind = serde_json::to_value(palette.classify(color)).unwrap_or(serde_json::Value::Null);
+					square_obj.insert(String::from("kind"), kind);
+				}//end replacing color of each square
+			}//end looping over each level
+		}//end upgrading each tab
+		value["version"] = serde_json::Value::from(3);
+	}//end upgrade_from_v2(value)
+
+	/// Saves this project as JSON to path.
+	pub fn save(&self, path: &Path) -> Result<(), String> {
+		let text = self.to_json()?;
+		std::fs::write(path, text).map_err(|err| format!("Couldn't write project to {}: {}", path.display(), err))
+	}//end save(self, path)
+
+	/// Loads a project from a JSON file at path.
+	pub fn load(path: &Path) -> Result<Project, String> {
+		let text = std::fs::read_to_string(path).map_err(|err| format!("Couldn't read project from {}: {}", path.display(), err))?;
+		Project::from_json(&text)
+	}//end load(path)
+}//end impl for Project
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::export::{text::{from_text, TextCharMap}, MapKind};
+
+	/// A project with one small level on each tab.
+	fn sample_project() -> Project {
+		let char_map = TextCharMap::default();
+		let cave_squares = from_text("#.#\n.>.", MapKind::Cave, &char_map, 2).unwrap();
+		let room_squares = from_text("#+#\n.S ", MapKind::Room, &char_map, 2).unwrap();
+		let room_settings = RoomSettings { seed: Some(42), loop_targets: vec![1, 2], ..Default::default() };
+		Project::new(
+			CaveProject { levels: Some(LevelStack::new(cave_squares)), settings: CaveSettings::default() },
+			RoomProject { levels: Some(LevelStack::new(room_squares)), settings: room_settings },
+		)
+	}//end sample_project()
+
+	/// Rewrites project in the version 2 format, which saved the color of each square instead of its kind.
+	fn as_version_2(project: &Project) -> serde_json::Value {
+		let mut value = serde_json::to_value(project).unwrap();
+		for (tab, palette) in [("cave", Palette::cave()), ("room", Palette::room())] {
+			for level in value[tab]["levels"]["levels"].as_array_mut().unwrap() {
+				for square in level["squares"].as_array_mut().unwrap() {
+					let square_obj = square.as_object_mut().unwrap();
+					let kind = serde_json::from_value(square_obj.remove("kind").unwrap()).unwrap();
+					square_obj.insert(String::from("color"), serde_json::to_value(palette.color(kind)).unwrap());
+				}//end replacing kind of each square
+			}//end looping over each level
+		}//end downgrading each tab
+		value["version"] = serde_json::Value::from(2);
+		value
+	}//end as_version_2(project)
+
+	/// Rewrites project in the version 1 format, which saved only the squares being shown on each tab.
+	fn as_version_1(project: &Project) -> serde_json::Value {
+		let mut value = as_version_2(project);
+		for tab in ["cave", "room"] {
+			let tab_obj = value[tab].as_object_mut().unwrap();
+			let mut levels = tab_obj.remove("levels").unwrap();
+			tab_obj.insert(String::from("squares"), levels["levels"][0].take());
+		}//end downgrading each tab
+		value["version"] = serde_json::Value::from(1);
+		value
+	}//end as_version_1(project)
+
+	#[test]
+	fn version_2_projects_read_kinds_from_colors() {
+		let project = sample_project();
+		assert_eq!(Project::from_json(&as_version_2(&project).to_string()).unwrap(), project);
+	}//end version_2_projects_read_kinds_from_colors()
+
+	#[test]
+	fn version_1_projects_upgrade_to_current() {
+		let project = sample_project();
+		let upgraded = Project::from_json(&as_version_1(&project).to_string()).unwrap();
+		assert_eq!(upgraded.version, PROJECT_FORMAT_VERSION);
+		assert_eq!(upgraded, project);
+	}//end version_1_projects_upgrade_to_current()
+
+	#[test]
+	fn project_round_trips_through_json() {
+		let project = sample_project();
+		assert_eq!(Project::from_json(&project.to_json().unwrap()).unwrap(), project);
+	}//end project_round_trips_through_json()
+
+	#[test]
+	fn project_rejects_newer_or_unversioned_files() {
+		let mut value = serde_json::to_value(sample_project()).unwrap();
+		value["version"] = serde_json::Value::from(PROJECT_FORMAT_VERSION + 1);
+		assert!(Project::from_json(&value.to_string()).is_err());
+		value.as_object_mut().unwrap().remove("version");
+		assert!(Project::from_json(&value.to_string()).is_err());
+		assert!(Project::from_json("not json").is_err());
+	}//end project_rejects_newer_or_unversioned_files()
+
+	#[test]
+	fn project_fills_in_missing_settings() {
+		let project = Project::from_json(&format!("{{\"version\": {}}}", PROJECT_FORMAT_VERSION)).unwrap();
+		assert_eq!(project.cave, CaveProject::default());
+		assert_eq!(project.room, RoomProject::default());
+	}//end project_fills_in_missing_settings()
+}//end mod tests
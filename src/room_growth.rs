@@ -1,26 +1,116 @@
-use rand::{prelude::SliceRandom, rngs::ThreadRng, Rng};
+use std::collections::BTreeMap;
 
-use crate::squares::SquareGrid;
+use rand::{prelude::SliceRandom, rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{squares::{CellKind, SquareGrid, SquareRegion}, zones::{ZoneMap, ZoneSettings, ZoneType}};
 
 
 /// Struct for handling processing for
 /// Constrained Room Growth
 pub struct CRG {
 	squares: Option<SquareGrid>,
-	rng: ThreadRng,
+	/// seed used to create rng, kept so results can be reproduced
+	seed: u64,
+	rng: StdRng,
+	/// zones that rooms are kept within, if any
+	zones: Option<ZoneMap>,
+	/// limits on the size and shape of rooms grown from starts
+	room_limits: RoomLimits,
 }//end struct CRG
 
+/// How room starts are spread out by [CRG::add_spaced_room_starts()].
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub enum StartPlacement {
+	/// Starts go on any Empty squares, so they might end up right next to each other.
+	Random,
+	/// Empty squares are tried in a random order, and each one becomes a start unless it is
+	/// closer than min_distance squares to a start that was already placed.
+	PoissonDisk { min_distance: f64 },
+	/// Squares are split into cells spacing squares across, and each cell gets a start at a random
+	/// Empty square away from the edges of the cell, so starts are at least about half of spacing apart.
+	JitteredGrid { spacing: usize },
+}//end enum StartPlacement
+
+/// Limits on the size and shape of rooms grown by [CRG::grow_rooms_from_starts()] and [CRG::grow_rooms_l_growth()].
+/// Sizes are of the floor of a room, not counting its walls.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomLimits {
+	/// Rooms narrower than this once growth stops are merged into a neighbor or removed.
+	pub min_width: usize,
+	/// Rooms shorter than this once growth stops are merged into a neighbor or removed.
+	pub min_height: usize,
+	pub max_width: Option<usize>,
+	pub max_height: Option<usize>,
+	/// Largest number of floor squares in a room.
+	pub max_area: Option<usize>,
+	/// Largest ratio of the longer side of a room to its shorter side.
+	pub max_aspect_ratio: Option<f64>,
+}//end struct RoomLimits
+
+impl Default for RoomLimits {
+	fn default() -> Self {
+		Self {
+			min_width: 1,
+			min_height: 1,
+			max_width: None,
+			max_height: None,
+			max_area: None,
+			max_aspect_ratio: None,
+		}//end struct construction
+	}//end default()
+}//end impl Default for RoomLimits
+
+impl RoomLimits {
+	/// Whether a room with floor w squares across and h squares tall is within the maximums.
+	pub fn allows(&self, w: usize, h: usize) -> bool {
+		self.allows_floor(w, h, w * h)
+	}//end allows(self, w, h)
+
+	/// Whether a room with area floor squares, fitting in a box w squares across and h squares tall, is within the maximums.  
+	/// Used for rooms which aren't rectangles, such as after L growth.
+	pub fn allows_floor(&self, w: usize, h: usize, area: usize) -> bool {
+		let aspect_ratio = w.max(h) as f64 / w.min(h).max(1) as f64;
+		self.max_width.is_none_or(|max| w <= max)
+			&& self.max_height.is_none_or(|max| h <= max)
+			&& self.max_area.is_none_or(|max| area <= max)
+			&& self.max_aspect_ratio.is_none_or(|max| aspect_ratio <= max)
+	}//end allows_floor(self, w, h, area)
+
+	/// Whether a room with floor w squares across and h squares tall is big enough to keep.
+	pub fn is_undersized(&self, w: usize, h: usize) -> bool {
+		w < self.min_width || h < self.min_height
+	}//end is_undersized(self, w, h)
+}//end impl for RoomLimits
+
 impl Default for CRG {
+	/// Uses a randomly chosen seed, which can be retrieved with [CRG::seed()].
 	fn default() -> Self {
+		let seed = rand::thread_rng().gen();
 		Self {
 			squares: None,
-			rng: rand::thread_rng(),
+			seed,
+			rng: StdRng::seed_from_u64(seed),
+			zones: None,
+			room_limits: RoomLimits::default(),
 		}//end struct construction
 	}//end default()
 }//end impl Default for CRG
 
 impl CRG {
 	pub fn squares(&self) -> &Option<SquareGrid> {&self.squares}
+	/// The seed used for all random choices made by this object.
+	pub fn seed(&self) -> &u64 {&self.seed}
+
+	/// Resets the random number generator to start from seed.  
+	/// Running the same steps on the same squares with the same seed gives the same results.
+	pub fn set_seed(&mut self, seed: u64) {self.seed = seed; self.rng = StdRng::seed_from_u64(seed);}
+	/// Convenience function for setting seed.
+	pub fn with_seed(mut self, seed: u64) -> CRG {self.set_seed(seed); self}
+	/// Convenience function for setting seed from a caller-supplied random number generator.  
+	/// The seed drawn from rng can still be retrieved with [CRG::seed()].
+	pub fn with_rng<R: Rng>(self, rng: &mut R) -> CRG {self.with_seed(rng.gen())}
 
 	/// Sets this object to use a particular collection of squares.
 	pub fn set_squares(&mut self, squares: SquareGrid) {self.squares = Some(squares)}
@@ -31,6 +121,85 @@ impl CRG {
 	/// Returns SquareGrid object with ownership, setting self.squares to None.
 	pub fn pop_squares(&mut self) -> Option<SquareGrid> {let squares = self.squares.clone(); self.squares = None; squares}
 
+	/// The zones rooms are kept within, if any have been grown or set.
+	pub fn zones(&self) -> &Option<ZoneMap> {&self.zones}
+	/// Sets the zones rooms are kept within. They should have been grown on the same size of squares.
+	pub fn set_zones(&mut self, zones: Option<ZoneMap>) {self.zones = zones}
+	/// Convenience function for setting zones.
+	pub fn with_zones(mut self, zones: ZoneMap) -> CRG {self.zones = Some(zones); self}
+
+	/// The limits on the size and shape of rooms grown from starts.
+	pub fn room_limits(&self) -> &RoomLimits {&self.room_limits}
+	/// Sets the limits on the size and shape of rooms grown from starts.
+	pub fn set_room_limits(&mut self, room_limits: RoomLimits) {self.room_limits = room_limits}
+	/// Convenience function for setting room limits.
+	pub fn with_room_limits(mut self, room_limits: RoomLimits) -> CRG {self.room_limits = room_limits; self}
+
+	/// Grows one zone for each entry of zone_types across squares, as described in [ZoneMap::grow()].  
+	/// Once zones are set, the floors of rooms grown by [grow_rooms_from_starts()] and [grow_rooms_l_growth()] stay inside their zone.
+	/// 
+	/// Returns Err if squares is None or the zones can't be grown.
+	pub fn grow_zones(&mut self, zone_types: &[ZoneType]) -> Result<(),String> {
+		match &self.squares {
+			Some(squares) => {
+				self.zones = Some(ZoneMap::grow(squares, zone_types, &mut self.rng)?);
+				Ok(())
+			}, None => Err(String::from("No Squares Set")),
+		}//end matching whether we have squares
+	}//end grow_zones(self, zone_types)
+
+	/// Randomly adds room starts to the Empty squares of each zone.  
+	/// Starts are kept off the edges of zones, so their rooms have space to grow.
+	/// If rooms_per_zone is None, each zone gets a random number of starts based on its size.
+	/// 
+	/// Returns Err without making changes if squares or zones are None, or some zone
+	/// doesn't have enough Empty squares for its starts.
+	pub fn add_zoned_room_starts(&mut self, rooms_per_zone: Option<usize>) -> Result<(),String> {
+		let zones = match &self.zones {
+			Some(zones) => zones,
+			None => return Err(String::from("Zones need to be grown before room starts can be placed in them.")),
+		};
+		match &mut self.squares {
+			Some(squares) => {
+				let mut squares_clone = squares.clone();
+				for (zone_index, zone_squares) in zones.zones().iter().enumerate() {
+					let empty_list: Vec<&(usize,usize)> = zone_squares.iter()
+						.filter(|(row, col)| zones.is_interior(*row, *col))
+						.filter(|(row, col)| squares_clone.get(row, col).map(|square| CRGC::from_kind(*square.kind())) == Some(CRGC::Empty))
+						.collect();
+					let room_num = match rooms_per_zone {
+						Some(rooms) => rooms,
+						None => {
+							let zone_span = (zone_squares.len() as f64).sqrt();
+							let r_min = ((zone_span / 4.0) as usize).max(1);
+							let r_max = ((zone_span / 2.0) as usize).max(r_min);
+							self.rng.gen_range(r_min..=r_max).min(empty_list.len())
+						}};
+					if empty_list.len() < room_num { return Err(format!("Zone {} needs {} room starts, but only has {} Empty squares away from its edges to put them on.", zone_index + 1, room_num, empty_list.len())); }
+
+					for (row, col) in empty_list.choose_multiple(&mut self.rng, room_num) {
+						if let Some(square) = squares_clone.get_mut(row, col) { square.set_kind(CRGC::RoomStart.kind()); }
+					}//end looping over row and column indices to place a room start
+				}//end placing starts in each zone
+
+				*squares = squares_clone;
+				Ok(())
+			}, None => Err(String::from("No Squares Set")),
+		}//end matching whether we have squares
+	}//end add_zoned_room_starts(self, rooms_per_zone)
+
+	/// Runs every step of zoned room generation: zones are grown, room starts are placed in each zone,
+	/// rooms are grown within their zones with rectangular and then L growth, and then rooms are connected with [connect_zones()].
+	/// 
+	/// Returns Err if any step fails, in which case squares might be partly generated.
+	pub fn generate_zoned_rooms(&mut self, settings: &ZoneSettings) -> Result<(),String> {
+		self.grow_zones(&settings.zone_types)?;
+		self.add_zoned_room_starts(settings.rooms_per_zone)?;
+		self.grow_rooms_from_starts()?;
+		self.grow_rooms_l_growth()?;
+		self.connect_zones(settings)
+	}//end generate_zoned_rooms(self, settings)
+
 	/// Randomly adds room starts to squares.  
 	/// Changes will be made to self.squares to reflect these changes.
 	/// 
@@ -40,13 +209,25 @@ impl CRG {
 	/// Optionally, you can specify the number of starts to be produced.
 	/// If rooms is None, then a random number of room starts will be generated.
 	pub fn add_random_room_starts(&mut self, rooms: Option<usize>) -> Result<(),String> {
+		self.add_spaced_room_starts(rooms, StartPlacement::Random)
+	}//end add_random_room_starts(self, rooms)
+
+	/// Adds room starts to the Empty squares of squares, spread out as described by placement.  
+	/// Changes will be made to self.squares to reflect these changes.
+	/// 
+	/// If rooms is None, then a random number of room starts will be generated, though
+	/// fewer might fit when placement keeps starts apart.
+	/// 
+	/// If squares is None, or rooms starts can't all be placed with the spacing asked for,
+	/// then no changes will be made, and this function will return Err.
+	pub fn add_spaced_room_starts(&mut self, rooms: Option<usize>, placement: StartPlacement) -> Result<(),String> {
 		match &mut self.squares {
 			Some(squares) => {
 				let room_num = match rooms {
 					Some(r) => r,
 					None => {
-						let row_col_avg = squares.rows() + squares.cols() / 2;
-						let r_min = 0.max(row_col_avg / 8);
+						let row_col_avg = (squares.rows() + squares.cols()) / 2;
+						let r_min = row_col_avg / 8;
 						let r_max = row_col_avg / 2;
 						self.rng.gen_range(r_min..=r_max)
 					}};
@@ -58,11 +239,8 @@ impl CRG {
 						for row in 0..*squares.rows() {
 							match squares.get(&row,&col) {
 								Some(sqr) => {
-									match CRGC::classify(*sqr.color()) {
-										CRGC::Empty => empt_sqr_vec.push((row,col)),
-										_ => (),
-									}//end matching color class
-								}, None => println!("Couldn't access square at row:{} and col:{} while placing random starts", row, col)
+									if CRGC::from_kind(*sqr.kind()) == CRGC::Empty { empt_sqr_vec.push((row,col)); }
+								}, None => return Err(format!("Couldn't access square at row:{} and col:{} while placing random starts", row, col)),
 							}//end matching whether we got the square
 						}//end looping over row indices
 					}//end looping over col indices
@@ -72,31 +250,151 @@ impl CRG {
 				// check that we have enough empty spaces for all room starts
 				if empty_list.len() < room_num { return Err(format!("Room starts cannot be placed because we want to add {} starts, but starts can only be places on Empty squares, and there are only {} Empty squares!", room_num, empty_list.len())) }
 
-				// randomly get [room_num] indices from empty_list
-				for (row,col) in empty_list.choose_multiple(&mut self.rng, room_num) {
-					match squares.get_mut(row, col) {
-						Some(square) => square.set_color(CRGC::RoomStart.color()),
-						None => println!("Couldn't get square at row:{} and col:{} for some reason.", row, col),
+				let chosen_list: Vec<(usize,usize)> = match placement {
+					// randomly get [room_num] indices from empty_list
+					StartPlacement::Random => empty_list.choose_multiple(&mut self.rng, room_num).cloned().collect(),
+					StartPlacement::PoissonDisk { min_distance } => CRG::poisson_disk_starts(&empty_list, min_distance, room_num, &mut self.rng),
+					StartPlacement::JitteredGrid { spacing } => CRG::jittered_grid_starts(squares, spacing, room_num, &mut self.rng),
+				};
+				if rooms.is_some() && chosen_list.len() < room_num {
+					return Err(format!("Only {} of {} room starts could be placed with the spacing asked for. Try fewer rooms or less spacing.", chosen_list.len(), room_num));
+				}//end if we couldn't fit all the starts asked for
+
+				let mut squares_clone = squares.clone();
+				for (row,col) in chosen_list.iter() {
+					match squares_clone.get_mut(row, col) {
+						Some(square) => square.set_kind(CRGC::RoomStart.kind()),
+						None => return Err(format!("Couldn't get square at row:{} and col:{} to place a room start.", row, col)),
 					}//end matching whether we can access the square to set color
 				}//end looping over row and column indices to place a room start
 				
+				*squares = squares_clone;
 				Ok(())
-			}, None => Err(format!("Could not add random starts to squares because squares is None."))
+			}, None => Err(String::from("Could not add random starts to squares because squares is None."))
 		}//end matching whether we can access squares
-	}//end add_random_room_starts(self, rooms)
+	}//end add_spaced_room_starts(self, rooms, placement)
+
+	/// Picks up to limit squares from empty_list in a random order, skipping any square
+	/// closer than min_distance squares to one already picked.
+	fn poisson_disk_starts<R: Rng>(empty_list: &[(usize,usize)], min_distance: f64, limit: usize, rng: &mut R) -> Vec<(usize,usize)> {
+		let mut order = empty_list.to_vec();
+		order.shuffle(rng);
+		// picked squares are bucketed by cells min_distance across, so only nearby buckets need checking
+		let bucket_size = min_distance.max(1.0);
+		let mut buckets: BTreeMap<(usize,usize), Vec<(usize,usize)>> = BTreeMap::new();
+		let mut starts = Vec::new();
+		for (row, col) in order {
+			if starts.len() >= limit { break; }
+			let bucket = ((row as f64 / bucket_size) as usize, (col as f64 / bucket_size) as usize);
+			let too_close = (bucket.0.saturating_sub(1)..=bucket.0 + 1).any(|bucket_row| {
+				(bucket.1.saturating_sub(1)..=bucket.1 + 1).any(|bucket_col| match buckets.get(&(bucket_row, bucket_col)) {
+					Some(picked) => picked.iter().any(|other| CRG::square_distance(*other, (row, col)) < min_distance),
+					None => false,
+				})
+			});
+			if !too_close {
+				buckets.entry(bucket).or_default().push((row, col));
+				starts.push((row, col));
+			}//end if this square is far enough from the others
+		}//end looping over empty squares in a random order
+		starts
+	}//end poisson_disk_starts(empty_list, min_distance, limit, rng)
+
+	/// Picks a random Empty square away from the edges of each cell, spacing squares across, of squares.  
+	/// If there are more cells than limit, then limit of them are picked at random.
+	fn jittered_grid_starts<R: Rng>(squares: &SquareGrid, spacing: usize, limit: usize, rng: &mut R) -> Vec<(usize,usize)> {
+		let spacing = spacing.max(1);
+		let margin = spacing / 4;
+		let rows = *squares.rows();
+		let cols = *squares.cols();
+		let mut cell_starts = Vec::new();
+		for cell_row in (0..rows).step_by(spacing) {
+			for cell_col in (0..cols).step_by(spacing) {
+				let options: Vec<(usize,usize)> = (cell_row + margin..(cell_row + spacing - margin).min(rows))
+					.flat_map(|row| (cell_col + margin..(cell_col + spacing - margin).min(cols)).map(move |col| (row, col)))
+					.filter(|(row, col)| squares.get(row, col).map(|square| CRGC::from_kind(*square.kind())) == Some(CRGC::Empty))
+					.collect();
+				if let Some(start) = options.choose(rng) { cell_starts.push(*start); }
+			}//end looping over cells in this row of cells
+		}//end looping over rows of cells
+		match cell_starts.len() > limit {
+			true => cell_starts.choose_multiple(rng, limit).cloned().collect(),
+			false => cell_starts,
+		}//end matching whether there are too many cells
+	}//end jittered_grid_starts(squares, spacing, limit, rng)
+
+	/// Straight line distance between two (row, col) coords, in squares.
+	fn square_distance(a: (usize,usize), b: (usize,usize)) -> f64 {
+		let row_diff = a.0 as f64 - b.0 as f64;
+		let col_diff = a.1 as f64 - b.1 as f64;
+		(row_diff * row_diff + col_diff * col_diff).sqrt()
+	}//end square_distance(a, b)
+
+	/// Merges room starts which are closer together than threshold squares, so that clumps of starts
+	/// grow into one room instead of several sliver rooms.  
+	/// Starts are grouped with every start closer than threshold to another start in the group, and
+	/// each group is replaced by the start closest to the middle of the group. The other starts become Empty.
+	/// 
+	/// If squares is None, then no changes will be made and this function will return Err.
+	pub fn merge_close_room_starts(&mut self, threshold: f64) -> Result<(),String> {
+		match &mut self.squares {
+			Some(squares) => {
+				let starts: Vec<(usize,usize)> = (0..*squares.rows())
+					.flat_map(|row| (0..*squares.cols()).map(move |col| (row, col)))
+					.filter(|(row, col)| squares.get(row, col).map(|square| CRGC::from_kind(*square.kind())) == Some(CRGC::RoomStart))
+					.collect();
+				let mut groups: Vec<usize> = (0..starts.len()).collect();
+				for start_a in 0..starts.len() {
+					for start_b in start_a + 1..starts.len() {
+						if CRG::square_distance(starts[start_a], starts[start_b]) < threshold { CRG::join_groups(&mut groups, start_a, start_b); }
+					}//end looping over later starts
+				}//end looping over each start
+
+				let mut members_of_group: BTreeMap<usize, Vec<(usize,usize)>> = BTreeMap::new();
+				for (start_index, start) in starts.iter().enumerate() {
+					let mut group = start_index;
+					while groups[group] != group { group = groups[group]; }
+					members_of_group.entry(group).or_default().push(*start);
+				}//end sorting starts into groups
+
+				for members in members_of_group.values().filter(|members| members.len() > 1) {
+					let middle_row = members.iter().map(|(row, _)| *row as f64).sum::<f64>() / members.len() as f64;
+					let middle_col = members.iter().map(|(_, col)| *col as f64).sum::<f64>() / members.len() as f64;
+					let distance_to_middle = |(row, col): &(usize,usize)| {
+						let row_diff = *row as f64 - middle_row;
+						let col_diff = *col as f64 - middle_col;
+						row_diff * row_diff + col_diff * col_diff
+					};
+					let kept = members.iter().min_by(|a, b| distance_to_middle(a).total_cmp(&distance_to_middle(b))).cloned();
+					for (row, col) in members.iter().filter(|member| Some(**member) != kept) {
+						if let Some(square) = squares.get_mut(row, col) { square.set_kind(CRGC::Empty.kind()); }
+					}//end clearing the starts that weren't kept
+				}//end looping over groups of more than one start
+
+				Ok(())
+			}, None => Err(String::from("No Squares Set"))
+		}//end matching whether we have squares
+	}//end merge_close_room_starts(self, threshold)
 
 	/// Grows rooms from room starts in squares.  
 	/// Changes will be made to self.squares to reflect these changes.  
 	/// 
 	/// This function only handles rectangular constrained growth.
-	/// 
+	/// If zones are set, the floor of rooms whose start is in a zone won't grow past the edge of that zone,
+	/// though their walls might.
+	///
+	/// Rooms stop growing in any direction that would take them past the maximums in [CRG::room_limits()],
+	/// and keep growing in the directions that don't. Once growth stops, rooms smaller than the minimums
+	/// are merged into a neighbor they share a wall with, or removed if they don't have one.
+	///
 	/// If squares is None, or something else goes wrong, then no changes will
 	/// be made. If squares is not changed, this function will return Err.
 	pub fn grow_rooms_from_starts(&mut self) -> Result<(),String> {
 		/*
 		TODO: The following alterations would likely improve this algorithm:
 		- check each square against squares_clone to ensure claims are not made against floors or walls
-		- check beginning room starts. If too many are close together, merge them
+		NOTE: Room starts which are close together aren't merged here. Callers run merge_close_room_starts() right
+		after placing starts, so that starts drawn by hand on the canvas are still grown exactly as drawn.
 		 */
 		
 		match &self.squares {
@@ -108,7 +406,7 @@ impl CRG {
 						for col in 0..*squares.cols() {
 							match squares.get(&row, &col) {
 								Some(square) => {
-									if CRGC::classify(*square.color()) == CRGC::RoomStart {
+									if CRGC::from_kind(*square.kind()) == CRGC::RoomStart {
 										room_starts.push(RoomFromStart::from_square_coord(col, row, room_starts.len()));
 									}//end if we found a room start
 								}, None => println!("Couldn't get square at row:{}, col:{}", row, col)
@@ -133,17 +431,19 @@ impl CRG {
 					// create parallel list to room_starts,
 					// room -> (Iter<SquareClaims>)
 					for room in room_starts.iter_mut() {
-						let room_index = room.index.clone();
+						let room_index = room.index;
+						let room_zone = self.zones.as_ref().and_then(|zones| zones.zone_at(room.init_y, room.init_x));
+						// claimed squares become floor, so they need to be in the zone, though walls may go past its edge
+						let outside_zone = |claim: &SquareClaim| match (&self.zones, room_zone) {
+							(Some(zones), Some(zone)) => zones.zone_at(claim.y, claim.x) != Some(zone),
+							_ => false,
+						};
 						let claims = all_claims.iter().filter(|elem| elem.claimants_indices.contains(&room_index)).cloned();
-						let allow_growth = claims.clone().filter(|elem| elem.claimants_indices.len() > 1).count() == 0;
+						let allow_growth = claims.clone().filter(|elem| elem.claimants_indices.len() > 1 || outside_zone(elem)).count() == 0;
 						room.allowed_growth = allow_growth;
 						if room.allowed_growth {
-							let prev = (room.x, room.y, room.w, room.h);
-							room.consume_claims(claims);
-							let cur = (room.x, room.y, room.w, room.h);
-							if prev == cur {
-								room.allowed_growth = false;
-							}//end if room is trying to grow but can't
+							let claims: Vec<SquareClaim> = claims.collect();
+							room.allowed_growth = room.grow_within_limits(&claims, &self.room_limits, &mut self.rng);
 						}//end if room is allowed to grow
 					}//end looping over rooms
 
@@ -152,15 +452,15 @@ impl CRG {
 
 				// update squares, draw room floors and walls.
 				let mut squares_clone = squares.clone();
-				for room in room_starts {
+				for room in room_starts.iter() {
 					// Paint squares within rooms as Floor
 					{for col in room.x..(room.x + room.w) {
 						for row in room.y..(room.y + room.h) {
 							match squares_clone.get_mut(&row, &col) {
 								Some(square) => {
-									match CRGC::classify(*square.color()) {
+									match CRGC::from_kind(*square.kind()) {
 										CRGC::Empty | CRGC::Other(_) | CRGC::RoomStart => {
-											square.set_color(CRGC::Floor.color());
+											square.set_kind(CRGC::Floor.kind());
 										}, _ => ()
 									}//end matching color classification of square
 								}, None => println!("Couldn't access square at row:{}, col:{}, painting floor", row, col)
@@ -174,9 +474,9 @@ impl CRG {
 						for (row,col) in border_coords.iter().map(|elem| (elem.y, elem.x)) {
 							match squares_clone.get_mut(&row, &col) {
 								Some(square) => {
-									match CRGC::classify(*square.color()) {
+									match CRGC::from_kind(*square.kind()) {
 										CRGC::Empty | CRGC::Other(_) => {
-											square.set_color(CRGC::Wall.color());
+											square.set_kind(CRGC::Wall.kind());
 										}, _ => ()
 									}//end making sure this square is empty before painting wall
 								}, None => println!("Couldn't access square at row:{}, col:{}, painting wall", row, col)
@@ -184,22 +484,91 @@ impl CRG {
 						}//end looping over coords which border room
 					}//end painting border squares
 				}//end looping through rooms we've grown from room starts
+				CRG::resolve_undersized_rooms(&mut squares_clone, &room_starts, &self.room_limits, self.zones.as_ref());
 
 				// update squares with the changes we've made
 				self.squares = Some(squares_clone);
 
 				Ok(())
-			}, None => Err(format!("No Squares Set"))
+			}, None => Err(String::from("No Squares Set"))
 		}//end matching whether we have squares
 	}//end grow_rooms_from_starts(self)
 
+	/// Merges each room in rooms that is below the minimums of limits into the neighbor it shares the
+	/// longest wall with, by turning that wall into floor. Only neighbors that meet the minimums are used,
+	/// as long as the merged room, counting rooms already merged into that neighbor, stays within the maximums
+	/// of limits, and if zones are given only neighbors in the same zone. Rooms that such a neighbor grew over or
+	/// against are already part of it, so they're left alone.
+	/// Other undersized rooms are cleared back to Empty, along with any walls only they used,
+	/// unless they have stairs in them.
+	fn resolve_undersized_rooms(squares: &mut SquareGrid, rooms: &[RoomFromStart], limits: &RoomLimits, zones: Option<&ZoneMap>) {
+		let zone_of = |room: &RoomFromStart| zones.and_then(|zones| zones.zone_at(room.init_y, room.init_x));
+		let mut removed = vec![false; rooms.len()];
+		// parallel to rooms, the bounding box as (left, top, right, bottom) just past the floor, and the floor area, of each room as others merge into it
+		let mut shapes: Vec<((usize,usize,usize,usize), usize)> = rooms.iter().map(|room| ((room.x, room.y, room.x + room.w, room.y + room.h), room.w * room.h)).collect();
+		for (room_idx, room) in rooms.iter().enumerate() {
+			if !limits.is_undersized(room.w, room.h) { continue; }
+			let full_sized = |other: &RoomFromStart| !limits.is_undersized(other.w, other.h);
+			// rooms can grow over or right up against starts that stopped early, which leaves those already part of a bigger room
+			if rooms.iter().any(|other| full_sized(other) && room.touches(other)) { continue; }
+			let merged_shape = |other_idx: usize, wall: &Vec<(usize,usize)>| {
+				let ((left, top, right, bottom), area) = shapes[other_idx];
+				((left.min(room.x), top.min(room.y), right.max(room.x + room.w), bottom.max(room.y + room.h)), area + room.w * room.h + wall.len())
+			};
+			let best_wall = rooms.iter().enumerate()
+				.filter(|(_, other)| full_sized(other) && zone_of(other) == zone_of(room))
+				.map(|(other_idx, other)| (other_idx, room.shared_wall(other)))
+				.filter(|(other_idx, wall)| {
+					let ((left, top, right, bottom), area) = merged_shape(*other_idx, wall);
+					limits.allows_floor(right - left, bottom - top, area)
+				})
+				.max_by_key(|(_, wall)| wall.len())
+				.filter(|(_, wall)| !wall.is_empty());
+			match best_wall {
+				Some((other_idx, wall)) => {
+					shapes[other_idx] = merged_shape(other_idx, &wall);
+					for (row, col) in wall {
+						if let Some(square) = squares.get_mut(&row, &col) {
+							if CRGC::from_kind(*square.kind()) == CRGC::Wall { square.set_kind(CRGC::Floor.kind()); }
+						}//end if we can access square in wall
+					}//end opening up the shared wall
+				}, None => {
+					let floor_coords: Vec<(usize,usize)> = (room.y..room.y + room.h).flat_map(|row| (room.x..room.x + room.w).map(move |col| (row, col))).collect();
+					let has_stairs = floor_coords.iter().any(|(row, col)| squares.get(row, col).is_some_and(|square| CRGC::from_kind(*square.kind()) == CRGC::Stairs));
+					if has_stairs { continue; }
+					// floor might also be covered by another room which hasn't been removed
+					let in_other_room = |row: usize, col: usize| rooms.iter().enumerate()
+						.any(|(other_idx, other)| other_idx != room_idx && !removed[other_idx] && other.contains(row, col));
+					for (row, col) in floor_coords.iter().filter(|(row, col)| !in_other_room(*row, *col)) {
+						if let Some(square) = squares.get_mut(row, col) {
+							if matches!(CRGC::from_kind(*square.kind()), CRGC::Floor | CRGC::RoomStart) { square.set_kind(CRGC::Empty.kind()); }
+						}//end if we can access square in floor
+					}//end clearing floor
+					let border_coords = RoomFromStart::corner_coords_to_square(&room.get_corner_coords(squares.cols() - 1, squares.rows() - 1), room.index);
+					for (row, col) in border_coords.iter().map(|claim| (claim.y, claim.x)) {
+						let is_wall = squares.get(&row, &col).is_some_and(|square| CRGC::from_kind(*square.kind()) == CRGC::Wall);
+						// walls next to anything else in the structure still belong to another room
+						let borders_other_room = (row.saturating_sub(1)..=(row + 1).min(squares.rows() - 1)).any(|row_a| {
+							(col.saturating_sub(1)..=(col + 1).min(squares.cols() - 1)).any(|col_a| squares.get(&row_a, &col_a)
+								.is_some_and(|square| !matches!(CRGC::from_kind(*square.kind()), CRGC::Empty | CRGC::Wall | CRGC::Other(_))))
+						});
+						if is_wall && !borders_other_room {
+							if let Some(square) = squares.get_mut(&row, &col) { square.set_kind(CRGC::Empty.kind()); }
+						}//end if wall is only used by this room
+					}//end clearing walls
+					removed[room_idx] = true;
+				},
+			}//end matching whether room has a neighbor to merge into
+		}//end looping over rooms
+	}//end resolve_undersized_rooms(squares, rooms, limits, zones)
+
 	pub fn place_random_doors(&mut self, base_p: f64) -> Result<(), String> {
 		match &mut self.squares {
 			Some(squares) => {
 				for row in 0..*squares.rows() {
 					for col in 0..*squares.cols() {
 						if let Some(wall_square) = squares.get(&row, &col) {
-							if CRGC::classify(*wall_square.color()) == CRGC::Wall {
+							if CRGC::from_kind(*wall_square.kind()) == CRGC::Wall {
 								let adjacent_squares = {
 									let mut squr_vec = Vec::new();
 									for row_a in row.checked_sub(1).unwrap_or(row)..(row + 1).min(squares.rows() - 1) {
@@ -215,11 +584,11 @@ impl CRG {
 									squr_vec
 								};//end getting vec of adjacent squares
 	
-								let adjacent_floors = adjacent_squares.iter().filter(|elem| CRGC::classify(*elem.color()) == CRGC::Floor).count();
+								let adjacent_floors = adjacent_squares.iter().filter(|elem| CRGC::from_kind(*elem.kind()) == CRGC::Floor).count();
 								let make_door = self.rng.gen_bool(base_p * adjacent_floors as f64);
 								if make_door {
 									let wall_square = squares.get_mut(&row,&col).unwrap();
-									wall_square.set_color(CRGC::Door.color())
+									wall_square.set_kind(CRGC::Door.kind())
 								}//end if we are going to make a door
 							}//end if we found a wall
 						}//end if we can get the square here
@@ -227,7 +596,7 @@ impl CRG {
 				}//end looping over rows
 
 				Ok(())
-			}, None => Err(format!("No Squares Set"))
+			}, None => Err(String::from("No Squares Set"))
 		}//end matching whether we have squares
 	}//end place_random_doors
 
@@ -239,31 +608,803 @@ impl CRG {
 	/// 
 	/// If squares is None, or something else goes wrong, then no changes will
 	/// be made. If squares is not changed, this function will return Err.
+	/// 
+	/// Each round, every room looks for the longest stretch of its own
+	/// wall that has Empty squares directly behind it, and moves that stretch
+	/// of wall outward by one square. A stretch always stops short of at least
+	/// one corner of its side, so only part of a side moves, and rooms grow
+	/// legs which make them L-shaped. Stretches which would take a room
+	/// past the maximums in [CRG::room_limits()] are skipped, measuring the room
+	/// by the box around its floor and the number of floor squares in it.
+	/// Rounds continue until no room is able to grow.
 	pub fn grow_rooms_l_growth(&mut self) -> Result<(),String> {
-		Err(format!("Not implemented yet."))
+		match &self.squares {
+			Some(squares) => {
+				let mut squares_clone = squares.clone();
+				let rows = *squares_clone.rows();
+				let cols = *squares_clone.cols();
+				if RoomGraph::from_squares(&squares_clone).rooms().is_empty() {
+					return Err(String::from("No rooms were found to grow. Try running initial room growth first."));
+				}//end if there's nothing to grow
+
+				let mut grew_this_round = true;
+				while grew_this_round {
+					grew_this_round = false;
+					let graph = RoomGraph::from_squares(&squares_clone);
+					let mut room_order: Vec<usize> = (0..graph.rooms().len()).collect();
+					room_order.shuffle(&mut self.rng);
+
+					for room_index in room_order {
+						let growth_runs = CRG::find_l_growth_runs(&squares_clone, &graph, self.zones.as_ref(), &self.room_limits, room_index);
+						// a side can give two runs of the same length, one short of each corner, so pick between ties randomly
+						let longest = growth_runs.iter().map(|(run, _)| run.len()).max().unwrap_or(0);
+						let longest_runs: Vec<&(SquareRegion, (isize, isize))> = growth_runs.iter().filter(|(run, _)| run.len() == longest).collect();
+						let growth_run = longest_runs.choose(&mut self.rng).map(|growth_run| (*growth_run).clone());
+						if let Some((run, (row_step, col_step))) = growth_run {
+							// make sure other rooms haven't grown into this space already this round
+							let still_valid = run.iter().all(|(row, col)| CRG::can_l_grow_from(&squares_clone, &graph, self.zones.as_ref(), room_index, *row, *col, (row_step, col_step)));
+							if !still_valid { continue; }
+
+							for (row, col) in run {
+								let new_floor = ((row as isize + row_step) as usize, (col as isize + col_step) as usize);
+								let new_wall = ((row as isize + row_step * 2) as usize, (col as isize + col_step * 2) as usize);
+								CRG::paint_region(&mut squares_clone, &vec![new_floor], CRGC::Floor);
+								CRG::paint_region(&mut squares_clone, &vec![new_wall], CRGC::Wall);
+								// close off any empty space around the new floor
+								for row_a in new_floor.0.saturating_sub(1)..=(new_floor.0 + 1).min(rows - 1) {
+									for col_a in new_floor.1.saturating_sub(1)..=(new_floor.1 + 1).min(cols - 1) {
+										if let Some(square) = squares_clone.get_mut(&row_a, &col_a) {
+											if CRGC::from_kind(*square.kind()) == CRGC::Empty { square.set_kind(CRGC::Wall.kind()); }
+										}//end if we can get the square around the new floor
+									}//end looping over cols around new floor
+								}//end looping over rows around new floor
+							}//end growing each square in the run
+							grew_this_round = true;
+						}//end if this room has somewhere to grow
+					}//end looping over each room
+				}//end looping while rooms are still growing
+
+				self.squares = Some(squares_clone);
+				Ok(())
+			}, None => Err(String::from("No Squares Set"))
+		}//end matching whether we have squares
 	}//end grow_rooms_l_growth(self)
 
+	/// Finds the stretches of wall room_index could grow into with L growth.  
+	/// Returns each stretch as a list of (row, col) coords of floor squares in the room,
+	/// along with the (row, col) direction that the wall in front of them would move.
+	/// A stretch covering a whole side from corner to corner would only make the room longer, so it's
+	/// given as two stretches instead, each one square short of a different corner.
+	/// Stretches that would take the room past the maximums of limits are left out.
+	fn find_l_growth_runs(squares: &SquareGrid, graph: &RoomGraph, zones: Option<&ZoneMap>, limits: &RoomLimits, room_index: usize) -> Vec<(SquareRegion, (isize, isize))> {
+		let mut runs = Vec::new();
+		let room_squares = match graph.rooms().get(room_index) {
+			Some(room_squares) => room_squares,
+			None => return runs,
+		};
+		let (min_row, max_row) = (room_squares.iter().map(|(row, _)| *row).min().unwrap_or(0), room_squares.iter().map(|(row, _)| *row).max().unwrap_or(0));
+		let (min_col, max_col) = (room_squares.iter().map(|(_, col)| *col).min().unwrap_or(0), room_squares.iter().map(|(_, col)| *col).max().unwrap_or(0));
+		// every square in a run grows the same way, so the room only gets longer along the direction of growth
+		let fits_limits = |run: &SquareRegion, (row_step, col_step): (isize, isize)| {
+			let (mut low_row, mut high_row, mut low_col, mut high_col) = (min_row as isize, max_row as isize, min_col as isize, max_col as isize);
+			for (row, col) in run {
+				let (new_row, new_col) = (*row as isize + row_step, *col as isize + col_step);
+				(low_row, high_row) = (low_row.min(new_row), high_row.max(new_row));
+				(low_col, high_col) = (low_col.min(new_col), high_col.max(new_col));
+			}//end looping over new floor squares
+			limits.allows_floor((high_col - low_col + 1) as usize, (high_row - low_row + 1) as usize, room_squares.len() + run.len())
+		};
+		for (row_step, col_step) in [(-1,0), (1,0), (0,-1), (0,1)] {
+			// floor squares which could grow in this direction, sorted along the wall
+			let mut growable: Vec<(usize,usize)> = room_squares.iter()
+				.filter(|(row, col)| CRG::can_l_grow_from(squares, graph, zones, room_index, *row, *col, (row_step, col_step)))
+				.cloned().collect();
+			growable.sort_by_key(|(row, col)| if row_step != 0 { (*row, *col) } else { (*col, *row) });
+
+			// split growable squares into runs which are next to each other along the wall
+			let mut cur_run: SquareRegion = Vec::new();
+			for (row, col) in growable {
+				let continues_run = match cur_run.last() {
+					Some((last_row, last_col)) => {
+						if row_step != 0 { *last_row == row && *last_col + 1 == col }
+						else { *last_col == col && *last_row + 1 == row }
+					}, None => true,
+				};
+				if !continues_run { runs.push((cur_run.clone(), (row_step, col_step))); cur_run.clear(); }
+				cur_run.push((row, col));
+			}//end looping over growable squares
+			if !cur_run.is_empty() { runs.push((cur_run, (row_step, col_step))); }
+		}//end looping over each direction
+
+		let in_room = |row: isize, col: isize| row >= 0 && col >= 0 && graph.room_at(row as usize, col as usize) == Some(room_index);
+		let mut runs: Vec<(SquareRegion, (isize, isize))> = runs.into_iter().flat_map(|(run, (row_step, col_step))| {
+			// runs go along the wall, which is across the direction of growth
+			let (along_row, along_col) = if row_step != 0 { (0, 1) } else { (1, 0) };
+			let (first, last) = (run[0], run[run.len() - 1]);
+			let reaches_first_corner = !in_room(first.0 as isize - along_row, first.1 as isize - along_col);
+			let reaches_last_corner = !in_room(last.0 as isize + along_row, last.1 as isize + along_col);
+			if !(reaches_first_corner && reaches_last_corner) { return vec![(run, (row_step, col_step))]; }
+			if run.len() < 2 { return Vec::new(); }
+			vec![(run[1..].to_vec(), (row_step, col_step)), (run[..run.len() - 1].to_vec(), (row_step, col_step))]
+		}).collect();
+		runs.retain(|(run, step)| fits_limits(run, *step));
+		runs
+	}//end find_l_growth_runs(squares, graph, zones, limits, room_index)
+
+	/// Checks whether the wall in front of the floor square at row, col can be moved one
+	/// square further in the (row, col) direction of step.  
+	/// This requires the square in front to be a wall that doesn't touch any other room,
+	/// and the square behind that wall to be Empty.
+	/// If zones are given, the wall also needs to be in the same zone as the floor square.
+	fn can_l_grow_from(squares: &SquareGrid, graph: &RoomGraph, zones: Option<&ZoneMap>, room_index: usize, row: usize, col: usize, (row_step, col_step): (isize, isize)) -> bool {
+		let step = |row: usize, col: usize, times: isize| -> Option<(usize,usize)> {
+			let new_row = row as isize + row_step * times;
+			let new_col = col as isize + col_step * times;
+			if new_row < 0 || new_col < 0 || new_row as usize >= *squares.rows() || new_col as usize >= *squares.cols() { None }
+			else { Some((new_row as usize, new_col as usize)) }
+		};
+		let class_at = |row: usize, col: usize| squares.get(&row, &col).map(|square| CRGC::from_kind(*square.kind()));
+		match (step(row, col, 1), step(row, col, 2)) {
+			(Some(wall), Some(beyond)) => {
+				if class_at(wall.0, wall.1) != Some(CRGC::Wall) || class_at(beyond.0, beyond.1) != Some(CRGC::Empty) { return false; }
+				if let Some(zones) = zones {
+					if zones.zone_at(wall.0, wall.1) != zones.zone_at(row, col) { return false; }
+				}//end if growth needs to stay within a zone
+				// the wall becoming floor can't touch anything except walls, empty space, or this room
+				RoomGraph::orthogonal_neighbors(wall.0, wall.1, *squares.rows(), *squares.cols()).into_iter().all(|(row_a, col_a)| {
+					match class_at(row_a, col_a) {
+						Some(CRGC::Wall) | Some(CRGC::Empty) => true,
+						_ => graph.room_at(row_a, col_a) == Some(room_index),
+					}//end matching class of square next to wall
+				})
+			}, _ => false,
+		}//end matching whether the squares in front are in bounds
+	}//end can_l_grow_from(squares, graph, zones, room_index, row, col, step)
+
 	/// Calculates a connectivity number that represents
 	/// minimum connectivity. This number represents the
 	/// minimum number of rooms one must travel through to
 	/// traverse the map.
-	pub fn calculate_connectivity(&mut self) -> usize {
-		todo!();
+	/// 
+	/// Rooms are regions of floor bounded by walls, and two rooms
+	/// are connected if a door touches both of them. The number
+	/// returned is the fewest rooms, counting the rooms at both ends,
+	/// on any path between two different level connections (stairs).
+	/// The [RoomGraph] used to calculate it is returned as well.
+	/// 
+	/// This function returns Err if squares is None, if there are fewer
+	/// than two level connections, or if no two level connections can
+	/// reach each other.
+	pub fn calculate_connectivity(&self) -> Result<(usize, RoomGraph), String> {
+		match &self.squares {
+			Some(squares) => {
+				let graph = RoomGraph::from_squares(squares);
+				if graph.stairs().len() < 2 {
+					return Err(format!("Connectivity needs at least two level connections, but only {} were found.", graph.stairs().len()));
+				}//end if we don't have enough stairs to connect
+				match graph.stairs_connectivity() {
+					Some(connectivity) => Ok((connectivity, graph)),
+					None => Err(format!("None of the {} level connections can reach each other through doors.", graph.stairs().len())),
+				}//end matching whether any stairs could reach each other
+			}, None => Err(String::from("No Squares Set"))
+		}//end matching whether we have squares
 	}//end calculate_connectivity(self)
 
 	/// Changes the number of doors throughout the map
 	/// in order to make sure connectivity is at least equal
 	/// to min_connectivity.
 	/// 
+	/// First, doors are added on walls shared by rooms which can't
+	/// reach each other, until every room can be reached. Then, doors
+	/// which aren't needed to keep every room reachable are removed
+	/// until the shortest path between level connections goes through
+	/// at least min_connectivity rooms.
+	/// 
 	/// If this function is successful, changes will be
 	/// reflected in squares. If something goes wrong, then
 	/// no changes will be made, and this function will
 	/// return Err.
 	pub fn enforce_connectivity(&mut self, min_connectivity: usize) -> Result<(),String> {
-		Err(format!("Not implemented yet."))
+		match &self.squares {
+			Some(squares) => {
+				let mut squares_clone = squares.clone();
+				let mut graph = RoomGraph::from_squares(&squares_clone);
+				if graph.stairs().len() < 2 {
+					return Err(format!("Connectivity needs at least two level connections, but only {} were found.", graph.stairs().len()));
+				}//end if we don't have enough stairs to measure connectivity
+
+				// add doors until every room can reach every other room
+				while !graph.is_fully_connected() {
+					let components = graph.components();
+					let candidates: Vec<SquareRegion> = CRG::find_door_candidates(&squares_clone, &graph).into_iter()
+						.filter(|(_, room_a, room_b)| components[*room_a] != components[*room_b])
+						.map(|(wall_squares, _, _)| wall_squares)
+						.collect();
+					if candidates.is_empty() {
+						return Err(format!("Couldn't connect all {} rooms, as some rooms in separate groups don't share a wall thin enough to place a door on.", graph.rooms().len()));
+					}//end if there's no way to connect more rooms
+
+					// prefer doors that don't make the path between stairs too short
+					let keeps_connectivity: Vec<&SquareRegion> = candidates.iter().filter(|wall_squares| {
+						let mut test_squares = squares_clone.clone();
+						CRG::paint_region(&mut test_squares, wall_squares, CRGC::Door);
+						RoomGraph::from_squares(&test_squares).stairs_connectivity().is_none_or(|conn| conn >= min_connectivity)
+					}).collect();
+					let chosen = match keeps_connectivity.choose(&mut self.rng) {
+						Some(wall_squares) => (*wall_squares).clone(),
+						None => candidates.choose(&mut self.rng).cloned().unwrap_or_default(),
+					};
+					CRG::paint_region(&mut squares_clone, &chosen, CRGC::Door);
+					graph = RoomGraph::from_squares(&squares_clone);
+				}//end looping while some rooms can't be reached
+
+				// remove doors until stairs are far enough apart
+				let mut connectivity = graph.stairs_connectivity().unwrap_or(0);
+				while connectivity < min_connectivity {
+					// find the removable door which does the most to separate the stairs.
+					// doors that don't change connectivity may still be removed, since
+					// doing so can open up better removals on later passes
+					let mut best_removal: Option<(usize, SquareGrid, RoomGraph)> = None;
+					for (door_squares, _) in graph.doors() {
+						let mut test_squares = squares_clone.clone();
+						CRG::paint_region(&mut test_squares, door_squares, CRGC::Wall);
+						let test_graph = RoomGraph::from_squares(&test_squares);
+						if !test_graph.is_fully_connected() { continue; }
+						let test_connectivity = test_graph.stairs_connectivity().unwrap_or(0);
+						let is_better = match &best_removal {
+							Some((best_connectivity, _, _)) => test_connectivity > *best_connectivity,
+							None => test_connectivity >= connectivity,
+						};
+						if is_better { best_removal = Some((test_connectivity, test_squares, test_graph)); }
+					}//end looping over doors we might remove
+
+					match best_removal {
+						Some((new_connectivity, new_squares, new_graph)) => {
+							connectivity = new_connectivity;
+							squares_clone = new_squares;
+							graph = new_graph;
+						}, None => return Err(format!("Couldn't raise connectivity to {}. The shortest path between level connections goes through {} rooms, and removing any more doors would leave some rooms unreachable.", min_connectivity, connectivity)),
+					}//end matching whether we found a door to remove
+				}//end looping while connectivity is too low
+
+				self.squares = Some(squares_clone);
+				Ok(())
+			}, None => Err(String::from("No Squares Set"))
+		}//end matching whether we have squares
 	}//end enforce connectivity(self, min_connectivity)
+
+	/// Adds doors between rooms which are far apart from each other, until the rooms and
+	/// doors have at least target_cycles independent cycles, as counted by [RoomGraph::independent_cycles()].
+	/// 
+	/// Each door is placed on a wall one or two squares thick between the two neighboring rooms
+	/// which need the most doors to get from one to the other, so each new loop is as long as
+	/// possible. Walls next to an existing door are skipped, so new doors don't merge into old ones.
+	/// 
+	/// If this function is successful, changes will be reflected in squares. If squares is None or
+	/// there are no more walls that would make a loop before reaching target_cycles, then no changes
+	/// will be made, and this function will return Err.
+	pub fn inject_loops(&mut self, target_cycles: usize) -> Result<(),String> {
+		match &self.squares {
+			Some(squares) => {
+				let mut squares_clone = squares.clone();
+				let mut graph = RoomGraph::from_squares(&squares_clone);
+				while graph.independent_cycles() < target_cycles {
+					let is_door = |row: usize, col: usize| squares_clone.get(&row, &col)
+						.is_some_and(|square| matches!(CRGC::from_kind(*square.kind()), CRGC::Door | CRGC::SecretDoor));
+					let mut candidates: Vec<(SquareRegion, usize, usize)> = CRG::find_door_candidates(&squares_clone, &graph).into_iter()
+						.filter(|(wall_squares, _, _)| !wall_squares.iter().any(|(row, col)| squares_clone.orthogonal_neighbors(*row, *col).any(|(row_a, col_a)| is_door(row_a, col_a))))
+						.collect();
+					candidates.shuffle(&mut self.rng);
+
+					// find the pair of rooms which are the most doors apart, ignoring rooms that can't reach each other,
+					// since a door between those would join them without making a loop
+					let mut distances_from: BTreeMap<usize, Vec<Option<usize>>> = BTreeMap::new();
+					let mut farthest: Option<(usize, SquareRegion)> = None;
+					for (wall_squares, room_a, room_b) in candidates {
+						let distances = distances_from.entry(room_a).or_insert_with(|| graph.room_distances(room_a));
+						if let Some(distance) = distances[room_b] {
+							let is_farther = farthest.as_ref().is_none_or(|(farthest_distance, _)| distance > *farthest_distance);
+							if distance >= 2 && is_farther { farthest = Some((distance, wall_squares)); }
+						}//end if these rooms can already reach each other
+					}//end looping over walls we could put doors on
+
+					match farthest {
+						Some((_, wall_squares)) => {
+							CRG::paint_region(&mut squares_clone, &wall_squares, CRGC::Door);
+							graph = RoomGraph::from_squares(&squares_clone);
+						}, None => return Err(format!("Couldn't reach {} independent cycles. The rooms only have {}, and no more rooms share a wall thin enough to make a loop with a door.", target_cycles, graph.independent_cycles())),
+					}//end matching whether we found somewhere to make a loop
+				}//end looping while there aren't enough cycles
+
+				self.squares = Some(squares_clone);
+				Ok(())
+			}, None => Err(String::from("No Squares Set"))
+		}//end matching whether we have squares
+	}//end inject_loops(self, target_cycles)
+
+	/// Turns some of the doors on shortcuts into secret doors.  
+	/// A door is on a shortcut if the rooms it connects could still reach each other without it,
+	/// and each of those doors becomes secret with a chance of secret_chance.
+	/// 
+	/// Afterwards, the stairs must still be able to reach each other without going through any secret doors,
+	/// so that players can always get between the up, down, and exit stairs of a level. If they can't, secret
+	/// doors, including any that were already in squares, are turned back into normal doors one at a time until they can.
+	/// 
+	/// If squares is None, then no changes will be made and this function will return Err.
+	pub fn place_secret_doors(&mut self, secret_chance: f64) -> Result<(),String> {
+		match &self.squares {
+			Some(squares) => {
+				let mut squares_clone = squares.clone();
+				let graph = RoomGraph::from_squares(&squares_clone);
+				for (door_index, (door_squares, _)) in graph.doors().iter().enumerate() {
+					if graph.is_secret_door(door_index) || !graph.is_shortcut(door_index) { continue; }
+					if self.rng.gen_bool(secret_chance.clamp(0.0, 1.0)) { CRG::paint_region(&mut squares_clone, door_squares, CRGC::SecretDoor); }
+				}//end looping over doors that might become secret
+
+				CRG::reveal_secret_doors(&mut squares_clone, &mut self.rng);
+				self.squares = Some(squares_clone);
+				Ok(())
+			}, None => Err(String::from("No Squares Set"))
+		}//end matching whether we have squares
+	}//end place_secret_doors(self, secret_chance)
+
+	/// Turns secret doors in squares back into normal doors until the stairs can reach each other without secret doors.  
+	/// Each time, a random secret door joining two groups of openly connected rooms is revealed, preferring
+	/// doors that touch more groups with stairs in them. Stops early if no secret door would help.
+	fn reveal_secret_doors<R: Rng>(squares: &mut SquareGrid, rng: &mut R) {
+		loop {
+			let graph = RoomGraph::from_squares(squares);
+			if graph.stairs_connected_without_secret_doors() { return; }
+			let open_groups = graph.open_components();
+			let stair_groups: Vec<usize> = graph.stairs().iter().map(|(_, room)| open_groups[*room]).collect();
+
+			let mut joining_doors: Vec<(usize, usize)> = Vec::new();
+			for (door_index, (_, touched_rooms)) in graph.doors().iter().enumerate() {
+				if !graph.is_secret_door(door_index) { continue; }
+				let mut touched_groups: Vec<usize> = touched_rooms.iter().map(|room| open_groups[*room]).collect();
+				touched_groups.sort_unstable();
+				touched_groups.dedup();
+				if touched_groups.len() < 2 { continue; }
+				let stair_groups_touched = touched_groups.iter().filter(|group| stair_groups.contains(group)).count();
+				joining_doors.push((door_index, stair_groups_touched));
+			}//end looping over secret doors
+			joining_doors.shuffle(rng);
+
+			match joining_doors.iter().max_by_key(|(_, stair_groups_touched)| *stair_groups_touched) {
+				Some((door_index, _)) => CRG::paint_region(squares, &graph.doors()[*door_index].0, CRGC::Door),
+				None => return,
+			}//end matching whether any secret door can help
+		}//end looping until stairs are connected or nothing else can be revealed
+	}//end reveal_secret_doors(squares, rng)
+
+	/// Adds doors so that rooms are connected one way within zones and another way between zones.
+	/// 
+	/// Doors are only placed on walls one or two squares thick between two rooms, with at most one
+	/// door for each pair of rooms. Within each zone, doors are first added until every room in the zone
+	/// can reach the others, and then each other pair of rooms in the zone gets a door with a chance of
+	/// [ZoneSettings::doors_within], scaled by [ZoneType::within_door_scale()]. Between zones, doors are added
+	/// until every zone can reach the others, with dead end zones linked to only one other zone where possible,
+	/// and then each other pair of rooms in different zones which aren't dead ends gets a door with a chance
+	/// of [ZoneSettings::doors_between]. Any rooms still left unreachable get doors wherever they can.
+	/// 
+	/// Rooms which don't share a thin wall with any other room can't be connected, and are left as they are.  
+	/// Returns Err without making changes if squares or zones are None.
+	pub fn connect_zones(&mut self, settings: &ZoneSettings) -> Result<(),String> {
+		let zones = match &self.zones {
+			Some(zones) => zones,
+			None => return Err(String::from("Zones need to be grown before they can be connected.")),
+		};
+		match &self.squares {
+			Some(squares) => {
+				let mut squares_clone = squares.clone();
+				let graph = RoomGraph::from_squares(&squares_clone);
+				let room_zones: Vec<Option<usize>> = graph.rooms().iter()
+					.map(|room| room.first().and_then(|(row, col)| zones.zone_at(*row, *col)))
+					.collect();
+				let is_dead_end = |zone: usize| zones.zone_type(zone).is_some_and(|zone_type| zone_type.is_dead_end());
+
+				// pick a random door for each pair of rooms sharing a thin wall, in a random order
+				let mut candidates = CRG::find_door_candidates(&squares_clone, &graph);
+				candidates.shuffle(&mut self.rng);
+				let mut door_for_pair: BTreeMap<(usize,usize), SquareRegion> = BTreeMap::new();
+				for (wall_squares, room_a, room_b) in candidates {
+					door_for_pair.entry((room_a.min(room_b), room_a.max(room_b))).or_insert(wall_squares);
+				}//end keeping one door for each pair of rooms
+				let mut pairs: Vec<((usize,usize), SquareRegion)> = door_for_pair.into_iter().collect();
+				pairs.shuffle(&mut self.rng);
+				let mut used = vec![false; pairs.len()];
+
+				// rooms already connected through existing doors start out joined
+				let mut room_groups: Vec<usize> = (0..graph.rooms().len()).collect();
+				for (room_a, adjacent) in graph.adjacency().iter().enumerate() {
+					for (room_b, _) in adjacent { CRG::join_groups(&mut room_groups, room_a, *room_b); }
+				}//end joining rooms connected by existing doors
+
+				// connect rooms within each zone
+				for (pair_idx, ((room_a, room_b), _)) in pairs.iter().enumerate() {
+					let zone = match (room_zones[*room_a], room_zones[*room_b]) {
+						(Some(zone_a), Some(zone_b)) if zone_a == zone_b => zone_a,
+						_ => continue,
+					};
+					let extra_chance = settings.doors_within * zones.zone_type(zone).map_or(1.0, |zone_type| zone_type.within_door_scale());
+					used[pair_idx] = CRG::join_groups(&mut room_groups, *room_a, *room_b) || self.rng.gen_bool(extra_chance.clamp(0.0, 1.0));
+				}//end looping over pairs of rooms in the same zone
+
+				// connect zones to each other, first between zones that aren't dead ends, then giving each
+				// dead end a single link to a zone that isn't one, then linking dead ends to zones that
+				// aren't dead ends again where a dead end is the only way through, then linking whatever is still separate
+				let mut zone_groups: Vec<usize> = (0..zones.len()).collect();
+				let mut zone_links = vec![0; zones.len()];
+				for pass in 0..4 {
+					for (pair_idx, ((room_a, room_b), _)) in pairs.iter().enumerate() {
+						let (zone_a, zone_b) = match (room_zones[*room_a], room_zones[*room_b]) {
+							(Some(zone_a), Some(zone_b)) if zone_a != zone_b => (zone_a, zone_b),
+							_ => continue,
+						};
+						let can_link = match (pass, is_dead_end(zone_a), is_dead_end(zone_b)) {
+							(0..=2, false, false) => true,
+							// a dead end is only linked to a zone that isn't one, and only once unless that's the only way through
+							(1, true, false) => zone_links[zone_a] == 0,
+							(1, false, true) => zone_links[zone_b] == 0,
+							(2, true, false) | (2, false, true) => true,
+							(0..=2, _, _) => false,
+							_ => true,
+						};
+						if !can_link { continue; }
+						if CRG::join_groups(&mut zone_groups, zone_a, zone_b) {
+							used[pair_idx] = true;
+							zone_links[zone_a] += 1;
+							zone_links[zone_b] += 1;
+							CRG::join_groups(&mut room_groups, *room_a, *room_b);
+						}//end if this door links two separate groups of zones
+					}//end looping over pairs of rooms in different zones
+				}//end looping over each pass
+
+				// add extra links between zones which aren't dead ends
+				for (pair_idx, ((room_a, room_b), _)) in pairs.iter().enumerate() {
+					if used[pair_idx] { continue; }
+					if let (Some(zone_a), Some(zone_b)) = (room_zones[*room_a], room_zones[*room_b]) {
+						if zone_a == zone_b || is_dead_end(zone_a) || is_dead_end(zone_b) { continue; }
+						if self.rng.gen_bool(settings.doors_between.clamp(0.0, 1.0)) {
+							used[pair_idx] = true;
+							CRG::join_groups(&mut room_groups, *room_a, *room_b);
+						}//end if we're adding an extra link
+					}//end if both rooms are in zones
+				}//end looping over pairs of rooms that might get extra links
+
+				// make sure any rooms left out, such as rooms outside of zones, can still be reached
+				for (pair_idx, ((room_a, room_b), _)) in pairs.iter().enumerate() {
+					if !used[pair_idx] && CRG::join_groups(&mut room_groups, *room_a, *room_b) { used[pair_idx] = true; }
+				}//end looping over pairs to connect anything left over
+
+				for (pair_idx, (_, wall_squares)) in pairs.iter().enumerate() {
+					if used[pair_idx] { CRG::paint_region(&mut squares_clone, wall_squares, CRGC::Door); }
+				}//end painting each door we chose
+
+				self.squares = Some(squares_clone);
+				Ok(())
+			}, None => Err(String::from("No Squares Set"))
+		}//end matching whether we have squares
+	}//end connect_zones(self, settings)
+
+	/// Joins the groups containing a and b, where groups[idx] points towards the first member of the group idx is in.  
+	/// Returns true if a and b were in different groups.
+	fn join_groups(groups: &mut Vec<usize>, a: usize, b: usize) -> bool {
+		let find = |groups: &mut Vec<usize>, mut idx: usize| {
+			while groups[idx] != idx { groups[idx] = groups[groups[idx]]; idx = groups[idx]; }
+			idx
+		};
+		let group_a = find(groups, a);
+		let group_b = find(groups, b);
+		if group_a == group_b { return false; }
+		groups[group_a.max(group_b)] = group_a.min(group_b);
+		true
+	}//end join_groups(groups, a, b)
+
+	/// Finds walls, one or two squares thick, which separate two different rooms.  
+	/// Returns the (row, col) coords of the wall squares, and the indices of the
+	/// rooms on either side of them. Turning those wall squares into doors would
+	/// connect those rooms.
+	fn find_door_candidates(squares: &SquareGrid, graph: &RoomGraph) -> Vec<(SquareRegion, usize, usize)> {
+		let rows = *squares.rows();
+		let cols = *squares.cols();
+		let is_wall = |row: usize, col: usize| {
+			match squares.get(&row, &col) {
+				Some(square) => CRGC::from_kind(*square.kind()) == CRGC::Wall,
+				None => false,
+			}//end matching whether we can get square
+		};
+		let mut candidates = Vec::new();
+		for row in 0..rows {
+			for col in 0..cols {
+				if !is_wall(row, col) { continue; }
+				// look down and right from this square, for walls of thickness 1 or 2
+				for (row_step, col_step) in [(1,0), (0,1)] {
+					if row < row_step || col < col_step { continue; }
+					let before = (row - row_step, col - col_step);
+					for thickness in 1..=2 {
+						let after = (row + row_step * thickness, col + col_step * thickness);
+						if after.0 >= rows || after.1 >= cols { break; }
+						let mut wall_squares = Vec::new();
+						for step in 0..thickness { wall_squares.push((row + row_step * step, col + col_step * step)); }
+						if !wall_squares.iter().all(|(w_row, w_col)| is_wall(*w_row, *w_col)) { break; }
+						if let (Some(room_a), Some(room_b)) = (graph.room_at(before.0, before.1), graph.room_at(after.0, after.1)) {
+							if room_a != room_b { candidates.push((wall_squares, room_a, room_b)); }
+							break;
+						}//end if there are rooms on either side of the wall
+					}//end looping over possible wall thicknesses
+				}//end looping over directions
+			}//end looping over cols
+		}//end looping over rows
+		candidates
+	}//end find_door_candidates(squares, graph)
+
+	/// Sets the color of every square in region to the color of class.
+	fn paint_region(squares: &mut SquareGrid, region: &SquareRegion, class: CRGC) {
+		for (row, col) in region {
+			match squares.get_mut(row, col) {
+				Some(square) => square.set_kind(class.kind()),
+				None => println!("Couldn't access square at row:{}, col:{}, painting {:?}", row, col, class),
+			}//end matching whether we can get the square
+		}//end looping over squares in region
+	}//end paint_region(squares, region, class)
 }//end impl for CRG
 
+/// Graph of the rooms within a [SquareGrid], built by [CRG::calculate_connectivity()].  
+/// Rooms are groups of orthogonally adjacent Floor, RoomStart, and Stairs squares.
+/// Doors are groups of orthogonally adjacent Door squares, or of SecretDoor squares, and a door
+/// connects every room it touches.  
+/// All coordinates are stored as (row, col).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RoomGraph {
+	/// coords of the squares in each room
+	rooms: Vec<SquareRegion>,
+	/// coords of the squares in each door, plus the indices of rooms the door touches
+	doors: Vec<(SquareRegion, Vec<usize>)>,
+	/// parallel to doors, whether each door is made of SecretDoor squares
+	secret_doors: Vec<bool>,
+	/// parallel to rooms, (room index, door index) for each room reachable through a door
+	adjacency: Vec<Vec<(usize,usize)>>,
+	/// coords of the squares in each level connection, plus the index of the room it is in
+	stairs: Vec<(SquareRegion, usize)>,
+	/// room index of each square, in row-major order
+	room_of_square: Vec<Option<usize>>,
+	rows: usize,
+	cols: usize,
+}//end struct RoomGraph
+
+impl RoomGraph {
+	/// Builds the graph of rooms, doors, and level connections from squares.
+	pub fn from_squares(squares: &SquareGrid) -> RoomGraph {
+		let rows = *squares.rows();
+		let cols = *squares.cols();
+		let classes: Vec<Option<CRGC>> = (0..rows * cols)
+			.map(|idx| squares.get(&(idx / cols), &(idx % cols)).map(|sqr| CRGC::from_kind(*sqr.kind())))
+			.collect();
+		let is_walkable = |class: &Option<CRGC>| matches!(class, Some(CRGC::Floor) | Some(CRGC::RoomStart) | Some(CRGC::Stairs));
+
+		// flood fill walkable squares into rooms
+		let mut room_of_square = vec![None; rows * cols];
+		let mut rooms = Vec::new();
+		for start in 0..rows * cols {
+			if room_of_square[start].is_some() || !is_walkable(&classes[start]) { continue; }
+			let room_index = rooms.len();
+			room_of_square[start] = Some(room_index);
+			let region = RoomGraph::flood_region(start, rows, cols, |idx| {
+				if room_of_square[idx].is_none() && is_walkable(&classes[idx]) {
+					room_of_square[idx] = Some(room_index);
+					true
+				} else { false }
+			});
+			rooms.push(region);
+		}//end looping over squares to find rooms
+
+		// flood fill door squares into doors, recording the rooms each door touches
+		let mut door_visited = vec![false; rows * cols];
+		let mut doors = Vec::new();
+		let mut secret_doors = Vec::new();
+		let mut adjacency = vec![Vec::new(); rooms.len()];
+		for start in 0..rows * cols {
+			let door_class = classes[start];
+			if door_visited[start] || !matches!(door_class, Some(CRGC::Door) | Some(CRGC::SecretDoor)) { continue; }
+			door_visited[start] = true;
+			let region = RoomGraph::flood_region(start, rows, cols, |idx| {
+				if !door_visited[idx] && classes[idx] == door_class {
+					door_visited[idx] = true;
+					true
+				} else { false }
+			});
+			let mut touched_rooms = Vec::new();
+			for (row, col) in &region {
+				for (row_a, col_a) in RoomGraph::orthogonal_neighbors(*row, *col, rows, cols) {
+					if let Some(room_index) = room_of_square[row_a * cols + col_a] {
+						if !touched_rooms.contains(&room_index) { touched_rooms.push(room_index); }
+					}//end if this neighbor is part of a room
+				}//end looping over neighbors of door square
+			}//end looping over squares in door
+			let door_index = doors.len();
+			for room_a in &touched_rooms {
+				for room_b in &touched_rooms {
+					if room_a != room_b { adjacency[*room_a].push((*room_b, door_index)); }
+				}//end looping over other rooms touched by door
+			}//end connecting each pair of rooms touched by door
+			doors.push((region, touched_rooms));
+			secret_doors.push(door_class == Some(CRGC::SecretDoor));
+		}//end looping over squares to find doors
+
+		// flood fill stairs squares into level connections
+		let mut stairs_visited = vec![false; rows * cols];
+		let mut stairs = Vec::new();
+		for start in 0..rows * cols {
+			if stairs_visited[start] || classes[start] != Some(CRGC::Stairs) { continue; }
+			stairs_visited[start] = true;
+			let region = RoomGraph::flood_region(start, rows, cols, |idx| {
+				if !stairs_visited[idx] && classes[idx] == Some(CRGC::Stairs) {
+					stairs_visited[idx] = true;
+					true
+				} else { false }
+			});
+			// stairs are walkable, so they were already flood filled into a room
+			if let Some(room_index) = room_of_square[start] { stairs.push((region, room_index)); }
+		}//end looping over squares to find stairs
+
+		RoomGraph { rooms, doors, secret_doors, adjacency, stairs, room_of_square, rows, cols }
+	}//end from_squares(squares)
+
+	/// Flood fills outward from start, which is a row-major index.  
+	/// The closure should return true, and do any of its own bookkeeping, if the
+	/// row-major index given to it should be added to the region.
+	/// Start is always added to the region.  
+	/// Returns the (row, col) coords of every square in the region.
+	fn flood_region<F: FnMut(usize) -> bool>(start: usize, rows: usize, cols: usize, mut accept: F) -> SquareRegion {
+		let mut region = Vec::new();
+		let mut to_visit = vec![(start / cols, start % cols)];
+		while let Some((row, col)) = to_visit.pop() {
+			region.push((row, col));
+			for (row_a, col_a) in RoomGraph::orthogonal_neighbors(row, col, rows, cols) {
+				if accept(row_a * cols + col_a) { to_visit.push((row_a, col_a)); }
+			}//end looping over neighbors
+		}//end looping while we have squares to visit
+		region
+	}//end flood_region(start, rows, cols, accept)
+
+	/// Gets the (row, col) coords orthogonally adjacent to row, col that are within bounds.
+	fn orthogonal_neighbors(row: usize, col: usize, rows: usize, cols: usize) -> Vec<(usize,usize)> {
+		let mut neighbors = Vec::new();
+		if row > 0 { neighbors.push((row - 1, col)); }
+		if row + 1 < rows { neighbors.push((row + 1, col)); }
+		if col > 0 { neighbors.push((row, col - 1)); }
+		if col + 1 < cols { neighbors.push((row, col + 1)); }
+		neighbors
+	}//end orthogonal_neighbors(row, col, rows, cols)
+
+	/// The (row, col) coords of squares within each room.
+	pub fn rooms(&self) -> &Vec<SquareRegion> { &self.rooms }
+	/// The (row, col) coords of squares within each door, plus the indices of rooms each door touches.
+	pub fn doors(&self) -> &Vec<(SquareRegion, Vec<usize>)> { &self.doors }
+	/// Returns true if the door at door_index is made of SecretDoor squares.
+	pub fn is_secret_door(&self, door_index: usize) -> bool { self.secret_doors.get(door_index).copied().unwrap_or(false) }
+	/// Parallel to rooms, gives (room index, door index) for each room reachable through a single door.
+	pub fn adjacency(&self) -> &Vec<Vec<(usize,usize)>> { &self.adjacency }
+	/// The (row, col) coords of squares within each level connection, plus the index of the room it is in.
+	pub fn stairs(&self) -> &Vec<(SquareRegion, usize)> { &self.stairs }
+	/// Gets the index of the room containing the square at row, col, if any.
+	pub fn room_at(&self, row: usize, col: usize) -> Option<usize> {
+		if row < self.rows && col < self.cols { self.room_of_square[row * self.cols + col] } else { None }
+	}//end room_at(row, col)
+
+	/// Finds the number of doors needed to reach each room from start_room.  
+	/// Parallel to rooms. Rooms which can't be reached are None.
+	pub fn room_distances(&self, start_room: usize) -> Vec<Option<usize>> {
+		self.distances_through(start_room, |_| true)
+	}//end room_distances(self, start_room)
+
+	/// Finds the number of doors needed to reach each room from start_room without going through any secret doors.  
+	/// Parallel to rooms. Rooms which can't be reached are None.
+	pub fn open_room_distances(&self, start_room: usize) -> Vec<Option<usize>> {
+		self.distances_through(start_room, |door_index| !self.is_secret_door(door_index))
+	}//end open_room_distances(self, start_room)
+
+	/// Finds the number of doors needed to reach each room from start_room, only going through doors
+	/// for which can_pass returns true.  
+	/// Parallel to rooms. Rooms which can't be reached are None.
+	fn distances_through<F: Fn(usize) -> bool>(&self, start_room: usize, can_pass: F) -> Vec<Option<usize>> {
+		let mut distances = vec![None; self.rooms.len()];
+		if start_room >= self.rooms.len() { return distances; }
+		distances[start_room] = Some(0);
+		let mut queue = std::collections::VecDeque::new();
+		queue.push_back(start_room);
+		while let Some(room) = queue.pop_front() {
+			let room_distance = distances[room].unwrap_or(0);
+			for (adjacent_room, door_index) in &self.adjacency[room] {
+				if distances[*adjacent_room].is_none() && can_pass(*door_index) {
+					distances[*adjacent_room] = Some(room_distance + 1);
+					queue.push_back(*adjacent_room);
+				}//end if we haven't reached this room yet
+			}//end looping over rooms adjacent to this one
+		}//end looping while rooms are left to visit
+		distances
+	}//end distances_through(self, start_room, can_pass)
+
+	/// Groups rooms that can reach each other through doors.  
+	/// Parallel to rooms, gives the index of the group each room is in.
+	pub fn components(&self) -> Vec<usize> {
+		self.components_through(|room| self.room_distances(room))
+	}//end components(self)
+
+	/// Groups rooms that can reach each other without going through any secret doors.  
+	/// Parallel to rooms, gives the index of the group each room is in.
+	pub fn open_components(&self) -> Vec<usize> {
+		self.components_through(|room| self.open_room_distances(room))
+	}//end open_components(self)
+
+	/// Groups rooms using distances, which gives the distance from a room to every other room.
+	fn components_through<F: Fn(usize) -> Vec<Option<usize>>>(&self, distances: F) -> Vec<usize> {
+		let mut components: Vec<Option<usize>> = vec![None; self.rooms.len()];
+		let mut component_count = 0;
+		for room in 0..self.rooms.len() {
+			if components[room].is_some() { continue; }
+			for (other_room, distance) in distances(room).iter().enumerate() {
+				if distance.is_some() { components[other_room] = Some(component_count); }
+			}//end marking every room reachable from this one
+			component_count += 1;
+		}//end looping over rooms without a group
+		components.into_iter().map(|component| component.unwrap_or(0)).collect()
+	}//end components_through(self, distances)
+
+	/// Counts the independent cycles among rooms and doors, which is the number of doors that could be
+	/// removed without changing which rooms can reach each other.  
+	/// A door touching more than two rooms counts as one less connection than the number of rooms it touches.
+	pub fn independent_cycles(&self) -> usize {
+		let connections: usize = self.doors.iter().map(|(_, touched_rooms)| touched_rooms.len().saturating_sub(1)).sum();
+		let component_count = self.components().iter().max().map_or(0, |max_component| max_component + 1);
+		(connections + component_count).saturating_sub(self.rooms.len())
+	}//end independent_cycles(self)
+
+	/// Returns true if the rooms touched by the door at door_index could still reach each other without it,
+	/// meaning that the door is a shortcut rather than the only way through.
+	pub fn is_shortcut(&self, door_index: usize) -> bool {
+		match self.doors.get(door_index) {
+			Some((_, touched_rooms)) if touched_rooms.len() > 1 => {
+				let distances = self.distances_through(touched_rooms[0], |other_door| other_door != door_index);
+				touched_rooms.iter().all(|room| distances[*room].is_some())
+			}, _ => false,
+		}//end matching whether door connects anything
+	}//end is_shortcut(self, door_index)
+
+	/// Returns true if every level connection can reach every other level connection without going through any secret doors.
+	pub fn stairs_connected_without_secret_doors(&self) -> bool {
+		match self.stairs.first() {
+			Some((_, first_room)) => {
+				let distances = self.open_room_distances(*first_room);
+				self.stairs.iter().all(|(_, room)| distances[*room].is_some())
+			}, None => true,
+		}//end matching whether there are any stairs
+	}//end stairs_connected_without_secret_doors(self)
+
+	/// Returns true if every room can be reached from every other room.
+	pub fn is_fully_connected(&self) -> bool {
+		self.rooms.is_empty() || self.room_distances(0).iter().all(|dist| dist.is_some())
+	}//end is_fully_connected(self)
+
+	/// Finds the fewest rooms, including the rooms at both ends, which must be
+	/// traveled through to get between two different level connections.  
+	/// Returns None if no two level connections can reach each other.
+	pub fn stairs_connectivity(&self) -> Option<usize> {
+		let mut connectivity: Option<usize> = None;
+		for (stair_index, (_, start_room)) in self.stairs.iter().enumerate() {
+			let distances = self.room_distances(*start_room);
+			for (_, other_room) in self.stairs.iter().skip(stair_index + 1) {
+				if let Some(distance) = distances[*other_room] {
+					let rooms_traversed = distance + 1;
+					connectivity = Some(connectivity.map_or(rooms_traversed, |cur| cur.min(rooms_traversed)));
+				}//end if we can reach the other stairs
+			}//end looping over the other stairs
+		}//end looping over each set of stairs
+		connectivity
+	}//end stairs_connectivity(self)
+}//end impl for RoomGraph
+
 /// Struct to hold some information about rectangular rooms,
 /// used as helper struct when growing rooms from RoomStarts.
 #[derive(Clone, Copy, Debug, PartialOrd)]
@@ -293,15 +1434,15 @@ impl RoomFromStart {
 	/// It is possible that corner coords might be within Room
 	fn get_corner_coords(&self, max_x: usize, max_y: usize) -> Vec<SquareClaim> {
 		let mut corners = Vec::new();
-		let low_x = self.x.checked_sub(1).unwrap_or(0);
-		let low_y = self.y.checked_sub(1).unwrap_or(0);
+		let low_x = self.x.saturating_sub(1);
+		let low_y = self.y.saturating_sub(1);
 		let hih_x = max_x.min(self.x + self.w);
 		let hih_y = max_y.min(self.y + self.h);
 		corners.push(SquareClaim::new(low_x, low_y, self.index));
 		corners.push(SquareClaim::new(low_x, hih_y, self.index));
 		corners.push(SquareClaim::new(hih_x, low_y, self.index));
 		corners.push(SquareClaim::new(hih_x, hih_y, self.index));
-		return corners;
+		corners
 	}//end get_corner coords
 
 	fn corner_coords_to_square(corners: &Vec<SquareClaim>, room_index: usize) -> Vec<SquareClaim> {
@@ -330,7 +1471,7 @@ impl RoomFromStart {
 			if !square_claims.contains(&claim2) {square_claims.push(claim2);}
 		}//end looping over y values
 
-		return square_claims;
+		square_claims
 	}//end corner_coords_to_squares
 
 	fn consume_claims<I: Iterator<Item = SquareClaim>>(&mut self, claims: I) {
@@ -352,6 +1493,74 @@ impl RoomFromStart {
 		self.w = max_x - self.x + 1;
 		self.h = max_y - self.y + 1;
 	}//end consume_claims
+
+	/// Gets this room grown by claims, or None if that wouldn't change it or would go past limits.
+	fn grown_by(&self, claims: &[SquareClaim], limits: &RoomLimits) -> Option<RoomFromStart> {
+		let mut grown = *self;
+		grown.consume_claims(claims.iter().cloned());
+		let changed = (grown.x, grown.y, grown.w, grown.h) != (self.x, self.y, self.w, self.h);
+		if changed && limits.allows(grown.w, grown.h) { Some(grown) } else { None }
+	}//end grown_by(self, claims, limits)
+
+	/// Grows this room by claims while staying within limits.
+	/// Growing on every side is tried first, and if that would go past limits, then growing along one axis, starting with the shorter side
+	/// of the room, and then growing on a single side, in a random order.
+	/// Returns false if the room can't grow any of these ways.
+	fn grow_within_limits<R: Rng>(&mut self, claims: &[SquareClaim], limits: &RoomLimits, rng: &mut R) -> bool {
+		let mut grown = *self;
+		grown.consume_claims(claims.iter().cloned());
+		if (grown.x, grown.y, grown.w, grown.h) == (self.x, self.y, self.w, self.h) { return false; }
+		if limits.allows(grown.w, grown.h) { *self = grown; return true; }
+
+		let (left, right, top, bottom) = (self.x, self.x + self.w - 1, self.y, self.y + self.h - 1);
+		let filtered = |keep: &dyn Fn(&SquareClaim) -> bool| claims.iter().filter(|claim| keep(claim)).cloned().collect::<Vec<SquareClaim>>();
+		// leaving out corners keeps growth to a single axis
+		let vertical = filtered(&|claim| claim.x >= left && claim.x <= right);
+		let horizontal = filtered(&|claim| claim.y >= top && claim.y <= bottom);
+		let mut axes = [vertical.clone(), horizontal.clone()];
+		if self.w < self.h || (self.w == self.h && rng.gen_bool(0.5)) { axes.reverse(); }
+		let mut sides = [
+			filtered(&|claim| claim.y < top && vertical.contains(claim)),
+			filtered(&|claim| claim.y > bottom && vertical.contains(claim)),
+			filtered(&|claim| claim.x < left && horizontal.contains(claim)),
+			filtered(&|claim| claim.x > right && horizontal.contains(claim)),
+		];
+		sides.shuffle(rng);
+
+		for candidate in axes.iter().chain(sides.iter()) {
+			if let Some(grown) = self.grown_by(candidate, limits) { *self = grown; return true; }
+		}//end trying each smaller way to grow
+		false
+	}//end grow_within_limits(self, claims, limits, rng)
+
+	/// Whether the square at row, col is part of the floor of this room.
+	fn contains(&self, row: usize, col: usize) -> bool {
+		col >= self.x && col < self.x + self.w && row >= self.y && row < self.y + self.h
+	}//end contains(self, row, col)
+
+	/// Whether the floor of this room and the floor of other share any squares, or are orthogonally next to each other.
+	fn touches(&self, other: &RoomFromStart) -> bool {
+		let x_overlap = self.x < other.x + other.w && other.x < self.x + self.w;
+		let y_overlap = self.y < other.y + other.h && other.y < self.y + self.h;
+		let x_touch = self.x <= other.x + other.w && other.x <= self.x + self.w;
+		let y_touch = self.y <= other.y + other.h && other.y <= self.y + self.h;
+		(x_overlap && y_touch) || (y_overlap && x_touch)
+	}//end touches(self, other)
+
+	/// Gets the (row, col) coords of the wall squares between this room and other,
+	/// if they are separated by a single wall. Returns an empty list otherwise.
+	fn shared_wall(&self, other: &RoomFromStart) -> Vec<(usize,usize)> {
+		let overlap = |low_a: usize, len_a: usize, low_b: usize, len_b: usize| low_a.max(low_b)..(low_a + len_a).min(low_b + len_b);
+		if other.y + other.h + 1 == self.y {
+			overlap(self.x, self.w, other.x, other.w).map(|col| (self.y - 1, col)).collect()
+		} else if self.y + self.h + 1 == other.y {
+			overlap(self.x, self.w, other.x, other.w).map(|col| (self.y + self.h, col)).collect()
+		} else if other.x + other.w + 1 == self.x {
+			overlap(self.y, self.h, other.y, other.h).map(|row| (row, self.x - 1)).collect()
+		} else if self.x + self.w + 1 == other.x {
+			overlap(self.y, self.h, other.y, other.h).map(|row| (row, self.x + self.w)).collect()
+		} else { Vec::new() }
+	}//end shared_wall(self, other)
 }//end impl Room
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
@@ -363,13 +1572,12 @@ struct SquareClaim {
 
 impl SquareClaim {
 	fn new(x: usize, y: usize, claim_index: usize) -> SquareClaim {
-		let mut tmp_vec = Vec::new(); tmp_vec.push(claim_index);
-		SquareClaim {x, y, claimants_indices: tmp_vec}
+		SquareClaim {x, y, claimants_indices: vec![claim_index]}
 	}//end new(x,y,claim)
 
 	/// Updates a list of claims with a new claim, returning true if the square
 	/// provided is already being claimed by another room.
-	fn update_claims<'room>(x_new: usize, y_new: usize, claims: &mut Vec<SquareClaim>, room_claim_index: usize) -> bool {
+	fn update_claims(x_new: usize, y_new: usize, claims: &mut Vec<SquareClaim>, room_claim_index: usize) -> bool {
 		let mut found_same = false;
 		for claim in claims.iter_mut() {
 			if claim.x == x_new && claim.y == y_new {
@@ -382,10 +1590,11 @@ impl SquareClaim {
 	}//end update claims
 }//end impl SquareClaim
 
-/// Constrained Room Growth Classification (based on Color)
+/// Constrained Room Growth Classification, read from the [CellKind] of each square.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
 pub enum CRGC {
 	Door,
+	SecretDoor,
 	Empty,
 	Floor,
 	RoomStart,
@@ -396,28 +1605,508 @@ pub enum CRGC {
 
 #[allow(dead_code)]
 impl CRGC {
-	pub fn classify(color: (u8,u8,u8)) -> CRGC {
-		match color {
-			(0,0,0) => CRGC::Wall,
-			(255,255,255) => CRGC::Empty,
-			(0,255,0) => CRGC::Stairs,
-			(255,0,0) => CRGC::RoomStart,
-			(0,0,255) => CRGC::Door,
-			(140,140,140) => CRGC::Floor,
-			_ => CRGC::Other(color),
-		}//end matching color
-	}//end classify()
-
-	pub fn color(&self) -> (u8,u8,u8) {
+	pub fn from_kind(kind: CellKind) -> CRGC {
+		match kind {
+			CellKind::Wall => CRGC::Wall,
+			CellKind::Empty => CRGC::Empty,
+			CellKind::Stairs => CRGC::Stairs,
+			CellKind::RoomStart => CRGC::RoomStart,
+			CellKind::Door => CRGC::Door,
+			CellKind::SecretDoor => CRGC::SecretDoor,
+			CellKind::Floor => CRGC::Floor,
+			CellKind::Other(color) => CRGC::Other(color),
+		}//end matching kind
+	}//end from_kind()
+
+	/// Gets the kind of square written for this classification.
+	pub fn kind(&self) -> CellKind {
 		match self {
-			CRGC::Door => (0,0,255),
-			CRGC::Empty => (255,255,255),
-			CRGC::Floor => (140,140,140),
-			CRGC::RoomStart => (255,0,0),
-			CRGC::Stairs => (0,255,0),
-			CRGC::Wall => (0,0,0),
-			CRGC::Other(c) => *c,
+			CRGC::Door => CellKind::Door,
+			CRGC::SecretDoor => CellKind::SecretDoor,
+			CRGC::Empty => CellKind::Empty,
+			CRGC::Floor => CellKind::Floor,
+			CRGC::RoomStart => CellKind::RoomStart,
+			CRGC::Stairs => CellKind::Stairs,
+			CRGC::Wall => CellKind::Wall,
+			CRGC::Other(c) => CellKind::Other(*c),
 		}//end matching self
-	}//end color()
+	}//end kind()
+
+}//end impl for CRGC
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::export::{text::{from_text, TextCharMap}, MapKind};
+
+	/// Builds room squares from a text map, using the default room characters.
+	fn room_squares(text: &str) -> SquareGrid {
+		from_text(text, MapKind::Room, &TextCharMap::default(), 1).unwrap()
+	}//end room_squares(text)
+
+	#[test]
+	fn connectivity_counts_rooms_between_stairs() {
+		let squares = room_squares("\
+#############
+#>..#...#..>#
+#...+...+...#
+#############");
+		let (connectivity, graph) = CRG::default().with_squares(squares).calculate_connectivity().unwrap();
+		assert_eq!(connectivity, 3);
+		assert_eq!(graph.rooms().len(), 3);
+		assert_eq!(graph.doors().len(), 2);
+		assert!(graph.is_fully_connected());
+	}//end connectivity_counts_rooms_between_stairs()
+
+	#[test]
+	fn connectivity_errs_when_stairs_cannot_meet() {
+		let squares = room_squares("\
+#########
+#>..#..>#
+#...#...#
+#########");
+		assert!(CRG::default().with_squares(squares).calculate_connectivity().is_err());
+	}//end connectivity_errs_when_stairs_cannot_meet()
+
+	#[test]
+	fn connectivity_needs_two_stairs() {
+		let squares = room_squares("\
+#########
+#>..+...#
+#########");
+		assert!(CRG::default().with_squares(squares).calculate_connectivity().is_err());
+	}//end connectivity_needs_two_stairs()
+
+	#[test]
+	fn enforce_connectivity_connects_every_room() {
+		let squares = room_squares("\
+#############
+#>..#...#..>#
+#...#...#...#
+#############");
+		let mut rg = CRG::default().with_seed(7).with_squares(squares);
+		rg.enforce_connectivity(3).unwrap();
+		let (connectivity, graph) = rg.calculate_connectivity().unwrap();
+		assert!(graph.is_fully_connected());
+		assert_eq!(connectivity, 3);
+	}//end enforce_connectivity_connects_every_room()
+
+	#[test]
+	fn enforce_connectivity_removes_shortcuts() {
+		// stairs are in the two top rooms, so the door between them has to go to reach a connectivity of 4
+		let squares = room_squares("\
+#########
+#>..+..>#
+#...#...#
+##+###+##
+#...#...#
+#...+...#
+#########");
+		let mut rg = CRG::default().with_seed(7).with_squares(squares);
+		rg.enforce_connectivity(4).unwrap();
+		let (connectivity, graph) = rg.calculate_connectivity().unwrap();
+		assert!(graph.is_fully_connected());
+		assert!(connectivity >= 4);
+		assert_eq!(graph.doors().len(), 3);
+	}//end enforce_connectivity_removes_shortcuts()
+
+	#[test]
+	fn enforce_connectivity_leaves_squares_on_err() {
+		let text = "\
+#########
+#>..+..>#
+#########";
+		let mut rg = CRG::default().with_seed(7).with_squares(room_squares(text));
+		assert!(rg.enforce_connectivity(5).is_err());
+		assert_eq!(rg.get_squares(), Some(&room_squares(text)));
+	}//end enforce_connectivity_leaves_squares_on_err()
+
+	#[test]
+	fn inject_loops_reaches_cycle_target() {
+		// the four rooms are joined in a line, so any new door between them makes a loop
+		let squares = room_squares("\
+###########
+#>...#...>#
+#....+....#
+#....#....#
+###+###+###
+#....#....#
+#....#....#
+#....#....#
+###########");
+		let mut rg = CRG::default().with_seed(3).with_squares(squares);
+		assert_eq!(RoomGraph::from_squares(rg.get_squares().unwrap()).independent_cycles(), 0);
+		rg.inject_loops(1).unwrap();
+		let graph = RoomGraph::from_squares(rg.get_squares().unwrap());
+		assert!(graph.independent_cycles() >= 1);
+		assert_eq!(graph.doors().len(), 4);
+		assert!(graph.is_fully_connected());
+	}//end inject_loops_reaches_cycle_target()
+
+	#[test]
+	fn inject_loops_leaves_squares_on_err() {
+		// the only walls between rooms are next to the doors already there
+		let text = "\
+#############
+#>..#...#..>#
+#...+...+...#
+#############";
+		let mut rg = CRG::default().with_seed(3).with_squares(room_squares(text));
+		assert!(rg.inject_loops(1).is_err());
+		assert_eq!(rg.get_squares(), Some(&room_squares(text)));
+		assert!(rg.inject_loops(0).is_ok());
+	}//end inject_loops_leaves_squares_on_err()
+
+	#[test]
+	fn secret_doors_leave_stairs_reachable() {
+		// the four rooms form a ring, so every door is a shortcut and can be made secret
+		let text = "\
+###########
+#>...#....#
+#....+....#
+#....#....#
+###+###+###
+#....#....#
+#....+...>#
+#....#....#
+###########";
+		for seed in 0..20 {
+			let mut rg = CRG::default().with_seed(seed).with_squares(room_squares(text));
+			rg.place_secret_doors(1.0).unwrap();
+			let graph = RoomGraph::from_squares(rg.get_squares().unwrap());
+			assert!(graph.stairs_connected_without_secret_doors(), "stairs cut off with seed {}", seed);
+			assert!(graph.is_fully_connected());
+			let secret_count = (0..graph.doors().len()).filter(|door_index| graph.is_secret_door(*door_index)).count();
+			assert!(secret_count >= 1, "no secret doors left with seed {}", seed);
+		}//end looping over seeds
+	}//end secret_doors_leave_stairs_reachable()
+
+	#[test]
+	fn secret_doors_blocking_stairs_are_revealed() {
+		let mut rg = CRG::default().with_seed(1).with_squares(room_squares("\
+#########
+#>..S..>#
+#########"));
+		rg.place_secret_doors(0.0).unwrap();
+		assert_eq!(rg.get_squares(), Some(&room_squares("\
+#########
+#>..+..>#
+#########")));
+	}//end secret_doors_blocking_stairs_are_revealed()
+
+	/// Gets the width and height of the box around room, and the number of floor squares in it.
+	fn room_size(room: &SquareRegion) -> (usize, usize, usize) {
+		let rows = room.iter().map(|(row, _)| *row);
+		let cols = room.iter().map(|(_, col)| *col);
+		let height = rows.clone().max().unwrap() - rows.min().unwrap() + 1;
+		let width = cols.clone().max().unwrap() - cols.min().unwrap() + 1;
+		(width, height, room.len())
+	}//end room_size(room)
+
+	#[test]
+	fn rooms_from_starts_stay_within_limits() {
+		let limits = RoomLimits { max_width: Some(6), max_height: Some(4), max_area: Some(20), max_aspect_ratio: Some(2.0), ..RoomLimits::default() };
+		let empty_text = vec![" ".repeat(30); 20].join("\n");
+		for seed in 0..5 {
+			let mut rg = CRG::default().with_seed(seed).with_room_limits(limits.clone()).with_squares(room_squares(&empty_text));
+			rg.add_spaced_room_starts(Some(6), StartPlacement::PoissonDisk { min_distance: 6.0 }).unwrap();
+			rg.grow_rooms_from_starts().unwrap();
+			let graph = RoomGraph::from_squares(rg.get_squares().unwrap());
+			assert!(!graph.rooms().is_empty());
+			assert!(graph.rooms().iter().any(|room| room.len() > 1), "no room grew with seed {}", seed);
+			for room in graph.rooms() {
+				let (width, height, area) = room_size(room);
+				assert!(limits.allows_floor(width, height, area), "{}x{} room of {} squares with seed {}", width, height, area, seed);
+			}//end checking each room
+		}//end looping over seeds
+	}//end rooms_from_starts_stay_within_limits()
+
+	#[test]
+	fn l_growth_stays_within_limits() {
+		let text = [
+			"              ",
+			"              ",
+			"  #####       ",
+			"  #...#       ",
+			"  #...#       ",
+			"  #...#       ",
+			"  #####       ",
+			"              ",
+			"              ",
+		].join("\n");
+		let mut unlimited = CRG::default().with_seed(5).with_squares(room_squares(&text));
+		unlimited.grow_rooms_l_growth().unwrap();
+		let (_, _, unlimited_area) = room_size(&RoomGraph::from_squares(unlimited.get_squares().unwrap()).rooms()[0]);
+		assert!(unlimited_area > 16);
+
+		let limits = RoomLimits { max_width: Some(5), max_area: Some(14), ..RoomLimits::default() };
+		for seed in 0..5 {
+			let mut rg = CRG::default().with_seed(seed).with_room_limits(limits.clone()).with_squares(room_squares(&text));
+			rg.grow_rooms_l_growth().unwrap();
+			let graph = RoomGraph::from_squares(rg.get_squares().unwrap());
+			assert_eq!(graph.rooms().len(), 1);
+			let (width, height, area) = room_size(&graph.rooms()[0]);
+			assert!(area > 9, "room didn't grow with seed {}", seed);
+			assert!(limits.allows_floor(width, height, area), "{}x{} room of {} squares with seed {}", width, height, area, seed);
+		}//end looping over seeds
+	}//end l_growth_stays_within_limits()
+
+	/// A room one square wide next to a room four squares wide, as rooms grown from starts would leave them.
+	fn narrow_and_wide_rooms() -> (SquareGrid, Vec<RoomFromStart>) {
+		let squares = room_squares("\
+########
+#.#....#
+#.#....#
+#.#....#
+########");
+		let narrow = RoomFromStart { x: 1, y: 1, w: 1, h: 3, index: 0, init_x: 1, init_y: 1, allowed_growth: false };
+		let wide = RoomFromStart { x: 3, y: 1, w: 4, h: 3, index: 1, init_x: 3, init_y: 1, allowed_growth: false };
+		(squares, vec![narrow, wide])
+	}//end narrow_and_wide_rooms()
+
+	#[test]
+	fn undersized_rooms_merge_into_neighbors() {
+		let (mut squares, rooms) = narrow_and_wide_rooms();
+		CRG::resolve_undersized_rooms(&mut squares, &rooms, &RoomLimits { min_width: 2, ..RoomLimits::default() }, None);
+		assert_eq!(squares, room_squares("\
+########
+#......#
+#......#
+#......#
+########"));
+	}//end undersized_rooms_merge_into_neighbors()
+
+	#[test]
+	fn undersized_rooms_are_removed_when_merging_is_too_big() {
+		let (mut squares, rooms) = narrow_and_wide_rooms();
+		// merging would make a room of 3 + 12 + 3 squares
+		CRG::resolve_undersized_rooms(&mut squares, &rooms, &RoomLimits { min_width: 2, max_area: Some(17), ..RoomLimits::default() }, None);
+		assert_eq!(squares, room_squares(&["  ######", "  #....#", "  #....#", "  #....#", "  ######"].join("\n")));
+		let (mut squares, rooms) = narrow_and_wide_rooms();
+		CRG::resolve_undersized_rooms(&mut squares, &rooms, &RoomLimits { min_width: 2, max_area: Some(18), ..RoomLimits::default() }, None);
+		assert_eq!(RoomGraph::from_squares(&squares).rooms().len(), 1);
+	}//end undersized_rooms_are_removed_when_merging_is_too_big()
+
+	#[test]
+	fn undersized_rooms_are_removed_when_merging_is_too_wide() {
+		let (mut squares, rooms) = narrow_and_wide_rooms();
+		// merging would make a room 1 + 1 + 4 squares across
+		CRG::resolve_undersized_rooms(&mut squares, &rooms, &RoomLimits { min_width: 2, max_width: Some(5), ..RoomLimits::default() }, None);
+		assert_eq!(squares, room_squares(&["  ######", "  #....#", "  #....#", "  #....#", "  ######"].join("\n")));
+		let (mut squares, rooms) = narrow_and_wide_rooms();
+		CRG::resolve_undersized_rooms(&mut squares, &rooms, &RoomLimits { min_width: 2, max_width: Some(6), ..RoomLimits::default() }, None);
+		assert_eq!(RoomGraph::from_squares(&squares).rooms().len(), 1);
+		let (mut squares, rooms) = narrow_and_wide_rooms();
+		// a room 6 squares across and 3 tall has an aspect ratio of 2
+		CRG::resolve_undersized_rooms(&mut squares, &rooms, &RoomLimits { min_width: 2, max_aspect_ratio: Some(1.9), ..RoomLimits::default() }, None);
+		assert_eq!(RoomGraph::from_squares(&squares).rooms().len(), 1);
+		assert_eq!(RoomGraph::from_squares(&squares).rooms()[0].len(), 12);
+	}//end undersized_rooms_are_removed_when_merging_is_too_wide()
+
+	#[test]
+	fn undersized_room_merges_count_earlier_merges() {
+		let squares = room_squares("\
+##########
+#.#....#.#
+#.#....#.#
+#.#....#.#
+##########");
+		let rooms = vec![
+			RoomFromStart { x: 1, y: 1, w: 1, h: 3, index: 0, init_x: 1, init_y: 1, allowed_growth: false },
+			RoomFromStart { x: 3, y: 1, w: 4, h: 3, index: 1, init_x: 3, init_y: 1, allowed_growth: false },
+			RoomFromStart { x: 8, y: 1, w: 1, h: 3, index: 2, init_x: 8, init_y: 1, allowed_growth: false },
+		];
+		// either narrow room fits when merged alone, making 18 squares, but not both, making 24
+		let mut merged = squares.clone();
+		CRG::resolve_undersized_rooms(&mut merged, &rooms, &RoomLimits { min_width: 2, max_area: Some(20), ..RoomLimits::default() }, None);
+		assert_eq!(merged, room_squares(&["########  ", "#......#  ", "#......#  ", "#......#  ", "########  "].join("\n")));
+		let mut merged = squares.clone();
+		CRG::resolve_undersized_rooms(&mut merged, &rooms, &RoomLimits { min_width: 2, max_width: Some(7), ..RoomLimits::default() }, None);
+		assert_eq!(RoomGraph::from_squares(&merged).rooms()[0].len(), 18);
+		let mut merged = squares;
+		CRG::resolve_undersized_rooms(&mut merged, &rooms, &RoomLimits { min_width: 2, max_area: Some(24), ..RoomLimits::default() }, None);
+		assert_eq!(RoomGraph::from_squares(&merged).rooms()[0].len(), 24);
+	}//end undersized_room_merges_count_earlier_merges()
+
+	#[test]
+	fn l_growth_makes_rooms_that_are_not_rectangles() {
+		let text = [
+			"           ",
+			"           ",
+			"   #####   ",
+			"   #...#   ",
+			"   #...#   ",
+			"   #####   ",
+			"           ",
+			"           ",
+		].join("\n");
+		for seed in 0..10 {
+			let mut rg = CRG::default().with_seed(seed).with_squares(room_squares(&text));
+			rg.grow_rooms_l_growth().unwrap();
+			let graph = RoomGraph::from_squares(rg.get_squares().unwrap());
+			assert_eq!(graph.rooms().len(), 1);
+			let (width, height, area) = room_size(&graph.rooms()[0]);
+			assert!(area > 6, "room didn't grow with seed {}", seed);
+			assert!(area < width * height, "{}x{} room is still a rectangle with seed {}", width, height, seed);
+		}//end looping over seeds
+	}//end l_growth_makes_rooms_that_are_not_rectangles()
+
+	/// Places starts on an empty grid, grows rooms, and adds doors, all with the random choices of rg.
+	fn generate_rooms(mut rg: CRG) -> SquareGrid {
+		rg.set_squares(room_squares(&vec![" ".repeat(30); 20].join("\n")));
+		rg.add_random_room_starts(Some(6)).unwrap();
+		rg.grow_rooms_from_starts().unwrap();
+		rg.place_random_doors(0.05).unwrap();
+		rg.pop_squares().unwrap()
+	}//end generate_rooms(rg)
+
+	#[test]
+	fn same_seed_gives_same_rooms() {
+		assert_eq!(generate_rooms(CRG::default().with_seed(11)), generate_rooms(CRG::default().with_seed(11)));
+		assert_ne!(generate_rooms(CRG::default().with_seed(11)), generate_rooms(CRG::default().with_seed(12)));
+		let rg = CRG::default().with_rng(&mut StdRng::seed_from_u64(4));
+		let seed = *rg.seed();
+		assert_eq!(generate_rooms(rg), generate_rooms(CRG::default().with_seed(seed)));
+	}//end same_seed_gives_same_rooms()
+	/// Generates zoned rooms on an empty grid with the given seed, returning the rooms and the zones they grew in.
+	fn generate_zoned_rooms(seed: u64, settings: &ZoneSettings) -> (SquareGrid, ZoneMap) {
+		let mut rg = CRG::default().with_seed(seed).with_squares(room_squares(&vec![" ".repeat(40); 30].join("\n")));
+		rg.generate_zoned_rooms(settings).unwrap();
+		let zones = rg.zones().clone().unwrap();
+		(rg.pop_squares().unwrap(), zones)
+	}//end generate_zoned_rooms(seed, settings)
+
+	/// Finds the rooms start can reach through edges between rooms that are all in rooms.
+	fn rooms_reached(edges: &[(usize,usize)], start: usize, rooms: &[usize]) -> Vec<usize> {
+		let mut reached = vec![start];
+		let mut idx = 0;
+		while idx < reached.len() {
+			for (room_a, room_b) in edges {
+				let other = match (*room_a == reached[idx], *room_b == reached[idx]) { (true, _) => *room_b, (_, true) => *room_a, _ => continue };
+				if rooms.contains(&other) && !reached.contains(&other) { reached.push(other); }
+			}//end looping over edges
+			idx += 1;
+		}//end reaching rooms
+		reached
+	}//end rooms_reached(edges, start, rooms)
+
+	#[test]
+	fn connect_zones_reaches_every_zone_with_one_link_per_dead_end() {
+		let settings = ZoneSettings { zone_types: vec![ZoneType::Public, ZoneType::SubLevel, ZoneType::Private, ZoneType::BossArea, ZoneType::SubLevel], ..ZoneSettings::default() };
+		let mut dead_ends_checked = 0;
+		for seed in 0..50 {
+			let (squares, zones) = generate_zoned_rooms(seed, &settings);
+			let is_dead_end = |zone: usize| zones.zone_type(zone).is_some_and(|zone_type| zone_type.is_dead_end());
+			let graph = RoomGraph::from_squares(&squares);
+			let room_zones: Vec<Option<usize>> = graph.rooms().iter()
+				.map(|room| room.first().and_then(|(row, col)| zones.zone_at(*row, *col)))
+				.collect();
+
+			// rooms are only left apart when no thin wall or door joins them
+			let all_rooms: Vec<usize> = (0..room_zones.len()).collect();
+			let doors: Vec<(usize,usize)> = graph.doors().iter()
+				.flat_map(|(_, door_rooms)| door_rooms.iter().flat_map(|room_a| door_rooms.iter().filter(move |room_b| room_a < *room_b).map(move |room_b| (*room_a, *room_b))))
+				.collect();
+			let mut could_link = doors.clone();
+			could_link.extend(CRG::find_door_candidates(&squares, &graph).into_iter().map(|(_, room_a, room_b)| (room_a, room_b)));
+			// rooms cut off from the rest are left alone, so follow the largest group that could be joined
+			let joinable = all_rooms.iter().map(|room| rooms_reached(&could_link, *room, &all_rooms)).max_by_key(|rooms| rooms.len()).unwrap();
+			let mut reached = rooms_reached(&doors, joinable[0], &all_rooms);
+			reached.sort();
+			let mut joinable_sorted = joinable.clone();
+			joinable_sorted.sort();
+			assert_eq!(reached, joinable_sorted, "rooms which could be joined weren't with seed {}", seed);
+			let mut zones_reached: Vec<usize> = reached.iter().filter_map(|room| room_zones[*room]).collect();
+			zones_reached.sort();
+			zones_reached.dedup();
+
+			// dead ends can only be kept to one link if the zones that aren't dead ends can reach each other
+			// without them, the rooms of each zone can reach each other without leaving it, and every
+			// dead end borders a zone that isn't one
+			let rooms_in = |in_zone: &dyn Fn(usize) -> bool| -> Vec<usize> { joinable.iter().copied().filter(|room| room_zones[*room].is_some_and(in_zone)).collect() };
+			let links_within = |rooms: &[usize]| rooms.is_empty() || rooms_reached(&could_link, rooms[0], rooms).len() == rooms.len();
+			if !links_within(&rooms_in(&|zone| !is_dead_end(zone))) { continue; }
+			if !zones_reached.iter().all(|zone_a| links_within(&rooms_in(&|zone_b| zone_b == *zone_a))) { continue; }
+			let borders_open_zone = |dead_end: usize| could_link.iter().any(|(room_a, room_b)| match (room_zones[*room_a], room_zones[*room_b]) {
+				(Some(zone_a), Some(zone_b)) => (zone_a == dead_end && !is_dead_end(zone_b)) || (zone_b == dead_end && !is_dead_end(zone_a)),
+				_ => false,
+			});
+			if !room_zones.iter().flatten().all(|zone| !is_dead_end(*zone) || borders_open_zone(*zone)) { continue; }
+			for dead_end in zones_reached.iter().copied().filter(|zone| is_dead_end(*zone)) {
+				let linked_zones: Vec<usize> = doors.iter().filter_map(|(room_a, room_b)| match (room_zones[*room_a], room_zones[*room_b]) {
+					(Some(zone_a), Some(zone_b)) if zone_a == dead_end && zone_b != dead_end => Some(zone_b),
+					(Some(zone_a), Some(zone_b)) if zone_b == dead_end && zone_a != dead_end => Some(zone_a),
+					_ => None,
+				}).collect();
+				assert_eq!(linked_zones.len(), 1, "dead end zone {} is linked to zones {:?} with seed {}", dead_end, linked_zones, seed);
+				assert!(!is_dead_end(linked_zones[0]), "dead end zone {} is linked to another dead end with seed {}", dead_end, seed);
+				dead_ends_checked += 1;
+			}//end checking each dead end zone
+		}//end looping over seeds
+		assert!(dead_ends_checked >= 10, "only {} dead ends could be checked", dead_ends_checked);
+	}//end connect_zones_reaches_every_zone_with_one_link_per_dead_end()
+	/// Gets the (row, col) coords of every room start in squares.
+	fn room_starts(squares: &SquareGrid) -> Vec<(usize,usize)> {
+		(0..*squares.rows()).flat_map(|row| (0..*squares.cols()).map(move |col| (row, col)))
+			.filter(|(row, col)| squares.get(row, col).map(|square| CRGC::from_kind(*square.kind())) == Some(CRGC::RoomStart))
+			.collect()
+	}//end room_starts(squares)
+
+	#[test]
+	fn close_room_starts_merge() {
+		let squares = room_squares(&[
+			"          ",
+			" ***      ",
+			"          ",
+			"        * ",
+			"          ",
+			"    *   * ",
+		].join("\n"));
+		let mut rg = CRG::default().with_squares(squares);
+		rg.merge_close_room_starts(2.0).unwrap();
+		assert_eq!(room_starts(rg.get_squares().unwrap()), vec![(1, 2), (3, 8), (5, 4), (5, 8)]);
+		rg.merge_close_room_starts(2.5).unwrap();
+		assert_eq!(room_starts(rg.get_squares().unwrap()), vec![(1, 2), (3, 8), (5, 4)]);
+		assert!(CRG::default().merge_close_room_starts(2.0).is_err());
+	}//end close_room_starts_merge()
+
+	#[test]
+	fn distant_room_starts_stay() {
+		let squares = room_squares(&[
+			"*   *",
+			"     ",
+			"     ",
+			"*   *",
+		].join("\n"));
+		let mut rg = CRG::default().with_squares(squares.clone());
+		rg.merge_close_room_starts(3.0).unwrap();
+		assert_eq!(rg.get_squares().unwrap(), &squares);
+	}//end distant_room_starts_stay()
+
+	#[test]
+	fn jittered_starts_stay_inside_their_cells() {
+		let squares = room_squares(&vec![" ".repeat(20); 20].join("\n"));
+		for seed in 0..10 {
+			let starts = CRG::jittered_grid_starts(&squares, 5, 100, &mut StdRng::seed_from_u64(seed));
+			assert_eq!(starts.len(), 16);
+			let mut cells: Vec<(usize,usize)> = starts.iter().map(|(row, col)| (row / 5, col / 5)).collect();
+			cells.sort();
+			cells.dedup();
+			assert_eq!(cells.len(), 16, "two starts share a cell with seed {}", seed);
+			// a margin of a quarter of the spacing is kept clear on each side of every cell
+			assert!(starts.iter().all(|(row, col)| (1..4).contains(&(row % 5)) && (1..4).contains(&(col % 5))), "start near cell edge with seed {}", seed);
+			assert_eq!(CRG::jittered_grid_starts(&squares, 5, 6, &mut StdRng::seed_from_u64(seed)).len(), 6);
+		}//end looping over seeds
+	}//end jittered_starts_stay_inside_their_cells()
 
-}//end impl for CRGC
\ No newline at end of file
+	#[test]
+	fn random_start_count_scales_with_grid_size() {
+		for seed in 0..20 {
+			let mut rg = CRG::default().with_seed(seed).with_squares(room_squares(&vec![" ".repeat(30); 20].join("\n")));
+			rg.add_random_room_starts(None).unwrap();
+			let start_count = room_starts(rg.get_squares().unwrap()).len();
+			// 20 rows and 30 cols average out to 25, so between 25 / 8 and 25 / 2 starts are placed
+			assert!((3..=12).contains(&start_count), "{} starts with seed {}", start_count, seed);
+		}//end looping over seeds
+	}//end random_start_count_scales_with_grid_size()
+}//end mod tests
@@ -1,14 +1,34 @@
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Ord, PartialOrd, Hash)]
+/// What a [Square] represents on a map.  
+/// Cave generation and room generation both read and write these,
+/// so the same map can go through both without being misread.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum CellKind {
+	Wall,
+	Floor,
+	/// Space that hasn't been used for anything yet.
+	Empty,
+	Stairs,
+	Door,
+	/// Door that is hidden from players until they find it.
+	SecretDoor,
+	RoomStart,
+	/// Something not understood by generation, remembered by the color it was drawn with.
+	Other((u8,u8,u8)),
+}//end enum CellKind
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 /// Represents an atomic rectangle of pixels for use in cellular automata generations.
 /// In this case, [x] and [y] refer to the x and y of ther pixel in the upper left corner 
-/// of the [Square] in regards to the full image this [Square] is a part of.
+/// of the [Square] in regards to the full image this [Square] is a part of.  
+/// What the square represents is stored as its [CellKind]. Use a [Palette] to get a color for it.
 pub struct Square {
 	x: usize,
 	y: usize,
 	width: usize,
 	height: usize,
-	color: (u8,u8,u8),
+	kind: CellKind,
 }//end struct Square
 
 #[allow(dead_code)]
@@ -19,24 +39,74 @@ impl Square {
 			y,
 			width,
 			height,
-			color: (0,255,255)
+			kind: CellKind::Empty,
 		}//end struct init
 	}//end new()
 
-	/// Allows easy setting of color with new Square.
-	pub fn with_color(mut self, color: (u8,u8,u8)) -> Square { self.color = color; return self; }
+	/// Allows easy setting of kind with new Square.
+	pub fn with_kind(mut self, kind: CellKind) -> Square { self.kind = kind; self }
 
-	/// Sets the color of this square
-	pub fn set_color(&mut self, color: (u8,u8,u8)) { self.color = color; }
+	/// Sets what this square represents.
+	pub fn set_kind(&mut self, kind: CellKind) { self.kind = kind; }
 	
 	pub fn x(&self) -> &usize { &self.x }
 	pub fn y(&self) -> &usize { &self.y }
 	pub fn width(&self) -> &usize { &self.width }
 	pub fn height(&self) -> &usize { &self.height }
-	pub fn color(&self) -> &(u8,u8,u8) { &self.color }
+	pub fn kind(&self) -> &CellKind { &self.kind }
 }//end impl for Square
 
-#[derive(Debug, PartialEq, Eq, Clone, Ord, PartialOrd, Hash)]
+/// The colors used to draw each [CellKind], and to tell which kind a drawn color means.  
+/// Every kind in a palette should have a different color, so that drawing squares and reading them back gives the same kinds.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub struct Palette {
+	pub wall: (u8,u8,u8),
+	pub floor: (u8,u8,u8),
+	pub empty: (u8,u8,u8),
+	pub stairs: (u8,u8,u8),
+	pub door: (u8,u8,u8),
+	pub secret_door: (u8,u8,u8),
+	pub room_start: (u8,u8,u8),
+}//end struct Palette
+
+impl Palette {
+	/// Colors used on the cave canvas, where white is floor.
+	pub fn cave() -> Palette {
+		Palette { wall: (0,0,0), floor: (255,255,255), empty: (210,210,210), stairs: (0,255,0), door: (0,0,255), secret_door: (128,0,128), room_start: (255,0,0) }
+	}//end cave()
+
+	/// Colors used on the room canvas, where white is empty space and gray is floor.
+	pub fn room() -> Palette {
+		Palette { wall: (0,0,0), floor: (140,140,140), empty: (255,255,255), stairs: (0,255,0), door: (0,0,255), secret_door: (128,0,128), room_start: (255,0,0) }
+	}//end room()
+
+	/// Gets the color used to draw kind.
+	pub fn color(&self, kind: CellKind) -> (u8,u8,u8) {
+		match kind {
+			CellKind::Wall => self.wall,
+			CellKind::Floor => self.floor,
+			CellKind::Empty => self.empty,
+			CellKind::Stairs => self.stairs,
+			CellKind::Door => self.door,
+			CellKind::SecretDoor => self.secret_door,
+			CellKind::RoomStart => self.room_start,
+			CellKind::Other(color) => color,
+		}//end matching kind
+	}//end color(self, kind)
+
+	/// Gets the kind drawn with color. Colors not in this palette are [CellKind::Other].
+	pub fn classify(&self, color: (u8,u8,u8)) -> CellKind {
+		[CellKind::Wall, CellKind::Floor, CellKind::Empty, CellKind::Stairs, CellKind::Door, CellKind::SecretDoor, CellKind::RoomStart].into_iter()
+			.find(|kind| self.color(*kind) == color)
+			.unwrap_or(CellKind::Other(color))
+	}//end classify(self, color)
+}//end impl for Palette
+
+/// The (row, col) coords of a group of orthogonally adjacent squares.
+pub type SquareRegion = Vec<(usize,usize)>;
+
+#[derive(Debug, PartialEq, Eq, Clone, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(try_from = "SavedSquareGrid", into = "SavedSquareGrid")]
 /// Represents all the [Squares] that represent the groups within a full image.  
 /// Once loaded into a SquareGrid, the record of image dimensions can't be changed, nor can the 
 /// positioning of squares.  
@@ -54,39 +124,74 @@ pub struct SquareGrid {
 impl SquareGrid {
 	/// - The only way to create a SquareGrid is with a vec of Squares and the dimensions of the original image.  
 	/// - When a SquareGrid is created, the bounds of each Square is checked ot ensure that no Squares exceed
-	/// the bounds of the given image dimensions.  
+	///   the bounds of the given image dimensions.  
 	/// - Since the bounds of Square objects cannot change after initialization, this means
-	/// that all bounds in every Square object within a SquareGrid should be valid for the given image dimensions.  
+	///   that all bounds in every Square object within a SquareGrid should be valid for the given image dimensions.  
+	/// - Squares are placed into rows by y value and into cols by x value, so they don't need to be given in any order.
+	///   They are stored row by row afterwards, which is the order [iter()] goes through them.  
 	/// ## Err Conditions
 	/// This function returns Err() if:  
 	/// - a square is found whose bounds exceed image dimensions  
+	/// - two squares have the same x and y
+	/// - some row and col has no square at it, so squares don't make a full grid
 	/// ## Not Checked
 	/// This function does not check if:  
 	/// - squares have bounds that overlap with each other
 	/// - squares completely cover all pixels within image dimensions
 	pub fn from_squares(squares: Vec<Square>, img_width: usize, img_height: usize) -> Result<SquareGrid, (Square, String)> {
-		// keep track of unique x values within squares, number of cols
-		let mut x_col_track: Vec<usize> = Vec::new();
-		// keep track of unique y values within squares, number of rows
-		let mut y_row_track: Vec<usize> = Vec::new();
-
 		for square in &squares {
-			// add new x to x_col_track
-			if !x_col_track.contains(&&square.x) { x_col_track.push(square.x); }
-			// add new y to y_row_track
-			if !y_row_track.contains(&&square.y) { y_row_track.push(square.y); }
 			// do bounds checking to ensure this square fits within img_width and img_height
 			if square.x + square.width > img_width || square.y + square.height > img_height {
-				let complaint = format!("Square with x:{0}, y:{1}, w:{2}, h:{3} has invalid bounds.\nFurthest x,y reach of square is ({4},{5}), while image has width of {6} and height of {7}.\nThe color of this square is {8:?}.", square.x, square.y, square.width, square.height, square.x + square.width, square.y + square.height, img_width, img_height, square.color);
+				let complaint = format!("Square with x:{0}, y:{1}, w:{2}, h:{3} has invalid bounds.\nFurthest x,y reach of square is ({4},{5}), while image has width of {6} and height of {7}.\nThe kind of this square is {8:?}.", square.x, square.y, square.width, square.height, square.x + square.width, square.y + square.height, img_width, img_height, square.kind);
 				return Err((*square, complaint));
 			}//end if this square has invalid bounds
 		}//end looping through all squares
 
+		// squares made by squareularization are already in order, so they can be used as is
+		if let Some(col_count) = SquareGrid::row_major_cols(&squares) {
+			let row_count = squares.len() / col_count;
+			return Ok(SquareGrid { squares, img_width, img_height, rows: row_count, cols: col_count });
+		}//end if squares are already a full grid in order
+
+		// unique x values within squares, one for each col, and unique y values, one for each row
+		let mut x_col_track: Vec<usize> = squares.iter().map(|square| square.x).collect();
+		let mut y_row_track: Vec<usize> = squares.iter().map(|square| square.y).collect();
+		x_col_track.sort_unstable();
+		x_col_track.dedup();
+		y_row_track.sort_unstable();
+		y_row_track.dedup();
+
 		let col_count = x_col_track.len();
 		let row_count = y_row_track.len();
 
+		// put each square in its place within a dense row-major buffer
+		let mut placed: Vec<Option<Square>> = vec![None; row_count * col_count];
+		for square in &squares {
+			// both searches always succeed, since every x and y was recorded above
+			let row = y_row_track.binary_search(&square.y).unwrap_or_default();
+			let col = x_col_track.binary_search(&square.x).unwrap_or_default();
+			if let Some(other) = placed[row * col_count + col] {
+				let complaint = format!("Square with x:{0}, y:{1}, w:{2}, h:{3} is at the same place as another square with w:{4}, h:{5}.\nEach row and col can only have one square.", square.x, square.y, square.width, square.height, other.width, other.height);
+				return Err((*square, complaint));
+			}//end if another square is already in this place
+			placed[row * col_count + col] = Some(*square);
+		}//end placing each square
+
+		let mut dense_squares = Vec::with_capacity(placed.len());
+		for (index, square) in placed.into_iter().enumerate() {
+			match square {
+				Some(square) => dense_squares.push(square),
+				None => {
+					let (row, col) = (index / col_count, index % col_count);
+					let complaint = format!("There's no square at row {0}, col {1} (x:{2}, y:{3}), so the squares don't make a full grid.", row, col, x_col_track[col], y_row_track[row]);
+					// there's no square to report, so an empty one stands in for the gap
+					return Err((Square::new(x_col_track[col], y_row_track[row], 0, 0), complaint));
+				},
+			}//end matching whether a square was placed here
+		}//end checking that every place has a square
+
 		Ok(SquareGrid {
-			squares,
+			squares: dense_squares,
 			img_width,
 			img_height,
 			rows: row_count,
@@ -94,9 +199,24 @@ impl SquareGrid {
 		})//end struct init
 	}//end from_squares
 
-	/// Allows immutable iteration through squares
+	/// Gets the number of cols if squares are already a full grid stored row by row,
+	/// with x increasing along each row and y increasing down each col.
+	fn row_major_cols(squares: &[Square]) -> Option<usize> {
+		let first = squares.first()?;
+		let col_count = squares.iter().take_while(|square| square.y == first.y).count();
+		if !squares.len().is_multiple_of(col_count) { return None; }
+		let first_row = &squares[..col_count];
+		if first_row.windows(2).any(|pair| pair[0].x >= pair[1].x) { return None; }
+		for (row_idx, row) in squares.chunks(col_count).enumerate() {
+			if row_idx > 0 && row[0].y <= squares[(row_idx - 1) * col_count].y { return None; }
+			if row.iter().zip(first_row).any(|(square, top)| square.y != row[0].y || square.x != top.x) { return None; }
+		}//end checking each row
+		Some(col_count)
+	}//end row_major_cols(squares)
+
+	/// Allows immutable iteration through squares, row by row.
 	pub fn iter(&self) -> std::slice::Iter<'_, Square> { self.squares.iter() }
-	/// Allows mutable iteration through squares
+	/// Allows mutable iteration through squares, row by row.
 	pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Square> { self.squares.iter_mut() }
 	/// Width of original image when square info was taken.
 	pub fn img_width(&self) -> &usize { &self.img_width }
@@ -109,16 +229,104 @@ impl SquareGrid {
 	/// Calculated as the number of unique x values of squares.
 	pub fn cols(&self) -> &usize { &self.cols }
 
-	/// Gets a reference to the square at the specified location, assuming valid indices.
-	pub fn get(&self, row: &usize, col: &usize) -> Option<&Square> { 
-		let index = row * self.cols + col;
-		if index < self.squares.len() {
-			Some(&self.squares[index]) } 
-		else { None }}
-	/// Gets a mutable reference to the square at the specified location, assuming valid indices.
+	/// Gets the position in [iter()] of the square at row, col, or None if row or col is out of bounds.
+	pub fn index(&self, row: usize, col: usize) -> Option<usize> {
+		if row < self.rows && col < self.cols { Some(row * self.cols + col) } else { None }
+	}//end index(self, row, col)
+	/// Gets the (row, col) of the square at position index in [iter()], or None if index is out of bounds.
+	pub fn coords(&self, index: usize) -> Option<(usize,usize)> {
+		if index < self.squares.len() { Some((index / self.cols, index % self.cols)) } else { None }
+	}//end coords(self, index)
+
+	/// Gets a reference to the square at the specified location, or None if row or col is out of bounds.
+	pub fn get(&self, row: &usize, col: &usize) -> Option<&Square> {
+		self.index(*row, *col).map(|index| &self.squares[index])
+	}//end get(self, row, col)
+	/// Gets a mutable reference to the square at the specified location, or None if row or col is out of bounds.
 	pub fn get_mut(&mut self, row: &usize, col: &usize) -> Option<&mut Square> {
-		let index = row * self.cols + col;
-		if index < self.squares.len() {
-			Some(&mut self.squares[index])
-		} else {None}}
+		self.index(*row, *col).map(|index| &mut self.squares[index])
+	}//end get_mut(self, row, col)
+
+	/// Gets the (row, col) coords orthogonally adjacent to row, col that are within bounds.  
+	/// They come in the order up, down, left, right.
+	pub fn orthogonal_neighbors(&self, row: usize, col: usize) -> impl Iterator<Item = (usize,usize)> {
+		let (rows, cols) = (self.rows, self.cols);
+		[(-1,0), (1,0), (0,-1), (0,1)].into_iter().filter_map(move |offset| offset_within(row, col, offset, rows, cols))
+	}//end orthogonal_neighbors(self, row, col)
+	/// Gets the (row, col) coords of the up to 8 squares touching row, col, including diagonally, that are within bounds.  
+	/// They come row by row, starting with the row above.
+	pub fn neighbors(&self, row: usize, col: usize) -> impl Iterator<Item = (usize,usize)> {
+		let (rows, cols) = (self.rows, self.cols);
+		[(-1,-1), (-1,0), (-1,1), (0,-1), (0,1), (1,-1), (1,0), (1,1)].into_iter().filter_map(move |offset| offset_within(row, col, offset, rows, cols))
+	}//end neighbors(self, row, col)
 }//end impl for SquareGrid
+
+/// Moves row, col by offset, returning None if that leaves a grid of rows by cols.
+fn offset_within(row: usize, col: usize, offset: (isize,isize), rows: usize, cols: usize) -> Option<(usize,usize)> {
+	let row = row.checked_add_signed(offset.0).filter(|row| *row < rows)?;
+	let col = col.checked_add_signed(offset.1).filter(|col| *col < cols)?;
+	Some((row, col))
+}//end offset_within(row, col, offset, rows, cols)
+
+/// The parts of a [SquareGrid] that get saved.  
+/// Loading goes through [SquareGrid::from_squares()], so saved files get the same bounds checking.
+#[derive(Serialize, Deserialize)]
+struct SavedSquareGrid {
+	squares: Vec<Square>,
+	img_width: usize,
+	img_height: usize,
+}//end struct SavedSquareGrid
+
+impl From<SquareGrid> for SavedSquareGrid {
+	fn from(grid: SquareGrid) -> Self {
+		SavedSquareGrid { squares: grid.squares, img_width: grid.img_width, img_height: grid.img_height }
+	}//end from(grid)
+}//end impl From<SquareGrid> for SavedSquareGrid
+
+impl TryFrom<SavedSquareGrid> for SquareGrid {
+	type Error = String;
+	fn try_from(saved: SavedSquareGrid) -> Result<Self, Self::Error> {
+		SquareGrid::from_squares(saved.squares, saved.img_width, saved.img_height).map_err(|err_info| err_info.1)
+	}//end try_from(saved)
+}//end impl TryFrom<SavedSquareGrid> for SquareGrid
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Squares 2 pixels across for every (row, col) in coords, given in that order.
+	fn squares_at(coords: &[(usize,usize)]) -> Vec<Square> {
+		coords.iter().map(|&(row, col)| Square::new(col * 2, row * 2, 2, 2).with_kind(CellKind::Floor)).collect()
+	}//end squares_at(coords)
+
+	#[test]
+	fn from_squares_sorts_squares_into_rows_and_cols() {
+		let in_order = SquareGrid::from_squares(squares_at(&[(0,0), (0,1), (0,2), (1,0), (1,1), (1,2)]), 6, 4).unwrap();
+		let shuffled = SquareGrid::from_squares(squares_at(&[(1,2), (0,1), (1,0), (0,0), (1,1), (0,2)]), 6, 4).unwrap();
+		assert_eq!(in_order, shuffled);
+		assert_eq!((*in_order.rows(), *in_order.cols()), (2, 3));
+		assert_eq!(in_order.get(&1, &2).map(|square| (square.x, square.y)), Some((4, 2)));
+	}//end from_squares_sorts_squares_into_rows_and_cols()
+
+	#[test]
+	fn from_squares_errs_on_duplicate_squares() {
+		let result = SquareGrid::from_squares(squares_at(&[(0,0), (0,1), (1,0), (1,1), (0,1)]), 4, 4);
+		let (square, _) = result.unwrap_err();
+		assert_eq!((square.x, square.y), (2, 0));
+		assert!(SquareGrid::from_squares(squares_at(&[(0,0), (0,0)]), 2, 2).is_err());
+	}//end from_squares_errs_on_duplicate_squares()
+
+	#[test]
+	fn from_squares_errs_on_holes() {
+		let result = SquareGrid::from_squares(squares_at(&[(0,0), (0,1), (1,0)]), 4, 4);
+		let (square, _) = result.unwrap_err();
+		assert_eq!((square.x, square.y), (2, 2));
+		assert!(SquareGrid::from_squares(squares_at(&[(0,0), (0,1), (1,1), (1,2)]), 6, 4).is_err());
+	}//end from_squares_errs_on_holes()
+
+	#[test]
+	fn from_squares_errs_outside_image() {
+		assert!(SquareGrid::from_squares(squares_at(&[(0,0), (0,1)]), 3, 2).is_err());
+		assert!(SquareGrid::from_squares(squares_at(&[(0,0), (1,0)]), 2, 3).is_err());
+	}//end from_squares_errs_outside_image()
+}//end mod tests
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+
+use crate::squares::{CellKind, SquareGrid};
+
+/// Where a stair square leads.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum StairDirection {
+	/// Leads to a level with a lower index.
+	Up,
+	/// Leads to a level with a higher index.
+	Down,
+	/// Leads out of the map entirely, so it has no target level.
+	Outside,
+}//end enum StairDirection
+
+impl StairDirection {
+	/// The direction a stair at the other end of a link should have.
+	pub fn opposite(&self) -> StairDirection {
+		match self {
+			StairDirection::Up => StairDirection::Down,
+			StairDirection::Down => StairDirection::Up,
+			StairDirection::Outside => StairDirection::Outside,
+		}//end matching direction
+	}//end opposite(self)
+}//end impl for StairDirection
+
+/// Records where the stair square at [row], [col] of a level leads.
+/// [target_level] is the index of the level it leads to, or None for [StairDirection::Outside].
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct StairLink {
+	pub row: usize,
+	pub col: usize,
+	pub direction: StairDirection,
+	pub target_level: Option<usize>,
+}//end struct StairLink
+
+impl StairLink {
+	pub fn new(row: usize, col: usize, direction: StairDirection, target_level: Option<usize>) -> StairLink {
+		StairLink { row, col, direction, target_level }
+	}//end new(row, col, direction, target_level)
+
+	/// Creates a link for a stair leading out of the map.
+	pub fn outside(row: usize, col: usize) -> StairLink {
+		StairLink { row, col, direction: StairDirection::Outside, target_level: None }
+	}//end outside(row, col)
+}//end impl for StairLink
+
+impl std::fmt::Display for StairLink {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match (self.direction, self.target_level) {
+			(StairDirection::Outside, _) => write!(f, "Outside"),
+			(direction, Some(target_level)) => write!(f, "{:?} to Level {}", direction, target_level + 1),
+			(direction, None) => write!(f, "{:?} to no level", direction),
+		}//end matching direction and target
+	}//end fmt
+}//end impl Display for StairLink
+
+/// Something wrong with the stairs of a level, as found by [crate::levels::LevelStack::validate_stairs()].
+/// Levels are indices, so level 0 is shown to users as Level 1.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum StairProblem {
+	/// A stair square with no link, a link on a square that isn't a stair, or a link to a level that doesn't exist.
+	Dangling { level: usize, row: usize, col: usize },
+	/// A link whose target level doesn't have a stair at the same coordinates, or whose direction doesn't match its target.
+	Misaligned { level: usize, row: usize, col: usize, target_level: usize },
+	/// A link whose target stair doesn't link back to it.
+	OneWay { level: usize, row: usize, col: usize, target_level: usize },
+}//end enum StairProblem
+
+impl std::fmt::Display for StairProblem {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			StairProblem::Dangling { level, row, col } => write!(f, "Level {}, Row: {}, Col: {} doesn't lead anywhere.", level + 1, row, col),
+			StairProblem::Misaligned { level, row, col, target_level } => write!(f, "Level {}, Row: {}, Col: {} leads to Level {}, but there isn't a matching stair there.", level + 1, row, col, target_level + 1),
+			StairProblem::OneWay { level, row, col, target_level } => write!(f, "Level {}, Row: {}, Col: {} leads to Level {}, but that stair doesn't lead back.", level + 1, row, col, target_level + 1),
+		}//end matching problem
+	}//end fmt
+}//end impl Display for StairProblem
+
+/// Gets (row, col) coordinates of every stairs square in squares.
+pub fn find_stairs(squares: &SquareGrid) -> Vec<(usize,usize)> {
+	let mut stairs = Vec::new();
+	for row in 0..*squares.rows() {
+		for col in 0..*squares.cols() {
+			if is_stairs(squares, row, col) { stairs.push((row, col)); }
+		}//end looping over cols
+	}//end looping over rows
+	stairs
+}//end find_stairs(squares)
+
+/// Returns true if the square at row, col exists and is stairs.
+pub fn is_stairs(squares: &SquareGrid, row: usize, col: usize) -> bool {
+	match squares.get(&row, &col) {
+		Some(square) => *square.kind() == CellKind::Stairs,
+		None => false,
+	}//end matching whether square exists
+}//end is_stairs(squares, row, col)
@@ -0,0 +1,262 @@
+use rand::{prelude::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{room_growth::CRGC, squares::{SquareGrid, SquareRegion}};
+
+/// The kind of area a zone is, which decides how its rooms are connected.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum ZoneType {
+	/// Well traveled area, whose rooms get every extra door allowed within zones.
+	Public,
+	/// Quieter area, whose rooms get half as many extra doors as public zones.
+	Private,
+	/// Divided part of the level, reached from only one other zone where possible.
+	SubLevel,
+	/// Dead end reached from only one other zone where possible, with no extra doors between its rooms.
+	BossArea,
+}//end enum ZoneType
+
+impl ZoneType {
+	/// Name used for this type in zone lists, such as "public".
+	pub fn name(&self) -> &'static str {
+		match self {
+			ZoneType::Public => "public",
+			ZoneType::Private => "private",
+			ZoneType::SubLevel => "sublevel",
+			ZoneType::BossArea => "boss",
+		}//end matching self
+	}//end name(self)
+
+	/// Gets the type named name, ignoring case. Returns Err if name isn't a zone type.
+	pub fn from_name(name: &str) -> Result<ZoneType, String> {
+		match name.trim().to_lowercase().as_str() {
+			"public" => Ok(ZoneType::Public),
+			"private" => Ok(ZoneType::Private),
+			"sublevel" | "sub-level" => Ok(ZoneType::SubLevel),
+			"boss" | "boss-area" => Ok(ZoneType::BossArea),
+			other => Err(format!("Unrecognized zone type {}. Expected public, private, sublevel, or boss.", other)),
+		}//end matching name
+	}//end from_name(name)
+
+	/// Reads a comma separated list of zone type names, such as "public, private, boss".
+	pub fn parse_list(text: &str) -> Result<Vec<ZoneType>, String> {
+		text.split(',').filter(|name| !name.trim().is_empty()).map(ZoneType::from_name).collect()
+	}//end parse_list(text)
+
+	/// Writes zone_types as a comma separated list which [parse_list()] can read back.
+	pub fn list_names(zone_types: &[ZoneType]) -> String {
+		zone_types.iter().map(|zone_type| zone_type.name()).collect::<Vec<&str>>().join(", ")
+	}//end list_names(zone_types)
+
+	/// Amount [ZoneSettings::doors_within] is multiplied by for zones of this type.
+	pub fn within_door_scale(&self) -> f64 {
+		match self {
+			ZoneType::Public | ZoneType::SubLevel => 1.0,
+			ZoneType::Private => 0.5,
+			ZoneType::BossArea => 0.0,
+		}//end matching self
+	}//end within_door_scale(self)
+
+	/// Whether zones of this type should only be linked to a single other zone.
+	pub fn is_dead_end(&self) -> bool {
+		matches!(self, ZoneType::SubLevel | ZoneType::BossArea)
+	}//end is_dead_end(self)
+}//end impl for ZoneType
+
+/// Parameters for splitting a structure into zones before growing rooms in them.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ZoneSettings {
+	/// One zone is grown for each entry.
+	pub zone_types: Vec<ZoneType>,
+	/// Room starts placed in each zone, or None to pick a number based on the size of each zone.
+	pub rooms_per_zone: Option<usize>,
+	/// Chance that two rooms in the same zone get a door between them, once every room in the zone can already reach the others.
+	pub doors_within: f64,
+	/// Chance that two rooms in different zones get a door between them, once every zone can already reach the others.
+	/// Dead end zones never get these extra doors.
+	pub doors_between: f64,
+}//end struct ZoneSettings
+
+impl Default for ZoneSettings {
+	fn default() -> Self {
+		Self {
+			zone_types: vec![ZoneType::Public, ZoneType::Public, ZoneType::Private, ZoneType::BossArea],
+			rooms_per_zone: None,
+			doors_within: 0.3,
+			doors_between: 0.05,
+		}//end struct construction
+	}//end default()
+}//end impl Default for ZoneSettings
+
+/// Zones grown across a [SquareGrid], and which zone each square is in.
+/// All coordinates are stored as (row, col).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ZoneMap {
+	/// parallel to zones, the type of each zone
+	zone_types: Vec<ZoneType>,
+	/// coords of the squares in each zone
+	zones: Vec<SquareRegion>,
+	/// zone index of each square, in row-major order
+	zone_of_square: Vec<Option<usize>>,
+	rows: usize,
+	cols: usize,
+}//end struct ZoneMap
+
+impl ZoneMap {
+	/// Grows one zone for each entry of zone_types across the open squares of squares.
+	/// Open squares are Empty, RoomStart, Floor, and Stairs squares, so walls drawn on the
+	/// canvas act as the edges of the structure.
+	/// Each zone starts from a random open square. Zones then take turns claiming a random open square
+	/// next to a random square they already have, which gives them organic shapes. Growth stops once
+	/// every open square a zone can reach is claimed.
+	///
+	/// Returns Err if there are fewer open squares than zones, or no zones were asked for.
+	pub fn grow<R: Rng>(squares: &SquareGrid, zone_types: &[ZoneType], rng: &mut R) -> Result<ZoneMap, String> {
+		if zone_types.is_empty() { return Err(String::from("At least one zone type is needed to grow zones.")); }
+		let rows = *squares.rows();
+		let cols = *squares.cols();
+		let open_squares: Vec<(usize,usize)> = (0..rows * cols).map(|idx| (idx / cols, idx % cols))
+			.filter(|(row, col)| ZoneMap::is_open(squares, *row, *col)).collect();
+		if open_squares.len() < zone_types.len() {
+			return Err(format!("Can't grow {} zones, since there are only {} squares where rooms could go.", zone_types.len(), open_squares.len()));
+		}//end if there isn't room for every zone
+
+		let mut zone_of_square = vec![None; rows * cols];
+		let mut zones: Vec<SquareRegion> = Vec::new();
+		// squares in each zone which might still have open squares next to them
+		let mut frontiers: Vec<Vec<(usize,usize)>> = Vec::new();
+		for (zone_index, (row, col)) in open_squares.choose_multiple(rng, zone_types.len()).enumerate() {
+			zone_of_square[row * cols + col] = Some(zone_index);
+			zones.push(vec![(*row, *col)]);
+			frontiers.push(vec![(*row, *col)]);
+		}//end placing the first square of each zone
+
+		while frontiers.iter().any(|frontier| !frontier.is_empty()) {
+			for zone_index in 0..zones.len() {
+				let frontier = &mut frontiers[zone_index];
+				while !frontier.is_empty() {
+					let frontier_idx = rng.gen_range(0..frontier.len());
+					let (row, col) = frontier[frontier_idx];
+					let unclaimed: Vec<(usize,usize)> = squares.orthogonal_neighbors(row, col)
+						.filter(|(row_a, col_a)| zone_of_square[row_a * cols + col_a].is_none() && ZoneMap::is_open(squares, *row_a, *col_a))
+						.collect();
+					match unclaimed.choose(rng) {
+						Some((row_a, col_a)) => {
+							zone_of_square[row_a * cols + col_a] = Some(zone_index);
+							zones[zone_index].push((*row_a, *col_a));
+							frontier.push((*row_a, *col_a));
+							break;
+						}, None => { frontier.swap_remove(frontier_idx); },
+					}//end matching whether this square can still grow
+				}//end looping until this zone claims a square or runs out of room
+			}//end giving each zone a turn
+		}//end looping while zones can still grow
+
+		Ok(ZoneMap { zone_types: zone_types.to_vec(), zones, zone_of_square, rows, cols })
+	}//end grow(squares, zone_types, rng)
+
+	/// Whether the square at row, col is somewhere a zone can grow.
+	fn is_open(squares: &SquareGrid, row: usize, col: usize) -> bool {
+		match squares.get(&row, &col) {
+			Some(square) => matches!(CRGC::from_kind(*square.kind()), CRGC::Empty | CRGC::RoomStart | CRGC::Floor | CRGC::Stairs),
+			None => false,
+		}//end matching whether square exists
+	}//end is_open(squares, row, col)
+
+	/// The type of each zone.
+	pub fn zone_types(&self) -> &Vec<ZoneType> { &self.zone_types }
+	/// The (row, col) coords of squares within each zone.
+	pub fn zones(&self) -> &Vec<SquareRegion> { &self.zones }
+	/// Number of zones.
+	pub fn len(&self) -> usize { self.zones.len() }
+	/// Returns true if there are no zones.
+	pub fn is_empty(&self) -> bool { self.zones.is_empty() }
+	/// Gets the index of the zone containing the square at row, col, if any.
+	pub fn zone_at(&self, row: usize, col: usize) -> Option<usize> {
+		if row < self.rows && col < self.cols { self.zone_of_square[row * self.cols + col] } else { None }
+	}//end zone_at(self, row, col)
+	/// Whether the square at row, col and every square touching it, including diagonally, are in the same zone.  
+	/// Squares past the edges of the grid are ignored.
+	pub fn is_interior(&self, row: usize, col: usize) -> bool {
+		let zone = match self.zone_at(row, col) { Some(zone) => zone, None => return false };
+		(row.saturating_sub(1)..=(row + 1).min(self.rows - 1)).all(|row_a| {
+			(col.saturating_sub(1)..=(col + 1).min(self.cols - 1)).all(|col_a| self.zone_at(row_a, col_a) == Some(zone))
+		})
+	}//end is_interior(self, row, col)
+	/// Gets the type of the zone at zone_index.
+	pub fn zone_type(&self, zone_index: usize) -> Option<ZoneType> { self.zone_types.get(zone_index).copied() }
+}//end impl for ZoneMap
+
+#[cfg(test)]
+mod tests {
+	use rand::{rngs::StdRng, SeedableRng};
+
+	use super::*;
+	use crate::export::{text::{from_text, TextCharMap}, MapKind};
+
+	/// Builds room squares from a text map, using the default room characters.
+	fn room_squares(text: &str) -> SquareGrid {
+		from_text(text, MapKind::Room, &TextCharMap::default(), 1).unwrap()
+	}//end room_squares(text)
+
+	#[test]
+	fn grow_claims_every_open_square_zones_can_reach() {
+		// the pocket in the lower right is sealed off, so it only gets a zone if one starts there
+		let squares = room_squares(&[
+			"      #     ",
+			"  ..  #     ",
+			"      #     ",
+			"      ######",
+			"        #   ",
+			"        #   ",
+		].join("\n"));
+		let zone_types = [ZoneType::Public, ZoneType::Private, ZoneType::BossArea];
+		for seed in 0..20 {
+			let zones = ZoneMap::grow(&squares, &zone_types, &mut StdRng::seed_from_u64(seed)).unwrap();
+			assert_eq!(zones.len(), 3);
+			assert_eq!(zones.zones().iter().map(|zone| zone.len()).sum::<usize>(), (0..6 * 12).filter(|idx| zones.zone_at(idx / 12, idx % 12).is_some()).count());
+
+			// flood out from the first square of each zone to find every open square a zone could reach
+			let mut reachable: Vec<(usize,usize)> = zones.zones().iter().map(|zone| zone[0]).collect();
+			let mut idx = 0;
+			while idx < reachable.len() {
+				let (row, col) = reachable[idx];
+				for neighbor in squares.orthogonal_neighbors(row, col) {
+					if ZoneMap::is_open(&squares, neighbor.0, neighbor.1) && !reachable.contains(&neighbor) { reachable.push(neighbor); }
+				}//end looping over neighbors
+				idx += 1;
+			}//end flooding out from zone starts
+
+			for row in 0..6 {
+				for col in 0..12 {
+					let zone = zones.zone_at(row, col);
+					assert_eq!(zone.is_some(), reachable.contains(&(row, col)), "square {},{} with seed {}", row, col, seed);
+					if let Some(zone) = zone { assert!(zones.zones()[zone].contains(&(row, col))); }
+				}//end looping over cols
+			}//end looping over rows
+		}//end looping over seeds
+	}//end grow_claims_every_open_square_zones_can_reach()
+
+	#[test]
+	fn grow_needs_room_for_every_zone() {
+		let squares = room_squares("#.#\n###");
+		let mut rng = StdRng::seed_from_u64(0);
+		assert!(ZoneMap::grow(&squares, &[], &mut rng).is_err());
+		assert!(ZoneMap::grow(&squares, &[ZoneType::Public, ZoneType::Private], &mut rng).is_err());
+		assert!(ZoneMap::grow(&squares, &[ZoneType::Public], &mut rng).is_ok());
+	}//end grow_needs_room_for_every_zone()
+
+	#[test]
+	fn interior_squares_are_surrounded_by_their_zone() {
+		let squares = room_squares(".....\n..#..\n.....\n.....");
+		let zones = ZoneMap::grow(&squares, &[ZoneType::Public], &mut StdRng::seed_from_u64(0)).unwrap();
+		assert!(zones.is_interior(0, 0));
+		assert!(zones.is_interior(3, 4));
+		assert!(zones.is_interior(3, 2));
+		assert!(!zones.is_interior(0, 1));
+		assert!(!zones.is_interior(2, 3));
+		assert!(!zones.is_interior(1, 2));
+		assert!(!zones.is_interior(4, 0));
+	}//end interior_squares_are_surrounded_by_their_zone()
+}//end mod tests